@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use std::cell::Cell;
 use std::collections::HashMap;
 
 /// 디버그 로그 출력 (WASM 환경에서는 JS console.log로 전달)
@@ -14,19 +15,19 @@ extern "C" {
 }
 
 #[cfg(target_arch = "wasm32")]
-fn log_debug(msg: &str) {
+pub fn log_debug(msg: &str) {
     unsafe {
         log(msg);
     }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn log_debug(msg: &str) {
+pub fn log_debug(msg: &str) {
     println!("DEBUG: {}", msg);
 }
 
 /// 행마법 종류
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MoveType {
     TakeMove, // 이동 또는 잡기
     Move,     // 이동만 (빈 칸만)
@@ -41,6 +42,8 @@ pub enum MoveType {
 pub enum ActionTagType {
     Transition, // 기물 변환
     SetState,   // 상태 설정
+    AddState,   // 상태 증가
+    SubState,   // 상태 감소
 }
 
 /// 활성화된 칸에 부착되는 액션 태그
@@ -60,8 +63,101 @@ pub struct Activation {
     pub move_type: MoveType,  // 행마법 종류
     pub tags: Vec<ActionTag>, // 부착된 액션 태그들
     pub catch_to: Option<(i32, i32)>, //jump행마용 기물 잡는 곳 저장소
+    pub advance_to: Option<(i32, i32)>, // catch-advance: 잡은 뒤 전진할 칸 (기물 위치 기준)
 }
 
+/// 실행 추적 항목 (토큰 하나를 실행하기 직전의 상태)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub pc: usize,          // 토큰 인덱스
+    pub token: String,      // 토큰 표기 (Debug 출력)
+    pub anchor_x: i32,      // 실행 직전 앵커
+    pub anchor_y: i32,
+    pub last_value: bool,   // 실행 직전 마지막 식의 값
+}
+
+/// 스크립트 안의 위치 (1부터 시작하는 줄/열, 열은 문자 단위)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// 파싱 중 `end`로 대체된 알 수 없는 식 이름 (`Interpreter::unknown_words`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownWord {
+    pub word: String,
+    pub span: Span,
+}
+
+/// 스크립트 파싱 오류 (`Interpreter::validate`로 확인)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// 알 수 없는 식 이름 (`end`로 대체됨)
+    UnknownKeyword { word: String, span: Span },
+    /// 인자 개수가 맞지 않음
+    WrongArgCount { word: String, expected: &'static [usize], found: usize, span: Span },
+    /// `and`/`or`의 피연산자가 조건식이 아님 (`end`로 대체됨)
+    NotACondition { word: String, span: Span },
+    /// 짝이 없는 중괄호 (같은 식 연쇄 안에서 짝을 찾음)
+    UnmatchedBrace { span: Span },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownKeyword { word, span } => {
+                write!(f, "{}:{}: 알 수 없는 식입니다: {}", span.line, span.column, word)
+            }
+            ParseError::WrongArgCount { word, expected, found, span } => {
+                let expected: Vec<String> = expected.iter().map(usize::to_string).collect();
+                write!(f, "{}:{}: {}의 인자 개수가 맞지 않습니다 ({}개 필요, {}개 받음)",
+                    span.line, span.column, word, expected.join(" 또는 "), found)
+            }
+            ParseError::NotACondition { word, span } => {
+                write!(f, "{}:{}: and/or의 피연산자가 조건식이 아닙니다: {}", span.line, span.column, word)
+            }
+            ParseError::UnmatchedBrace { span } => {
+                write!(f, "{}:{}: 짝이 없는 중괄호입니다", span.line, span.column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 실행 통계 (프로파일링용)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExecStats {
+    pub tokens_executed: usize, // 실행한 토큰 수 (체인 종료로 건너뛴 토큰 제외)
+    pub activations: usize,     // 만들어진 활성화 수
+    pub loop_iterations: usize, // repeat/while로 되돌아간 횟수
+}
+
+/// 스크립트 실행 오류
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChessemblyError {
+    /// jmp/jne가 가리키는 라벨이 현재 식 연쇄에 없음
+    UndefinedLabel { name: String, chain: usize },
+    /// 실행한 토큰 수가 `max_steps`를 넘음 (무한 루프 방지)
+    StepLimitExceeded { max_steps: usize },
+}
+
+impl std::fmt::Display for ChessemblyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChessemblyError::UndefinedLabel { name, chain } => {
+                write!(f, "정의되지 않은 라벨입니다: {} ({}번째 식 연쇄)", name, chain)
+            }
+            ChessemblyError::StepLimitExceeded { max_steps } => {
+                write!(f, "실행 단계가 제한({})을 넘었습니다", max_steps)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChessemblyError {}
+
 /// 보드 상태 (외부에서 제공)
 pub struct BoardState {
     pub board_width: i32,
@@ -81,6 +177,46 @@ pub struct BoardState {
 }
 
 impl BoardState {
+    /// 빈 보드 생성 (기물/상태/위협 칸 없음, 체크 아님)
+    pub fn new(board_width: i32, board_height: i32, piece_x: i32, piece_y: i32, piece_name: impl Into<String>, is_white: bool) -> Self {
+        Self {
+            board_width,
+            board_height,
+            piece_x,
+            piece_y,
+            piece_name: piece_name.into(),
+            is_white,
+            pieces: HashMap::new(),
+            state: HashMap::new(),
+            danger_squares: std::collections::HashSet::new(),
+            in_check: false,
+        }
+    }
+    
+    /// 기물 추가
+    pub fn with_piece(mut self, x: i32, y: i32, piece_name: impl Into<String>, is_white: bool) -> Self {
+        self.pieces.insert((x, y), (piece_name.into(), is_white));
+        self
+    }
+    
+    /// 전역 상태 값 설정
+    pub fn with_state(mut self, key: impl Into<String>, value: i32) -> Self {
+        self.state.insert(key.into(), value);
+        self
+    }
+    
+    /// 위협받는 칸 추가
+    pub fn with_danger_square(mut self, x: i32, y: i32) -> Self {
+        self.danger_squares.insert((x, y));
+        self
+    }
+    
+    /// 체크 상태 설정
+    pub fn with_check(mut self, in_check: bool) -> Self {
+        self.in_check = in_check;
+        self
+    }
+    
     /// 해당 좌표가 보드 안인지
     fn in_bounds(&self, x: i32, y: i32) -> bool {
         x >= 0 && x < self.board_width && y >= 0 && y < self.board_height
@@ -127,12 +263,20 @@ enum Token {
     Move(i32, i32),
     Take(i32, i32),
     Catch(i32, i32),
+    /// (cdx, cdy)의 적을 제자리에서 잡은 뒤 (mdx, mdy)로 전진
+    CatchAdvance(i32, i32, i32, i32),
     Shift(i32, i32),
     Jump(i32, i32),
+    /// (dx, dy) 방향의 첫 기물(허들)을 넘어 바로 뒤 칸에 착지 (빈 칸 또는 적, 그래스호퍼)
+    Hop(i32, i32),
+    /// 첫 기물이 적이고 바로 뒤 칸이 비어 있으면 그 적을 잡으며 착지 (캐논, 이어지는 jump로 더 멀리 착지)
+    HopCatch(i32, i32),
+    /// 활성화 없이 기준 위치만 (dx, dy) 이동. 옮긴 위치가 보드 밖이면 false (체인 종료)
     Anchor(i32, i32),
     
     // 조건식
     Observe(i32, i32),
+    /// 빈 칸이면 true. 보드 안이면 기물이 있어도 앵커를 그 칸으로 옮김 (그래스호퍼의 착지 기준)
     Peek(i32, i32),
     Enemy(i32, i32),
     Friendly(i32, i32),
@@ -154,8 +298,14 @@ enum Token {
     // 상태 관련
     Piece(String),
     IfState(String, i32),
+    StateGt(String, i32),
+    StateLt(String, i32),
+    StateGte(String, i32),
+    StateLte(String, i32),
     SetState(String, i32),
     SetStateReset,
+    AddState(String, i32),
+    SubState(String, i32),
     Transition(String),
     
     // 제어
@@ -166,6 +316,9 @@ enum Token {
     Jne(String),
     Label(String),
     Not,
+    /// 인자가 없으면 직전 값과 다음 조건식을 결합, 있으면 피연산자 조건식들을 결합
+    And(Vec<Token>),
+    Or(Vec<Token>),
     End,
     
     // 구조
@@ -174,20 +327,65 @@ enum Token {
     Semicolon,
 }
 
+impl Token {
+    /// 앵커를 옮기지 않고 값만 계산할 수 있는 조건식인지 여부 (`and`/`or`의 피연산자)
+    fn is_condition(&self) -> bool {
+        match self {
+            Token::Observe(..) | Token::Peek(..) | Token::Enemy(..) | Token::Friendly(..) |
+            Token::PieceOn(..) | Token::Danger(..) | Token::Check | Token::Bound(..) |
+            Token::Edge(..) | Token::EdgeTop(..) | Token::EdgeBottom(..) | Token::EdgeLeft(..) |
+            Token::EdgeRight(..) | Token::Corner(..) | Token::CornerTopLeft(..) |
+            Token::CornerTopRight(..) | Token::CornerBottomLeft(..) | Token::CornerBottomRight(..) |
+            Token::Piece(_) | Token::IfState(..) | Token::StateGt(..) | Token::StateLt(..) |
+            Token::StateGte(..) | Token::StateLte(..) => true,
+            Token::And(operands) | Token::Or(operands) => !operands.is_empty(),
+            _ => false,
+        }
+    }
+}
+
 /// 렉서
 struct Lexer<'a> {
     input: &'a str,
     pos: usize,
+    line: usize,       // `scanned`까지 센 줄 번호
+    line_start: usize, // 현재 줄의 시작 위치
+    scanned: usize,    // 줄/열 계산을 마친 위치
+    token_start: Span, // 현재 토큰의 시작 위치
+    errors: Vec<ParseError>,
 }
 
 impl<'a> Lexer<'a> {
     fn new(input: &'a str) -> Self {
-        Self { input, pos: 0 }
+        Self {
+            input,
+            pos: 0,
+            line: 1,
+            line_start: 0,
+            scanned: 0,
+            token_start: Span::default(),
+            errors: Vec::new(),
+        }
+    }
+    
+    /// 현재 위치의 줄/열 (위치는 앞으로만 움직이므로 이어서 계산)
+    fn span(&mut self) -> Span {
+        for (i, byte) in self.input.as_bytes()[self.scanned..self.pos].iter().enumerate() {
+            if *byte == b'\n' {
+                self.line += 1;
+                self.line_start = self.scanned + i + 1;
+            }
+        }
+        self.scanned = self.pos;
+        Span {
+            line: self.line,
+            column: self.input[self.line_start..self.pos].chars().count() + 1,
+        }
     }
     
     fn skip_whitespace(&mut self) {
         let bytes = self.input.as_bytes();
-        while self.pos < bytes.len() && (bytes[self.pos] as char).is_whitespace() {
+        while self.pos < bytes.len() && bytes[self.pos].is_ascii_whitespace() {
             self.pos += 1;
         }
     }
@@ -198,15 +396,22 @@ impl<'a> Lexer<'a> {
             while self.pos < bytes.len() && bytes[self.pos] != b'\n' {
                 self.pos += 1;
             }
+        } else if bytes[self.pos..].starts_with(b"/*") {
+            // 블록 주석: 닫히지 않았으면 입력 끝까지 주석으로 처리
+            self.pos = match self.input[self.pos + 2..].find("*/") {
+                Some(end) => self.pos + 2 + end + 2,
+                None => bytes.len(),
+            };
         }
     }
     
+    /// 구분자는 모두 ASCII라 바이트 단위로 비교 (한글 같은 여러 바이트 문자의 중간에서 끊지 않도록)
     fn read_word(&mut self) -> String {
         let bytes = self.input.as_bytes();
         let start = self.pos;
         while self.pos < bytes.len() {
-            let ch = bytes[self.pos] as char;
-            if ch.is_whitespace() || ";{}(),#".contains(ch) {
+            let byte = bytes[self.pos];
+            if byte.is_ascii_whitespace() || b";{}(),#".contains(&byte) || bytes[self.pos..].starts_with(b"/*") {
                 break;
             }
             self.pos += 1;
@@ -224,44 +429,42 @@ impl<'a> Lexer<'a> {
         }
         self.pos += 1; // consume '('
         
-        let mut current = String::new();
+        // 인자는 원문에서 잘라냄 (구분자가 ASCII라 자르는 위치는 항상 문자 경계)
+        let mut current = self.pos;
         let mut depth = 0;
         
         while self.pos < bytes.len() {
-            let ch = bytes[self.pos] as char;
+            let byte = bytes[self.pos];
             self.pos += 1;
             
-            match ch {
-                '(' => {
-                    depth += 1;
-                    current.push(ch);
-                }
-                ')' => {
+            match byte {
+                b'(' => depth += 1,
+                b')' => {
                     if depth == 0 {
-                        let trimmed = current.trim().to_string();
+                        let trimmed = self.input[current..self.pos - 1].trim();
                         if !trimmed.is_empty() {
-                            args.push(trimmed);
+                            args.push(trimmed.to_string());
                         }
                         break;
                     }
                     depth -= 1;
-                    current.push(ch);
                 }
-                ',' if depth == 0 => {
-                    let trimmed = current.trim().to_string();
+                b',' if depth == 0 => {
+                    let trimmed = self.input[current..self.pos - 1].trim();
                     if !trimmed.is_empty() {
-                        args.push(trimmed);
+                        args.push(trimmed.to_string());
                     }
-                    current.clear();
+                    current = self.pos;
                 }
-                _ => current.push(ch),
+                _ => {}
             }
         }
         
         args
     }
     
-    fn next_token(&mut self) -> Option<Token> {
+    /// 다음 토큰과 그 시작 위치
+    fn next_token(&mut self) -> Option<(Token, Span)> {
         loop {
             self.skip_whitespace();
             self.skip_comment();
@@ -273,13 +476,16 @@ impl<'a> Lexer<'a> {
             }
             
             let ch = bytes[self.pos] as char;
+            self.token_start = self.span();
+            let span = self.token_start;
             
             // 단일 문자 토큰
             match ch {
-                ';' => { self.pos += 1; return Some(Token::Semicolon); }
-                '{' => { self.pos += 1; return Some(Token::OpenBrace); }
-                '}' => { self.pos += 1; return Some(Token::CloseBrace); }
+                ';' => { self.pos += 1; return Some((Token::Semicolon, span)); }
+                '{' => { self.pos += 1; return Some((Token::OpenBrace, span)); }
+                '}' => { self.pos += 1; return Some((Token::CloseBrace, span)); }
                 '#' => { self.skip_comment(); continue; }
+                '/' if bytes[self.pos..].starts_with(b"/*") => { self.skip_comment(); continue; }
                 _ => {}
             }
             
@@ -292,11 +498,21 @@ impl<'a> Lexer<'a> {
             
             let args = self.read_args();
             
-            return Some(self.parse_token(&word, args));
+            return Some((self.parse_token(&word, args), span));
         }
     }
     
-    fn parse_token(&self, word: &str, args: Vec<String>) -> Token {
+    fn parse_token(&mut self, word: &str, args: Vec<String>) -> Token {
+        if let Some(expected) = arg_counts(word) {
+            if !expected.contains(&args.len()) {
+                self.errors.push(ParseError::WrongArgCount {
+                    word: word.to_string(),
+                    expected,
+                    found: args.len(),
+                    span: self.token_start,
+                });
+            }
+        }
         let parse_i32 = |s: &str| s.parse::<i32>().unwrap_or(0);
         let get_xy = |args: &Vec<String>| -> (i32, i32) {
             if args.len() >= 2 {
@@ -312,8 +528,17 @@ impl<'a> Lexer<'a> {
             "move" => { let (dx, dy) = get_xy(&args); Token::Move(dx, dy) }
             "take" => { let (dx, dy) = get_xy(&args); Token::Take(dx, dy) }
             "catch" => { let (dx, dy) = get_xy(&args); Token::Catch(dx, dy) }
+            "catch-advance" => {
+                if args.len() >= 4 {
+                    Token::CatchAdvance(parse_i32(&args[0]), parse_i32(&args[1]), parse_i32(&args[2]), parse_i32(&args[3]))
+                } else {
+                    Token::End
+                }
+            }
             "shift" => { let (dx, dy) = get_xy(&args); Token::Shift(dx, dy) }
             "jump" => { let (dx, dy) = get_xy(&args); Token::Jump(dx, dy) }
+            "hop" => { let (dx, dy) = get_xy(&args); Token::Hop(dx, dy) }
+            "hop-catch" => { let (dx, dy) = get_xy(&args); Token::HopCatch(dx, dy) }
             "anchor" => { let (dx, dy) = get_xy(&args); Token::Anchor(dx, dy) }
             
             // 조건식
@@ -344,7 +569,7 @@ impl<'a> Lexer<'a> {
             
             // 상태
             "piece" => {
-                if args.len() >= 1 {
+                if !args.is_empty() {
                     Token::Piece(args[0].clone())
                 } else {
                     Token::End
@@ -357,6 +582,19 @@ impl<'a> Lexer<'a> {
                     Token::End
                 }
             }
+            "state-gt" | "state-lt" | "state-gte" | "state-lte" => {
+                if args.len() >= 2 {
+                    let (key, n) = (args[0].clone(), parse_i32(&args[1]));
+                    match word {
+                        "state-gt" => Token::StateGt(key, n),
+                        "state-lt" => Token::StateLt(key, n),
+                        "state-gte" => Token::StateGte(key, n),
+                        _ => Token::StateLte(key, n),
+                    }
+                } else {
+                    Token::End
+                }
+            }
             "set-state" => {
                 if args.len() >= 2 {
                     Token::SetState(args[0].clone(), parse_i32(&args[1]))
@@ -364,8 +602,20 @@ impl<'a> Lexer<'a> {
                     Token::SetStateReset
                 }
             }
+            "add-state" | "sub-state" => {
+                if args.len() >= 2 {
+                    let (key, value) = (args[0].clone(), parse_i32(&args[1]));
+                    if word == "add-state" {
+                        Token::AddState(key, value)
+                    } else {
+                        Token::SubState(key, value)
+                    }
+                } else {
+                    Token::End
+                }
+            }
             "transition" => {
-                if args.len() >= 1 {
+                if !args.is_empty() {
                     Token::Transition(args[0].clone())
                 } else {
                     Token::End
@@ -374,7 +624,7 @@ impl<'a> Lexer<'a> {
             
             // 제어
             "repeat" => {
-                if args.len() >= 1 {
+                if !args.is_empty() {
                     Token::Repeat(args[0].parse().unwrap_or(1))
                 } else {
                     Token::Repeat(1)
@@ -383,38 +633,226 @@ impl<'a> Lexer<'a> {
             "do" => Token::Do,
             "while" => Token::While,
             "jmp" => {
-                if args.len() >= 1 {
+                if !args.is_empty() {
                     Token::Jmp(args[0].clone())
                 } else {
                     Token::End
                 }
             }
             "jne" => {
-                if args.len() >= 1 {
+                if !args.is_empty() {
                     Token::Jne(args[0].clone())
                 } else {
                     Token::End
                 }
             }
             "label" => {
-                if args.len() >= 1 {
+                if !args.is_empty() {
                     Token::Label(args[0].clone())
                 } else {
                     Token::End
                 }
             }
             "not" => Token::Not,
+            "and" | "or" => {
+                let mut operands = Vec::new();
+                let span = self.token_start;
+                for arg in &args {
+                    let mut lexer = Lexer::new(arg);
+                    let operand = lexer.next_token().map(|(token, _)| token);
+                    // 피연산자 안의 오류는 바깥 식의 위치로 기록
+                    let nested = lexer.errors.len();
+                    self.errors.extend(lexer.errors.into_iter().map(|error| error.with_span(span)));
+                    match operand {
+                        Some(token) if token.is_condition() => operands.push(token),
+                        Some(Token::End) if nested > 0 => {}
+                        _ => self.errors.push(ParseError::NotACondition { word: arg.clone(), span }),
+                    }
+                }
+                if operands.len() != args.len() {
+                    Token::End
+                } else if word == "and" {
+                    Token::And(operands)
+                } else {
+                    Token::Or(operands)
+                }
+            }
             "end" => Token::End,
             
-            _ => Token::End, // 알 수 없는 토큰은 end로 처리
+            _ => {
+                // 알 수 없는 토큰은 end로 처리하고 위치를 기록
+                self.errors.push(ParseError::UnknownKeyword { word: word.to_string(), span: self.token_start });
+                Token::End
+            }
         }
     }
 }
 
+impl ParseError {
+    fn with_span(self, span: Span) -> Self {
+        match self {
+            ParseError::UnknownKeyword { word, .. } => ParseError::UnknownKeyword { word, span },
+            ParseError::WrongArgCount { word, expected, found, .. } => ParseError::WrongArgCount { word, expected, found, span },
+            ParseError::NotACondition { word, .. } => ParseError::NotACondition { word, span },
+            ParseError::UnmatchedBrace { .. } => ParseError::UnmatchedBrace { span },
+        }
+    }
+}
+
+/// 식 이름별로 허용되는 인자 개수 (알 수 없는 식과 가변 인자인 and/or는 `None`)
+fn arg_counts(word: &str) -> Option<&'static [usize]> {
+    match word {
+        "take-move" | "move" | "take" | "catch" | "shift" | "jump" | "hop" | "hop-catch" | "anchor" |
+        "observe" | "peek" | "enemy" | "friendly" | "danger" | "bound" | "edge" |
+        "edge-top" | "edge-bottom" | "edge-left" | "edge-right" | "corner" |
+        "corner-top-left" | "corner-top-right" | "corner-bottom-left" | "corner-bottom-right" |
+        "if-state" | "state-gt" | "state-lt" | "state-gte" | "state-lte" |
+        "add-state" | "sub-state" => Some(&[2]),
+        "catch-advance" => Some(&[4]),
+        "piece-on" => Some(&[3]),
+        "piece" | "transition" | "jmp" | "jne" | "label" => Some(&[1]),
+        "set-state" => Some(&[0, 2]),
+        "repeat" => Some(&[0, 1]),
+        "check" | "do" | "while" | "not" | "end" => Some(&[0]),
+        _ => None,
+    }
+}
+
+/// 백 기준 스크립트를 흑 기준으로 뒤집기 (y 인자 부호 반전, 위/아래 변·모서리 교환)
+///
+/// 식 이름과 인자 외의 공백/주석은 그대로 유지합니다.
+pub fn mirror_script(white_script: &str) -> String {
+    let mut out = String::with_capacity(white_script.len());
+    let mut rest = white_script;
+    
+    while let Some(ch) = rest.chars().next() {
+        if ch == '#' {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if rest.starts_with("/*") {
+            let end = rest[2..].find("*/").map_or(rest.len(), |end| end + 4);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if ch.is_whitespace() || ";{}(),".contains(ch) {
+            out.push(ch);
+            rest = &rest[ch.len_utf8()..];
+            continue;
+        }
+        
+        let word_end = rest.find(|c: char| c.is_whitespace() || ";{}(),#".contains(c)).unwrap_or(rest.len())
+            .min(rest.find("/*").unwrap_or(rest.len()));
+        let word = &rest[..word_end];
+        rest = &rest[word_end..];
+        out.push_str(mirror_word(word));
+        
+        // 인자 목록 (공백 뒤의 괄호까지)
+        let after_ws = rest.trim_start();
+        if let Some((args, close)) = after_ws.strip_prefix('(').and_then(|args| Some((args, matching_paren(args)?))) {
+            out.push_str(&rest[..rest.len() - after_ws.len()]);
+            let flipped = y_arg_indices(word);
+            let mirrored: Vec<String> = split_top_level(&args[..close])
+                .into_iter()
+                .map(str::trim)
+                .enumerate()
+                .map(|(i, arg)| match arg.parse::<i32>() {
+                    Ok(n) if flipped.contains(&i) => (-n).to_string(),
+                    // and/or의 피연산자는 그 자체로 식이므로 재귀적으로 뒤집음
+                    _ if matches!(word, "and" | "or") => mirror_script(arg),
+                    _ => arg.to_string(),
+                })
+                .collect();
+            out.push('(');
+            out.push_str(&mirrored.join(", "));
+            out.push(')');
+            rest = &args[close + 1..];
+        }
+    }
+    
+    out
+}
+
+/// 여는 괄호 바로 뒤의 문자열에서 짝이 되는 닫는 괄호 위치
+fn matching_paren(args: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, ch) in args.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 중첩 괄호 안의 쉼표는 무시하고 인자 분리
+fn split_top_level(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, ch) in args.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&args[start..]);
+    parts
+}
+
+/// 위/아래 방향이 있는 식 이름 교환
+fn mirror_word(word: &str) -> &str {
+    match word {
+        "edge-top" => "edge-bottom",
+        "edge-bottom" => "edge-top",
+        "corner-top-left" => "corner-bottom-left",
+        "corner-bottom-left" => "corner-top-left",
+        "corner-top-right" => "corner-bottom-right",
+        "corner-bottom-right" => "corner-top-right",
+        _ => word,
+    }
+}
+
+/// 식 이름별 y 좌표 인자의 위치
+fn y_arg_indices(word: &str) -> &'static [usize] {
+    match word {
+        "piece-on" => &[2],
+        "catch-advance" => &[1, 3],
+        "take-move" | "move" | "take" | "catch" | "shift" | "jump" | "hop" | "hop-catch" | "anchor" |
+        "observe" | "peek" | "enemy" | "friendly" | "danger" | "bound" | "edge" |
+        "edge-top" | "edge-bottom" | "edge-left" | "edge-right" | "corner" |
+        "corner-top-left" | "corner-top-right" | "corner-bottom-left" | "corner-bottom-right" => &[1],
+        _ => &[],
+    }
+}
+
 /// 인터프리터
 pub struct Interpreter {
     tokens: Vec<Token>,
+    spans: Vec<Span>, // 토큰별 스크립트 위치
+    parse_errors: Vec<ParseError>, // 마지막 parse에서 발견한 오류
     pub debug: bool,  // 디버그 모드 활성화 여부
+    max_steps: usize, // 한 번의 실행에서 허용하는 최대 토큰 실행 수
+    last_stats: Cell<ExecStats>, // 마지막으로 성공한 실행의 통계
+}
+
+/// 기본 최대 실행 단계 수
+pub const DEFAULT_MAX_STEPS: usize = 100_000;
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
@@ -422,9 +860,23 @@ impl Interpreter {
         Interpreter {
             debug: false,
             tokens: Vec::new(),
+            spans: Vec::new(),
+            parse_errors: Vec::new(),
+            max_steps: DEFAULT_MAX_STEPS,
+            last_stats: Cell::new(ExecStats::default()),
         }
     }
     
+    /// 마지막으로 성공한 `execute`의 실행 통계
+    pub fn last_stats(&self) -> ExecStats {
+        self.last_stats.get()
+    }
+    
+    /// 최대 실행 단계 수 설정 (넘으면 `StepLimitExceeded` 오류)
+    pub fn set_max_steps(&mut self, n: usize) {
+        self.max_steps = n;
+    }
+    
     /// 디버그 모드 설정
     pub fn set_debug(&mut self, enabled: bool) {
         self.debug = enabled;
@@ -439,17 +891,96 @@ impl Interpreter {
         activations.push(activation);
     }
     
+    /// 보드 상태와 무관한 고정 행마 오프셋 (미리보기용)
+    ///
+    /// 기물이 없는 31x31 보드의 중앙(가장 큰 16x16 보드의 최대 이동 거리 15칸을 모두 담는 크기)에서
+    /// 실행한 결과를 반환합니다. 기물 배치, 전역 상태, 위치(가장자리)에 따라 결과가 달라지는
+    /// 토큰이 하나라도 있으면 `None`을 반환합니다.
+    pub fn static_offsets(&self) -> Option<Vec<(i32, i32, MoveType)>> {
+        let depends_on_board = self.tokens.iter().any(|token| token.is_condition() || matches!(token,
+            Token::Take(..) | Token::Catch(..) | Token::CatchAdvance(..) | Token::Shift(..) | Token::Jump(..) |
+            Token::Hop(..) | Token::HopCatch(..)
+        ));
+        if depends_on_board {
+            return None;
+        }
+        
+        let mut board = BoardState::new(31, 31, 15, 15, "", true);
+        let activations = self.execute(&mut board).ok()?;
+        Some(activations.into_iter().map(|a| (a.dx, a.dy, a.move_type)).collect())
+    }
+    
     /// 스크립트 파싱
     pub fn parse(&mut self, input: &str) {
         let mut lexer = Lexer::new(input);
         self.tokens.clear();
-        while let Some(token) = lexer.next_token() {
+        self.spans.clear();
+        // 식 연쇄 안에서 아직 닫히지 않은 여는 괄호 위치
+        let mut open_braces: Vec<Span> = Vec::new();
+        while let Some((token, span)) = lexer.next_token() {
+            match token {
+                Token::OpenBrace => open_braces.push(span),
+                Token::CloseBrace if open_braces.pop().is_none() => {
+                    lexer.errors.push(ParseError::UnmatchedBrace { span });
+                }
+                Token::Semicolon => {
+                    lexer.errors.extend(open_braces.drain(..).map(|span| ParseError::UnmatchedBrace { span }));
+                }
+                _ => {}
+            }
             self.tokens.push(token);
+            self.spans.push(span);
+        }
+        lexer.errors.extend(open_braces.into_iter().map(|span| ParseError::UnmatchedBrace { span }));
+        self.parse_errors = lexer.errors;
+        if self.debug {
+            for error in &self.parse_errors {
+                log_debug(&format!("[Chessembly] {}", error));
+            }
+        }
+    }
+    
+    /// 마지막 `parse`의 오류 검사 (알 수 없는 식, 인자 개수, 조건식이 아닌 and/or 피연산자, 짝 없는 중괄호)
+    ///
+    /// 오류가 있어도 `parse`는 해당 식을 `end`로 대체해 실행할 수 있는 상태로 둡니다.
+    pub fn validate(&self) -> Result<(), Vec<ParseError>> {
+        if self.parse_errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.parse_errors.clone())
         }
     }
     
+    /// 마지막 `parse`에서 알 수 없어 `end`로 대체된 식들 (`validate` 오류 중 `UnknownKeyword`만 추림)
+    #[deprecated(note = "validate의 ParseError::UnknownKeyword를 사용하세요")]
+    pub fn unknown_words(&self) -> Vec<UnknownWord> {
+        self.parse_errors.iter()
+            .filter_map(|error| match error {
+                ParseError::UnknownKeyword { word, span } => Some(UnknownWord { word: word.clone(), span: *span }),
+                _ => None,
+            })
+            .collect()
+    }
+    
+    /// 마지막으로 파싱한 토큰들의 스크립트 위치 (토큰 인덱스 = `TraceStep::pc`)
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    
     /// 행마법 계산 실행
-    pub fn execute(&self, board: &mut BoardState) -> Vec<Activation> {
+    pub fn execute(&self, board: &mut BoardState) -> Result<Vec<Activation>, ChessemblyError> {
+        self.run(board, None)
+    }
+    
+    /// 행마법 계산 실행 + 토큰 단위 실행 추적 (스크립트 디버거용)
+    pub fn execute_traced(&self, board: &mut BoardState) -> Result<(Vec<Activation>, Vec<TraceStep>), ChessemblyError> {
+        let mut trace = Vec::new();
+        let activations = self.run(board, Some(&mut trace))?;
+        Ok((activations, trace))
+    }
+    
+    fn run(&self, board: &mut BoardState, mut trace: Option<&mut Vec<TraceStep>>) -> Result<Vec<Activation>, ChessemblyError> {
         if self.debug {
             log_debug(&format!("[Chessembly] Executing script for {} at ({}, {})", 
                 board.piece_name, board.piece_x, board.piece_y));
@@ -497,7 +1028,7 @@ impl Interpreter {
                 Token::Label(n) => {
                     labels
                         .entry(index_of_expression_chain)
-                        .or_insert_with(HashMap::new)
+                        .or_default()
                         .insert(n.to_string(), pc);
                 },
                 _ => continue,
@@ -506,14 +1037,33 @@ impl Interpreter {
 
         pc = 0usize;
         index_of_expression_chain = 0usize;
+        let mut steps = 0usize;
+        let mut loop_iterations = 0usize;
 
         while pc < self.tokens.len() {
             let token = &self.tokens[pc];
             
+            steps += 1;
+            if steps > self.max_steps {
+                if self.debug {
+                    log_debug(&format!("[Chessembly] 실행 단계 제한 초과 ({})", self.max_steps));
+                }
+                return Err(ChessemblyError::StepLimitExceeded { max_steps: self.max_steps });
+            }
+            
             if self.debug {
                 log_debug(&format!("  [PC:{}] Token: {:?} | Anchor: ({}, {}) | LastValue: {}", 
                     pc, token, anchor_x, anchor_y, last_value));
             }
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(TraceStep {
+                    pc,
+                    token: format!("{:?}", token),
+                    anchor_x,
+                    anchor_y,
+                    last_value,
+                });
+            }
             
             pc += 1;
             
@@ -521,16 +1071,23 @@ impl Interpreter {
             let should_terminate = !last_value && !matches!(token, 
                 Token::While | Token::Jmp(_) | Token::Jne(_) | Token::Not | 
                 Token::Label(_) | Token::Semicolon | Token::CloseBrace
-            );
+            ) && !matches!(token, Token::And(operands) | Token::Or(operands) if operands.is_empty());
             
             if should_terminate {
+                // 종료를 일으킨 토큰이 여는 괄호라면 그 스코프는 push되지 않았으므로
+                // 짝이 되는 닫는 괄호도 스코프 복원 없이 건너뛰어야 함
+                if matches!(token, Token::OpenBrace) {
+                    num_of_open_brace += 1;
+                }
                 // 현재 체인(;까지) 스킵
                 while pc < self.tokens.len() {
                     match &self.tokens[pc] {
                         Token::Semicolon => { 
-                            // 체인 종료: 앵커 초기화
+                            // 체인 종료: 앵커/스코프 초기화
                             anchor_x = 0;
                             anchor_y = 0;
+                            num_of_open_brace = 0;
+                            scope_stack.clear();
                             pending_tags.clear();
                             do_index = None;
                             last_take_pos = None;
@@ -566,9 +1123,10 @@ impl Interpreter {
             
             match token {
                 Token::Semicolon => {
-                    // 체인 종료, 앵커 초기화
+                    // 체인 종료, 앵커/스코프 초기화
                     anchor_x = 0;
                     anchor_y = 0;
+                    scope_stack.clear();
                     last_value = true;
                     pending_tags.clear();
                     do_index = None;
@@ -605,6 +1163,7 @@ impl Interpreter {
                             move_type: MoveType::TakeMove,
                             tags: pending_tags.clone(),
                             catch_to: None,
+                            advance_to: None,
                         });
                         anchor_x += dx;
                         anchor_y += dy;
@@ -616,6 +1175,7 @@ impl Interpreter {
                             move_type: MoveType::TakeMove,
                             tags: pending_tags.clone(),
                             catch_to: None,
+                            advance_to: None,
                         });
                         anchor_x += dx;
                         anchor_y += dy;
@@ -634,6 +1194,7 @@ impl Interpreter {
                             move_type: MoveType::Move,
                             tags: pending_tags.clone(),
                             catch_to: None,
+                            advance_to: None,
                         });
                         anchor_x += dx;
                         anchor_y += dy;
@@ -656,6 +1217,7 @@ impl Interpreter {
                             move_type: MoveType::Take,
                             tags: pending_tags.clone(),
                             catch_to: None,
+                            advance_to: None,
                         });
                         anchor_x += dx;
                         anchor_y += dy;
@@ -674,7 +1236,7 @@ impl Interpreter {
                 
                 Token::Jump(dx, dy) => {
                     // 앞의 take가 있고 적이 있었으면 take-jump 활성화
-                    if activations.last().unwrap().move_type == MoveType::Take {
+                    if activations.last().is_some_and(|last| last.move_type == MoveType::Take) {
                         activations.pop();
                     }
                     if let Some((_take_dx, _take_dy)) = last_take_pos.as_ref() {
@@ -690,6 +1252,7 @@ impl Interpreter {
                                 move_type: MoveType::Jump,
                                 tags: pending_tags.clone(),
                                 catch_to: last_take_pos,
+                                advance_to: None,
                             });
                             anchor_x += dx;
                             anchor_y += dy;
@@ -702,6 +1265,47 @@ impl Interpreter {
                     }
                 }
                 
+                Token::Hop(dx, dy) | Token::HopCatch(dx, dy) => {
+                    last_value = false;
+                    if (*dx, *dy) == (0, 0) {
+                        continue;
+                    }
+                    // 빈 칸을 지나 첫 기물(허들)까지
+                    let (mut screen_x, mut screen_y) = (anchor_x + dx, anchor_y + dy);
+                    while board.is_empty(board.piece_x + screen_x, board.piece_y + screen_y) {
+                        screen_x += dx;
+                        screen_y += dy;
+                    }
+                    if !board.in_bounds(board.piece_x + screen_x, board.piece_y + screen_y) {
+                        continue;
+                    }
+                    let (land_x, land_y) = (screen_x + dx, screen_y + dy);
+                    let landing_empty = board.is_empty(board.piece_x + land_x, board.piece_y + land_y);
+                    let activation = match token {
+                        Token::Hop(..) if landing_empty || board.has_enemy(board.piece_x + land_x, board.piece_y + land_y) => {
+                            Some((MoveType::TakeMove, None))
+                        }
+                        Token::HopCatch(..) if landing_empty && board.has_enemy(board.piece_x + screen_x, board.piece_y + screen_y) => {
+                            last_take_pos = Some((screen_x, screen_y));
+                            Some((MoveType::Jump, last_take_pos))
+                        }
+                        _ => None,
+                    };
+                    if let Some((move_type, catch_to)) = activation {
+                        self.add_activation(&mut activations, Activation {
+                            dx: land_x,
+                            dy: land_y,
+                            move_type,
+                            tags: pending_tags.clone(),
+                            catch_to,
+                            advance_to: None,
+                        });
+                        anchor_x = land_x;
+                        anchor_y = land_y;
+                        last_value = true;
+                    }
+                }
+                
                 Token::Catch(dx, dy) => {
                     let target_x = board.piece_x + anchor_x + dx;
                     let target_y = board.piece_y + anchor_y + dy;
@@ -713,6 +1317,7 @@ impl Interpreter {
                             move_type: MoveType::Catch,
                             tags: pending_tags.clone(),
                             catch_to: None,
+                            advance_to: None,
                         });
                         last_value = true;
                     } else {
@@ -721,6 +1326,31 @@ impl Interpreter {
                     // catch는 앵커를 이동하지 않음
                 }
                 
+                Token::CatchAdvance(cdx, cdy, mdx, mdy) => {
+                    let target_x = board.piece_x + anchor_x + cdx;
+                    let target_y = board.piece_y + anchor_y + cdy;
+                    let advance_x = board.piece_x + anchor_x + mdx;
+                    let advance_y = board.piece_y + anchor_y + mdy;
+                    // 전진 칸은 비어 있거나 잡힐 적의 칸이어야 함
+                    let can_advance = board.is_empty(advance_x, advance_y)
+                        || (mdx == cdx && mdy == cdy);
+                    
+                    if board.has_enemy(target_x, target_y) && can_advance {
+                        self.add_activation(&mut activations, Activation {
+                            dx: anchor_x + cdx,
+                            dy: anchor_y + cdy,
+                            move_type: MoveType::Catch,
+                            tags: pending_tags.clone(),
+                            catch_to: None,
+                            advance_to: Some((anchor_x + mdx, anchor_y + mdy)),
+                        });
+                        last_value = true;
+                    } else {
+                        last_value = false;
+                    }
+                    // catch와 마찬가지로 앵커를 이동하지 않음
+                }
+                
                 Token::Shift(dx, dy) => {
                     let target_x = board.piece_x + anchor_x + dx;
                     let target_y = board.piece_y + anchor_y + dy;
@@ -732,6 +1362,7 @@ impl Interpreter {
                             move_type: MoveType::Shift,
                             tags: pending_tags.clone(),
                             catch_to: None,
+                            advance_to: None,
                         });
                         anchor_x += dx;
                         anchor_y += dy;
@@ -744,134 +1375,48 @@ impl Interpreter {
                 Token::Anchor(dx, dy) => {
                     anchor_x += dx;
                     anchor_y += dy;
-                    last_value = true;
+                    last_value = board.in_bounds(board.piece_x + anchor_x, board.piece_y + anchor_y);
                 }
                 
                 // === 조건식 ===
-                Token::Observe(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = board.is_empty(target_x, target_y);
-                    // observe는 앵커를 이동하지 않음
-                }
-                
                 Token::Peek(dx, dy) => {
                     let target_x = board.piece_x + anchor_x + dx;
                     let target_y = board.piece_y + anchor_y + dy;
-                    if board.is_empty(target_x, target_y) {
-                        anchor_x += dx;
-                        anchor_y += dy;
-                        last_value = true;
-                    } else if board.is_empty(target_x, target_y) == false {
+                    if board.in_bounds(target_x, target_y) {
+                        // 막힌 칸이어도 앵커는 그 칸으로 이동
                         anchor_x += dx;
                         anchor_y += dy;
-                        last_value = false;
+                        last_value = board.is_empty(target_x, target_y);
                     } else {
+                        // 보드 밖이면 앵커는 제자리
                         last_value = false;
                     }
                 }
                 
-                Token::Enemy(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = board.has_enemy(target_x, target_y);
-                }
-                
-                Token::Friendly(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = board.has_friendly(target_x, target_y);
-                }
-                
-                Token::PieceOn(name, dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = board.has_piece(target_x, target_y, name);
-                }
-                
-                Token::Danger(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = board.danger_squares.contains(&(target_x, target_y));
-                }
-                
-                Token::Check => {
-                    last_value = board.in_check;
-                }
-                
-                Token::Bound(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = !board.in_bounds(target_x, target_y);
-                }
-                
-                Token::Edge(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = target_x < 0 || target_x >= board.board_width ||
-                                 target_y < 0 || target_y >= board.board_height;
-                }
-                
-                Token::EdgeTop(_, dy) => {
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = target_y >= board.board_height;
-                }
-
-                Token::EdgeBottom(_, dy) => {
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = target_y < 0;
-                }
-                
-                Token::EdgeLeft(dx, _) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    last_value = target_x < 0;
-                }
-                
-                Token::EdgeRight(dx, _) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    last_value = target_x >= board.board_width;
-                }
-                
-                Token::Corner(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    let out_x = target_x < 0 || target_x >= board.board_width;
-                    let out_y = target_y < 0 || target_y >= board.board_height;
-                    last_value = out_x && out_y;
-                }
-                
-                Token::CornerTopLeft(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = target_x < 0 && target_y >= board.board_height;
-                }
-                
-                Token::CornerTopRight(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = target_x >= board.board_width && target_y >= board.board_height;
-                }
-                
-                Token::CornerBottomLeft(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = target_x < 0 && target_y < 0;
-                }
-                
-                Token::CornerBottomRight(dx, dy) => {
-                    let target_x = board.piece_x + anchor_x + dx;
-                    let target_y = board.piece_y + anchor_y + dy;
-                    last_value = target_x >= board.board_width && target_y < 0;
-                }
-                
-                // === 상태 ===
-                Token::Piece(name) => {
-                    last_value = board.piece_name == *name;
+                Token::And(operands) | Token::Or(operands) if operands.is_empty() => {
+                    // 예외: 직전 값과 다음 조건식을 결합, 체인 종료 안함
+                    let is_and = matches!(token, Token::And(_));
+                    match self.tokens.get(pc) {
+                        Some(next) if next.is_condition() => {
+                            // 단락 평가: 결과가 이미 정해졌으면 다음 조건식은 평가하지 않음
+                            if last_value == is_and {
+                                last_value = Self::eval_condition(next, board, anchor_x, anchor_y);
+                            }
+                            pc += 1;
+                        }
+                        _ => last_value = false,
+                    }
                 }
                 
-                Token::IfState(key, expected) => {
-                    let actual = *board.state.get(key).unwrap_or(&0);
-                    last_value = actual == *expected;
+                Token::Observe(..) | Token::Enemy(..) | Token::Friendly(..) | Token::PieceOn(..) |
+                Token::Danger(..) | Token::Check | Token::Bound(..) | Token::Edge(..) |
+                Token::EdgeTop(..) | Token::EdgeBottom(..) | Token::EdgeLeft(..) | Token::EdgeRight(..) |
+                Token::Corner(..) | Token::CornerTopLeft(..) | Token::CornerTopRight(..) |
+                Token::CornerBottomLeft(..) | Token::CornerBottomRight(..) | Token::Piece(_) |
+                Token::IfState(..) | Token::StateGt(..) | Token::StateLt(..) | Token::StateGte(..) |
+                Token::StateLte(..) | Token::And(_) | Token::Or(_) => {
+                    // observe 등 조건식은 앵커를 이동하지 않음
+                    last_value = Self::eval_condition(token, board, anchor_x, anchor_y);
                 }
                 
                 Token::SetState(key, value) => {
@@ -884,6 +1429,21 @@ impl Interpreter {
                     last_value = true;
                 }
                 
+                Token::AddState(key, value) | Token::SubState(key, value) => {
+                    let tag_type = if matches!(token, Token::AddState(..)) {
+                        ActionTagType::AddState
+                    } else {
+                        ActionTagType::SubState
+                    };
+                    pending_tags.push(ActionTag {
+                        tag_type,
+                        key: key.clone(),
+                        value: *value,
+                        piece_name: None,
+                    });
+                    last_value = true;
+                }
+                
                 Token::SetStateReset => {
                     pending_tags.pop();
                     last_value = true;
@@ -906,6 +1466,7 @@ impl Interpreter {
                         // 반복할 시작점 계산 (n개 토큰 전)
                         let target = if pc > *n { pc - *n - 1 } else { 0 };
                         pc = target;
+                        loop_iterations += 1;
                     }
                     // repeat은 last_value를 그대로 전달
                 }
@@ -923,6 +1484,7 @@ impl Interpreter {
                     if last_value {
                         if let Some(target) = do_index {
                             pc = target;
+                            loop_iterations += 1;
                         }
                     }
                     last_value = true;
@@ -931,8 +1493,7 @@ impl Interpreter {
                 Token::Jmp(label) => {
                     // 예외: false여도 종료 안함
                     if last_value {
-                        let val_opt: usize = labels.get(&index_of_expression_chain).and_then(|inner| inner.get(label)).copied().expect("REASON");
-                        pc = val_opt;
+                        pc = Self::label_target(&labels, index_of_expression_chain, label)?;
                     }
                     last_value = true;
                 }
@@ -940,8 +1501,7 @@ impl Interpreter {
                 Token::Jne(label) => {
                     // 예외: false면 점프, 체인 종료 안함
                     if !last_value {
-                        let val_opt: usize = labels.get(&index_of_expression_chain).and_then(|inner| inner.get(label)).copied().expect("REASON");
-                        pc = val_opt;
+                        pc = Self::label_target(&labels, index_of_expression_chain, label)?;
                     }
                     last_value = true;
                 }
@@ -961,7 +1521,88 @@ impl Interpreter {
             }
         }
         
-        activations
+        self.last_stats.set(ExecStats {
+            tokens_executed: steps,
+            activations: activations.len(),
+            loop_iterations,
+        });
+        Ok(activations)
+    }
+    
+    /// 조건식 평가 (앵커는 이동하지 않음, `and`/`or`는 단락 평가)
+    fn eval_condition(token: &Token, board: &BoardState, anchor_x: i32, anchor_y: i32) -> bool {
+        let target = |dx: &i32, dy: &i32| (board.piece_x + anchor_x + dx, board.piece_y + anchor_y + dy);
+        match token {
+            Token::Observe(dx, dy) | Token::Peek(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                board.is_empty(x, y)
+            }
+            Token::Enemy(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                board.has_enemy(x, y)
+            }
+            Token::Friendly(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                board.has_friendly(x, y)
+            }
+            Token::PieceOn(name, dx, dy) => {
+                let (x, y) = target(dx, dy);
+                board.has_piece(x, y, name)
+            }
+            Token::Danger(dx, dy) => board.danger_squares.contains(&target(dx, dy)),
+            Token::Check => board.in_check,
+            Token::Bound(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                !board.in_bounds(x, y)
+            }
+            Token::Edge(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                x < 0 || x >= board.board_width || y < 0 || y >= board.board_height
+            }
+            Token::EdgeTop(dx, dy) => target(dx, dy).1 >= board.board_height,
+            Token::EdgeBottom(dx, dy) => target(dx, dy).1 < 0,
+            Token::EdgeLeft(dx, dy) => target(dx, dy).0 < 0,
+            Token::EdgeRight(dx, dy) => target(dx, dy).0 >= board.board_width,
+            Token::Corner(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                let out_x = x < 0 || x >= board.board_width;
+                let out_y = y < 0 || y >= board.board_height;
+                out_x && out_y
+            }
+            Token::CornerTopLeft(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                x < 0 && y >= board.board_height
+            }
+            Token::CornerTopRight(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                x >= board.board_width && y >= board.board_height
+            }
+            Token::CornerBottomLeft(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                x < 0 && y < 0
+            }
+            Token::CornerBottomRight(dx, dy) => {
+                let (x, y) = target(dx, dy);
+                x >= board.board_width && y < 0
+            }
+            Token::Piece(name) => board.piece_name == *name,
+            Token::IfState(key, expected) => *board.state.get(key).unwrap_or(&0) == *expected,
+            Token::StateGt(key, n) => *board.state.get(key).unwrap_or(&0) > *n,
+            Token::StateLt(key, n) => *board.state.get(key).unwrap_or(&0) < *n,
+            Token::StateGte(key, n) => *board.state.get(key).unwrap_or(&0) >= *n,
+            Token::StateLte(key, n) => *board.state.get(key).unwrap_or(&0) <= *n,
+            Token::And(operands) => operands.iter().all(|op| Self::eval_condition(op, board, anchor_x, anchor_y)),
+            Token::Or(operands) => operands.iter().any(|op| Self::eval_condition(op, board, anchor_x, anchor_y)),
+            _ => false,
+        }
+    }
+    
+    /// 현재 식 연쇄에서 라벨 위치 찾기
+    fn label_target(labels: &HashMap<usize, HashMap<String, usize>>, chain: usize, label: &str) -> Result<usize, ChessemblyError> {
+        labels.get(&chain)
+            .and_then(|inner| inner.get(label))
+            .copied()
+            .ok_or_else(|| ChessemblyError::UndefinedLabel { name: label.to_string(), chain })
     }
 }
 
@@ -970,18 +1611,66 @@ mod tests {
     use super::*;
     
     fn make_empty_board() -> BoardState {
-        BoardState {
-            board_width: 8,
-            board_height: 8,
-            piece_x: 4,
-            piece_y: 4,
-            piece_name: "test".to_string(),
-            is_white: true,
-            pieces: HashMap::new(),
-            state: HashMap::new(),
-            danger_squares: std::collections::HashSet::new(),
-            in_check: false,
-        }
+        BoardState::new(8, 8, 4, 4, "test", true)
+    }
+    
+    #[test]
+    fn test_board_state_builder() {
+        let mut board = BoardState::new(8, 8, 4, 4, "rook", true)
+            .with_piece(6, 4, "pawn", false)
+            .with_piece(4, 6, "pawn", true)
+            .with_state("mode", 1)
+            .with_danger_square(4, 5)
+            .with_check(true);
+        
+        assert_eq!(board.pieces.len(), 2);
+        assert_eq!(board.state.get("mode"), Some(&1));
+        assert!(board.danger_squares.contains(&(4, 5)));
+        assert!(board.in_check);
+        
+        let mut interp = Interpreter::new();
+        interp.parse("if-state(mode, 1) take-move(1, 0) repeat(1); take-move(0, 1) repeat(1);");
+        let activations = interp.execute(&mut board).unwrap();
+        
+        // 오른쪽: (5,4) + 적 (6,4) 잡기, 위쪽: (4,5)까지 (아군 (4,6)에 막힘)
+        assert_eq!(activations.len(), 3);
+        assert!(activations.iter().any(|a| a.dx == 2 && a.dy == 0));
+        assert!(!activations.iter().any(|a| a.dx == 0 && a.dy == 2));
+    }
+    
+    #[test]
+    fn test_static_offsets_knight() {
+        let mut interp = Interpreter::new();
+        interp.parse("take-move(1, 2); take-move(2, 1); take-move(2, -1); take-move(1, -2);
+                      take-move(-1, 2); take-move(-2, 1); take-move(-2, -1); take-move(-1, -2);");
+        let offsets = interp.static_offsets().expect("나이트는 고정 행마");
+        
+        assert_eq!(offsets.len(), 8);
+        assert!(offsets.contains(&(1, 2, MoveType::TakeMove)));
+        assert!(offsets.contains(&(-2, -1, MoveType::TakeMove)));
+    }
+    
+    #[test]
+    fn test_static_offsets_slider_is_bounded() {
+        let mut interp = Interpreter::new();
+        interp.parse("take-move(1, 0) repeat(1);");
+        let offsets = interp.static_offsets().unwrap();
+        
+        // 16x16 보드의 최대 이동 거리인 15칸까지
+        assert_eq!(offsets.len(), 15);
+        assert!(offsets.contains(&(15, 0, MoveType::TakeMove)));
+        assert!(!offsets.contains(&(16, 0, MoveType::TakeMove)));
+    }
+    
+    #[test]
+    fn test_static_offsets_cannon_is_dynamic() {
+        let mut interp = Interpreter::new();
+        interp.parse("do take(1, 0) enemy(0, 0) not while jump(1, 0) repeat(1);
+                      do peek(1, 0) while friendly(0, 0) move(1, 0) repeat(1);");
+        assert_eq!(interp.static_offsets(), None);
+        
+        interp.parse("state-gte(charge, 2) move(2, 0); or(check, observe(1, 0)) move(1, 0);");
+        assert_eq!(interp.static_offsets(), None);
     }
     
     #[test]
@@ -990,7 +1679,7 @@ mod tests {
         let mut interp = Interpreter::new();
         interp.parse("take-move(1, 0); take-move(0, 1); take-move(-1, 0); take-move(0, -1);");
         let mut board = make_empty_board();
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         assert_eq!(activations.len(), 4);
         assert!(activations.iter().any(|a| a.dx == 1 && a.dy == 0));
@@ -1005,7 +1694,7 @@ mod tests {
         let mut interp = Interpreter::new();
         interp.parse("take-move(1, 0) repeat(1);");
         let mut board = make_empty_board();
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // e5(4,4)에서 오른쪽으로 h5까지 3칸
         assert_eq!(activations.len(), 3);
@@ -1021,7 +1710,7 @@ mod tests {
         let mut board = make_empty_board();
         // (6, 4)에 아군 배치
         board.pieces.insert((6, 4), ("pawn".to_string(), true));
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // (5, 4)까지만 이동 가능 (dx=1)
         assert_eq!(activations.len(), 1);
@@ -1035,7 +1724,7 @@ mod tests {
         let mut board = make_empty_board();
         // (6, 4)에 적 배치
         board.pieces.insert((6, 4), ("pawn".to_string(), false));
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // (5, 4)와 (6, 4) 모두 활성화
         assert_eq!(activations.len(), 2);
@@ -1048,7 +1737,7 @@ mod tests {
         interp.parse("move(1, 0);");
         let mut board = make_empty_board();
         board.pieces.insert((5, 4), ("enemy".to_string(), false));
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // 적이 있으면 활성화 안됨
         assert_eq!(activations.len(), 0);
@@ -1060,7 +1749,7 @@ mod tests {
         let mut interp = Interpreter::new();
         interp.parse("take(1, 0);");
         let mut board = make_empty_board();
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // 빈 칸이면 활성화 안됨 (앵커만 이동)
         assert_eq!(activations.len(), 0);
@@ -1072,7 +1761,7 @@ mod tests {
         let mut interp = Interpreter::new();
         interp.parse("move(0, 1) { move(1, 1) } move(-1, 1);");
         let mut board = make_empty_board();
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // Y자 형태: (0,1), (1,2), (-1,2)
         assert_eq!(activations.len(), 3);
@@ -1089,7 +1778,7 @@ mod tests {
         let mut board = make_empty_board();
         // (5, 4)에 기물 배치 - 막힘
         board.pieces.insert((5, 4), ("blocker".to_string(), true));
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // observe가 false를 반환하여 take-move 실행 안됨
         assert_eq!(activations.len(), 0);
@@ -1101,7 +1790,7 @@ mod tests {
         let mut interp = Interpreter::new();
         interp.parse("do move(1, 0) while;");
         let mut board = make_empty_board();
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // 오른쪽 끝까지 슬라이드
         assert_eq!(activations.len(), 3);
@@ -1113,7 +1802,7 @@ mod tests {
         interp.parse("if-state(mode, 0) move(1, 0);");
         let mut board = make_empty_board();
         // mode 기본값은 0
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         assert_eq!(activations.len(), 1);
     }
@@ -1124,18 +1813,47 @@ mod tests {
         interp.parse("if-state(mode, 1) move(1, 0);");
         let mut board = make_empty_board();
         // mode는 0이므로 조건 불만족
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         assert_eq!(activations.len(), 0);
     }
     
+    #[test]
+    fn test_state_comparisons() {
+        let mut interp = Interpreter::new();
+        let mut board = make_empty_board().with_state("charge", 3);
+        let cases = [
+            ("state-gt(charge, 2)", true), ("state-gt(charge, 3)", false),
+            ("state-lt(charge, 4)", true), ("state-lt(charge, 3)", false),
+            ("state-gte(charge, 3)", true), ("state-gte(charge, 4)", false),
+            ("state-lte(charge, 3)", true), ("state-lte(charge, 2)", false),
+        ];
+        for (cond, expected) in cases {
+            interp.parse(&format!("{} move(1, 0);", cond));
+            let activations = interp.execute(&mut board).unwrap();
+            assert_eq!(activations.len(), expected as usize, "{}", cond);
+        }
+    }
+    
+    #[test]
+    fn test_state_comparison_unset_defaults_to_zero() {
+        let mut interp = Interpreter::new();
+        // 설정되지 않은 키는 0으로 간주, 앵커는 이동하지 않음
+        interp.parse("state-gte(charge, 0) state-lte(charge, 0) move(1, 0); state-gt(charge, 0) move(2, 0); state-lt(charge, 0) move(3, 0);");
+        let mut board = make_empty_board();
+        let activations = interp.execute(&mut board).unwrap();
+        
+        assert_eq!(activations.len(), 1);
+        assert_eq!((activations[0].dx, activations[0].dy), (1, 0));
+    }
+    
     #[test]
     fn test_piece_condition() {
         let mut interp = Interpreter::new();
         interp.parse("piece(rook) move(1, 0);");
         let mut board = make_empty_board();
         board.piece_name = "rook".to_string();
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         assert_eq!(activations.len(), 1);
     }
@@ -1145,7 +1863,7 @@ mod tests {
         let mut interp = Interpreter::new();
         interp.parse("transition(queen) move(1, 0);");
         let mut board = make_empty_board();
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         assert_eq!(activations.len(), 1);
         assert_eq!(activations[0].tags.len(), 1);
@@ -1153,6 +1871,169 @@ mod tests {
         assert_eq!(activations[0].tags[0].piece_name, Some("queen".to_string()));
     }
     
+    #[test]
+    fn test_add_sub_state_tags() {
+        let mut interp = Interpreter::new();
+        interp.parse("add-state(moves, 1) move(1, 0); sub-state(charge, 2) observe(-1, 0) not move(-1, 0);");
+        let mut board = make_empty_board();
+        let activations = interp.execute(&mut board).unwrap();
+        
+        // 두 번째 연쇄는 활성화되지 않았으므로 태그도 남지 않음
+        assert_eq!(activations.len(), 1);
+        assert_eq!(activations[0].tags, vec![ActionTag {
+            tag_type: ActionTagType::AddState,
+            key: "moves".to_string(),
+            value: 1,
+            piece_name: None,
+        }]);
+        
+        interp.parse("sub-state(charge, 2) move(1, 0);");
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations[0].tags[0].tag_type, ActionTagType::SubState);
+        assert_eq!(activations[0].tags[0].value, 2);
+    }
+    
+    #[test]
+    fn test_block_comment() {
+        let plain = "take-move(1, 0) repeat(1); take-move(0, 1) repeat(1);";
+        let mut expected = Interpreter::new();
+        expected.parse(plain);
+        
+        let mut interp = Interpreter::new();
+        interp.parse("take-move(1, 0)/* 가로 */repeat(1);\n/* take-move(-1, 0) repeat(1);\n   take-move(0, -1) repeat(1); */\ntake-move(0, 1) repeat(1);");
+        assert_eq!(interp.tokens, expected.tokens);
+        
+        // 닫히지 않은 블록 주석은 입력 끝까지
+        interp.parse("take-move(1, 0) repeat(1); take-move(0, 1) repeat(1); /* take-move(-1, 0) repeat(1);");
+        assert_eq!(interp.tokens, expected.tokens);
+        
+        assert_eq!(mirror_script("move(0, 1) /* move(0, 1) */ move(0, 2);"), "move(0, -1) /* move(0, 1) */ move(0, -2);");
+    }
+    
+    #[test]
+    #[allow(deprecated)]
+    fn test_spans_and_unknown_words() {
+        let mut interp = Interpreter::new();
+        interp.parse("move(1, 0);\n# 주석\n  /* 블록 */ jmpp(A) and(enemy(1, 0), dangr(1, 0));");
+        
+        assert_eq!(interp.spans(), &[
+            Span { line: 1, column: 1 },
+            Span { line: 1, column: 11 },
+            Span { line: 3, column: 12 },
+            Span { line: 3, column: 20 },
+            Span { line: 3, column: 49 },
+        ]);
+        assert_eq!(interp.validate(), Err(vec![
+            ParseError::UnknownKeyword { word: "jmpp".to_string(), span: Span { line: 3, column: 12 } },
+            ParseError::UnknownKeyword { word: "dangr".to_string(), span: Span { line: 3, column: 20 } },
+        ]));
+        assert_eq!(interp.unknown_words(), vec![
+            UnknownWord { word: "jmpp".to_string(), span: Span { line: 3, column: 12 } },
+            UnknownWord { word: "dangr".to_string(), span: Span { line: 3, column: 20 } },
+        ]);
+        
+        interp.parse("end;");
+        assert_eq!(interp.validate(), Ok(()));
+        assert!(interp.unknown_words().is_empty());
+    }
+    
+    #[test]
+    fn test_validate() {
+        let mut interp = Interpreter::new();
+        interp.parse("takemove(1,0);");
+        let errors = interp.validate().unwrap_err();
+        assert_eq!(errors, vec![ParseError::UnknownKeyword { word: "takemove".to_string(), span: Span { line: 1, column: 1 } }]);
+        assert_eq!(errors[0].to_string(), "1:1: 알 수 없는 식입니다: takemove");
+        
+        interp.parse("piece-on(pawn, 1) move(1, 0) set-state;\n{ take-move(0, 1) } } or(check, move(1, 0)) { move(1, 1);");
+        assert_eq!(interp.validate(), Err(vec![
+            ParseError::WrongArgCount { word: "piece-on".to_string(), expected: &[3], found: 2, span: Span { line: 1, column: 1 } },
+            ParseError::UnmatchedBrace { span: Span { line: 2, column: 21 } },
+            ParseError::NotACondition { word: "move(1, 0)".to_string(), span: Span { line: 2, column: 23 } },
+            ParseError::UnmatchedBrace { span: Span { line: 2, column: 45 } },
+        ]));
+        
+        // do/while과 조건 블록을 쓰는 올바른 스크립트는 통과
+        interp.parse("do take(1, 0) enemy(0, 0) not while jump(1, 0) repeat(1); if-state(mode, 1) { take-move(1, 1) repeat(1) };");
+        assert_eq!(interp.validate(), Ok(()));
+    }
+    
+    #[test]
+    fn test_non_ascii_words() {
+        let mut interp = Interpreter::new();
+        // 여러 바이트 문자 (갠은 UTF-8에 0xA0 바이트를 포함) 중간에서 끊거나 패닉하지 않음
+        interp.parse("move(1, 0); 가(1, 0); 갠;");
+        assert_eq!(interp.validate(), Err(vec![
+            ParseError::UnknownKeyword { word: "가".to_string(), span: Span { line: 1, column: 13 } },
+            ParseError::UnknownKeyword { word: "갠".to_string(), span: Span { line: 1, column: 22 } },
+        ]));
+        
+        interp.parse("move(0, 1) transition(기사);");
+        assert_eq!(interp.tokens[1], Token::Transition("기사".to_string()));
+    }
+    
+    #[test]
+    fn test_peek_stops_anchor_at_board_edge() {
+        let mut interp = Interpreter::new();
+        // 보드 밖을 엿보면 false, 앵커는 (4, 4)에 그대로
+        interp.parse("peek(4, 0) while move(-1, 0);");
+        let mut board = make_empty_board();
+        let activations = interp.execute(&mut board).unwrap();
+        
+        assert_eq!(activations.len(), 1);
+        assert_eq!((activations[0].dx, activations[0].dy), (-1, 0));
+    }
+    
+    #[test]
+    fn test_hop() {
+        let mut interp = Interpreter::new();
+        interp.parse("hop(1, 0);");
+        
+        // 허들 없음: 이동 불가
+        let mut board = make_empty_board();
+        assert!(interp.execute(&mut board).unwrap().is_empty());
+        
+        // 허들 하나, 빈 착지
+        let mut board = make_empty_board().with_piece(6, 4, "pawn", true);
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 1);
+        assert_eq!((activations[0].dx, activations[0].dy, activations[0].move_type), (3, 0, MoveType::TakeMove));
+        
+        // 허들 하나, 적이 있는 착지
+        let mut board = make_empty_board().with_piece(5, 4, "pawn", true).with_piece(6, 4, "pawn", false);
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!((activations[0].dx, activations[0].dy), (2, 0));
+        
+        // 허들 둘 (착지 칸에 아군): 이동 불가
+        let mut board = make_empty_board().with_piece(5, 4, "pawn", false).with_piece(6, 4, "pawn", true);
+        assert!(interp.execute(&mut board).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_hop_catch() {
+        let mut interp = Interpreter::new();
+        interp.parse("hop-catch(1, 0) jump(1, 0) repeat(1);");
+        
+        // 허들 없음
+        let mut board = make_empty_board();
+        assert!(interp.execute(&mut board).unwrap().is_empty());
+        
+        // 적 허들 하나: 허들을 잡고 그 뒤 빈 칸들에 착지
+        let mut board = make_empty_board().with_piece(5, 4, "pawn", false);
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 2);
+        assert!(activations.iter().all(|a| a.move_type == MoveType::Jump && a.catch_to == Some((1, 0))));
+        assert_eq!((activations[0].dx, activations[1].dx), (2, 3));
+        
+        // 아군 허들은 잡지 않음
+        let mut board = make_empty_board().with_piece(5, 4, "pawn", true);
+        assert!(interp.execute(&mut board).unwrap().is_empty());
+        
+        // 허들 둘 (착지 칸이 막힘)
+        let mut board = make_empty_board().with_piece(5, 4, "pawn", false).with_piece(6, 4, "pawn", false);
+        assert!(interp.execute(&mut board).unwrap().is_empty());
+    }
+    
     #[test]
     fn test_not() {
         let mut interp = Interpreter::new();
@@ -1161,11 +2042,59 @@ mod tests {
         let mut board = make_empty_board();
         // (5,4)에 기물 있으면 observe=false, not=true, jne 안함, move(2,0) 실행
         board.pieces.insert((5, 4), ("blocker".to_string(), true));
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         
         // observe=false -> not=true -> jne 안함 -> move(2,0) 시도하지만 실패
         // 그래서 label(SKIP) move(1,0)도 별도 체인으로 실행됨
-        assert!(activations.len() >= 1);
+        assert!(!activations.is_empty());
+    }
+
+    #[test]
+    fn test_and() {
+        let mut interp = Interpreter::new();
+        // 앞쪽이 적이고 그 칸이 위험하지 않을 때만 잡기
+        interp.parse("enemy(1, 0) and danger(1, 0) not take(1, 0); and(enemy(0, 1), observe(0, 2)) move(0, 2);");
+        let mut board = make_empty_board()
+            .with_piece(5, 4, "pawn", false)
+            .with_piece(4, 5, "pawn", true);
+        let activations = interp.execute(&mut board).unwrap();
+        // 1번 연쇄: enemy=true -> and danger=false -> not=true -> take(1,0)
+        // 2번 연쇄: enemy(0,1)이 false이므로 observe(0,2)는 평가하지 않고 false
+        assert_eq!(activations.len(), 1);
+        assert_eq!((activations[0].dx, activations[0].dy), (1, 0));
+
+        // 앞이 false면 and는 다음 조건식을 건너뛰고 false로 연쇄 종료
+        interp.parse("check and observe(1, 0) move(1, 0);");
+        let activations = interp.execute(&mut board).unwrap();
+        assert!(activations.is_empty());
+    }
+
+    #[test]
+    fn test_or() {
+        let mut interp = Interpreter::new();
+        // 빈 칸이거나 적이면 이동 (false여도 or에서 연쇄가 끝나지 않음)
+        interp.parse("observe(1, 0) or enemy(1, 0) take-move(1, 0); or(friendly(0, 1), peek(0, 1)) move(0, 2);");
+        let mut board = make_empty_board()
+            .with_piece(5, 4, "pawn", false)
+            .with_piece(4, 5, "pawn", true);
+        let activations = interp.execute(&mut board).unwrap();
+        // 2번 연쇄: friendly=true로 단락, peek는 평가되지 않고 앵커도 움직이지 않음
+        assert_eq!(activations.len(), 2);
+        assert_eq!((activations[0].dx, activations[0].dy), (1, 0));
+        assert_eq!((activations[1].dx, activations[1].dy), (0, 2));
+
+        // 앞이 true면 or는 다음 조건식을 건너뛰고 true 유지
+        interp.parse("observe(-1, 0) or enemy(-1, 0) move(-1, 0);");
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 1);
+    }
+
+    #[test]
+    fn test_and_or_operands_must_be_conditions() {
+        let mut interp = Interpreter::new();
+        interp.parse("or(observe(1, 0), move(1, 0)) move(1, 0);");
+        let mut board = make_empty_board();
+        assert!(interp.execute(&mut board).unwrap().is_empty());
     }
 
     #[test]
@@ -1174,10 +2103,152 @@ mod tests {
         interp.parse("if-state(mode, 1) set-state(mode, 0) { take-move(1, 0) repeat(1) } { take-move(-1, 0) repeat(1) };");
         let mut board = make_empty_board();
         // mode 기본 0이므로 조건 불만족 -> 모든 take-move는 무시되어야 함
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         assert_eq!(activations.len(), 0);
     }
 
+    #[test]
+    fn test_failed_step_skips_all_following_scopes() {
+        // 대각 1칸이 막히면 뒤따르는 두 스코프 모두 실행되지 않아야 함
+        let mut interp = Interpreter::new();
+        interp.parse("take-move(1, 1) { take-move(1, 0) repeat(1) } { take-move(0, 1) repeat(1) };");
+        let mut board = make_empty_board();
+        board.pieces.insert((5, 5), ("blocker".to_string(), true));
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 0);
+        
+        // 적을 잡으며 끝난 경우에도 그 너머로 슬라이드하지 않아야 함
+        board.pieces.insert((5, 5), ("enemy".to_string(), false));
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 1);
+        assert_eq!((activations[0].dx, activations[0].dy), (1, 1));
+    }
+
+    #[test]
+    fn test_execute_traced_records_each_token() {
+        let mut interp = Interpreter::new();
+        interp.parse("move(0, 1) move(0, 1);");
+        let mut board = make_empty_board();
+        let (activations, trace) = interp.execute_traced(&mut board).unwrap();
+        
+        assert_eq!(activations, interp.execute(&mut board).unwrap());
+        assert_eq!(trace.len(), 3);
+        assert_eq!(trace[0].pc, 0);
+        assert_eq!((trace[1].anchor_x, trace[1].anchor_y), (0, 1));
+        assert_eq!(trace[2].token, "Semicolon");
+    }
+
+    #[test]
+    fn test_anchor_off_board_stops_chain() {
+        let mut interp = Interpreter::new();
+        interp.parse("anchor(0, 2) move(1, 0); anchor(0, 5) move(1, 0); anchor(0, 5) anchor(0, -4) move(1, 0);");
+        let mut board = make_empty_board();
+        let activations = interp.execute(&mut board).unwrap();
+        
+        // (4,4)에서 위로 5칸은 보드 밖: 두 번째 체인은 종료, 세 번째도 중간에 밖으로 나가 종료
+        assert_eq!(activations.len(), 1);
+        assert_eq!((activations[0].dx, activations[0].dy), (1, 2));
+    }
+
+    #[test]
+    fn test_catch_advance() {
+        let mut interp = Interpreter::new();
+        interp.parse("catch-advance(0, 2, 0, 1);");
+        let mut board = make_empty_board().with_piece(4, 6, "enemy", false);
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 1);
+        assert_eq!((activations[0].dx, activations[0].dy), (0, 2));
+        assert_eq!(activations[0].move_type, MoveType::Catch);
+        assert_eq!(activations[0].advance_to, Some((0, 1)));
+        
+        // 전진 칸이 막혀 있으면 활성화되지 않음
+        let mut board = board.with_piece(4, 5, "blocker", true);
+        assert!(interp.execute(&mut board).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_jump_without_prior_activation() {
+        let mut interp = Interpreter::new();
+        interp.parse("jump(1,0);");
+        let mut board = make_empty_board();
+        assert!(interp.execute(&mut board).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_transition_only_on_promoting_step() {
+        let mut interp = Interpreter::new();
+        interp.parse("move(0, 1); edge-top(0, 2) transition(queen) move(0, 1);");
+        
+        // 7랭크 폰: 태그 없는 전진과 조건 뒤의 승급 전진이 따로 남음
+        let mut board = BoardState::new(8, 8, 4, 6, "pawn", true);
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 2);
+        assert!(activations[0].tags.is_empty());
+        assert_eq!(activations[1].tags.len(), 1);
+        assert_eq!(activations[1].tags[0].piece_name.as_deref(), Some("queen"));
+        
+        // 그 전 랭크에서는 태그 없음
+        let mut board = BoardState::new(8, 8, 4, 4, "pawn", true);
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 1);
+        assert!(activations[0].tags.is_empty());
+        
+        // 마지막 랭크에서 승급을 강제하려면 태그 없는 전진을 반대 조건으로 막음
+        interp.parse("edge-top(0, 2) not move(0, 1); edge-top(0, 2) transition(queen) move(0, 1);");
+        let mut board = BoardState::new(8, 8, 4, 6, "pawn", true);
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 1);
+        assert_eq!(activations[0].tags[0].piece_name.as_deref(), Some("queen"));
+        let mut board = BoardState::new(8, 8, 4, 4, "pawn", true);
+        let activations = interp.execute(&mut board).unwrap();
+        assert_eq!(activations.len(), 1);
+        assert!(activations[0].tags.is_empty());
+    }
+
+    #[test]
+    fn test_undefined_label_is_error() {
+        let mut interp = Interpreter::new();
+        interp.parse("move(1, 0); piece(test) jmp(nowhere) move(0, 1);");
+        let mut board = make_empty_board();
+        let err = interp.execute(&mut board).unwrap_err();
+        assert_eq!(err, ChessemblyError::UndefinedLabel { name: "nowhere".to_string(), chain: 1 });
+    }
+
+    #[test]
+    fn test_step_limit_stops_infinite_loop() {
+        let mut interp = Interpreter::new();
+        interp.parse("do move(0, 0) while;");
+        interp.set_max_steps(1_000);
+        let mut board = make_empty_board();
+        let err = interp.execute(&mut board).unwrap_err();
+        assert_eq!(err, ChessemblyError::StepLimitExceeded { max_steps: 1_000 });
+        
+        // 일반 스크립트는 제한 안에서 끝남
+        interp.parse("take-move(1, 0) repeat(1);");
+        assert_eq!(interp.execute(&mut board).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_mirror_script() {
+        assert_eq!(
+            mirror_script("take-move(1, 2) repeat(1); edge-top(0, 2) transition(queen) move(0, 1); # 위쪽\ncatch-advance(0, 2, 0, 1);"),
+            "take-move(1, -2) repeat(1); edge-bottom(0, -2) transition(queen) move(0, -1); # 위쪽\ncatch-advance(0, -2, 0, -1);"
+        );
+        assert_eq!(mirror_script("piece-on(pawn, 1, 1) set-state(turns, 3);"), "piece-on(pawn, 1, -1) set-state(turns, 3);");
+        assert_eq!(mirror_script("or(edge-top(0, 1), and(enemy(1, 1), danger(1,1))) move(0, 1);"), "or(edge-bottom(0, -1), and(enemy(1, -1), danger(1, -1))) move(0, -1);");
+    }
+
+    #[test]
+    fn test_exec_stats_rook_slide() {
+        let mut interp = Interpreter::new();
+        interp.parse("take-move(1, 0) repeat(1);");
+        let mut board = make_empty_board();
+        interp.execute(&mut board).unwrap();
+        
+        // (4,4)에서 오른쪽으로 3칸: take-move 4번(마지막은 실패), repeat 3번 되돌림, 실패 후 repeat에서 종료
+        assert_eq!(interp.last_stats(), ExecStats { tokens_executed: 8, activations: 3, loop_iterations: 3 });
+    }
+
     #[test]
     fn test_jmp(){
         let mut interp = Interpreter::new();
@@ -1185,7 +2256,7 @@ mod tests {
         let mut board = make_empty_board();
         
         //piece(test)는 true이니 label로 점프 해야 함.
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         assert_eq!(activations.len(), 0);
     }
 
@@ -1196,7 +2267,7 @@ mod tests {
         let mut board = make_empty_board();
         
         //piece(queen)는 false이니 label로 점프 해야 함.
-        let activations = interp.execute(&mut board);
+        let activations = interp.execute(&mut board).unwrap();
         assert_eq!(activations.len(), 2);
     }
 }
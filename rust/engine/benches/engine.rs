@@ -0,0 +1,74 @@
+use std::hint::black_box;
+
+use chessembly::{BoardState, Interpreter};
+use criterion::{criterion_group, criterion_main, Criterion};
+use engine::{GameState, PieceKind, PieceSpec, Square};
+
+/// 중앙의 아마존 주위로 양측 기물이 흩어진 포지션
+fn crowded_amazon_position() -> (GameState, String) {
+    let mut state = GameState::new(0);
+    let placements = [
+        (0, PieceKind::Amazon, Square::new(3, 3)),
+        (1, PieceKind::Rook, Square::new(3, 6)),
+        (0, PieceKind::Knight, Square::new(5, 3)),
+        (1, PieceKind::Bishop, Square::new(6, 6)),
+        (0, PieceKind::Pawn, Square::new(1, 1)),
+        (1, PieceKind::Knight, Square::new(0, 3)),
+        (0, PieceKind::Bishop, Square::new(2, 2)),
+        (1, PieceKind::Pawn, Square::new(5, 5)),
+    ];
+    for (owner, kind, _) in &placements {
+        let mut pocket = state.get_pocket(*owner).into_iter().map(PieceSpec::new).collect::<Vec<_>>();
+        pocket.push(PieceSpec::new(kind.clone()));
+        state.setup_pocket_unchecked(*owner, pocket);
+    }
+
+    let mut amazon_id = String::new();
+    for (owner, kind, square) in placements {
+        if state.current_player() != owner {
+            state.end_turn().unwrap();
+        }
+        let id = state.place_piece(owner, kind.clone(), square).unwrap();
+        if kind == PieceKind::Amazon {
+            amazon_id = id;
+        }
+        state.end_turn().unwrap();
+    }
+
+    // 스턴 없이 바로 움직일 수 있도록
+    for piece in state.pieces.values_mut() {
+        piece.stun = 0;
+    }
+    (state, amazon_id)
+}
+
+fn bench_amazon_legal_moves(c: &mut Criterion) {
+    let (state, amazon_id) = crowded_amazon_position();
+    c.bench_function("get_legal_moves/amazon_crowded", |b| {
+        b.iter(|| state.get_legal_moves(black_box(&amazon_id)))
+    });
+}
+
+fn bench_cannon_execute(c: &mut Criterion) {
+    let mut interpreter = Interpreter::new();
+    interpreter.parse(PieceKind::Cannon.chessembly_script(true));
+    let mut board = BoardState::new(8, 8, 3, 3, "Cannon", true)
+        .with_piece(3, 5, "Pawn", true)
+        .with_piece(3, 7, "Rook", false)
+        .with_piece(1, 3, "Knight", false)
+        .with_piece(0, 3, "Pawn", false)
+        .with_piece(6, 3, "Bishop", true);
+    c.bench_function("interpreter/execute_cannon", |b| {
+        b.iter(|| interpreter.execute(black_box(&mut board)).unwrap())
+    });
+}
+
+fn bench_perft(c: &mut Criterion) {
+    let state = GameState::new(0);
+    c.bench_function("perft/initial_kings_depth3", |b| {
+        b.iter(|| state.perft(black_box(3)))
+    });
+}
+
+criterion_group!(benches, bench_amazon_legal_moves, bench_cannon_execute, bench_perft);
+criterion_main!(benches);
@@ -2,12 +2,16 @@
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::cell::OnceCell;
+use std::sync::Arc;
+#[cfg(debug_assertions)]
+use std::cell::Cell;
 
 // Chessembly 인터프리터 사용
 use chessembly::{Interpreter, BoardState as ChessemblyBoard};
 
 // MoveType을 공개적으로 재export
-pub use chessembly::MoveType;
+pub use chessembly::{MoveType, TraceStep};
 
 pub type PlayerId = u8;
 pub type PieceId = String;
@@ -24,35 +28,78 @@ impl Square {
         Self { x, y }
     }
     
-    /// "e4" 같은 문자열에서 파싱
+    /// "e4" 같은 문자열에서 파싱 (8칸을 넘는 보드는 "j10"처럼 랭크가 두 자리 이상)
     pub fn from_notation(s: &str) -> Option<Self> {
-        let chars: Vec<char> = s.chars().collect();
-        if chars.len() != 2 {
+        let mut chars = s.chars();
+        let file = chars.next().filter(|c| c.is_ascii_lowercase())?;
+        let rank = chars.as_str();
+        if rank.is_empty() || rank.starts_with('0') || !rank.bytes().all(|b| b.is_ascii_digit()) {
             return None;
         }
-        let x = (chars[0] as i32) - ('a' as i32);
-        let y = (chars[1] as i32) - ('1' as i32);
-        if x >= 0 && x < 8 && y >= 0 && y < 8 {
-            Some(Self { x, y })
-        } else {
-            None
-        }
+        let y = rank.parse::<i32>().ok()? - 1;
+        Some(Self { x: file as i32 - 'a' as i32, y })
     }
     
-    /// 체스 표기법으로 변환
+    /// 체스 표기법으로 변환 (파일은 a~z라 가로 26칸까지)
     pub fn to_notation(&self) -> String {
         let file = (b'a' + self.x as u8) as char;
-        let rank = (b'1' + self.y as u8) as char;
-        format!("{}{}", file, rank)
+        format!("{}{}", file, self.y + 1)
     }
     
+    /// 기본 8x8 보드 안인지 (크기가 다른 보드는 `is_within`/`GameState::contains_square`)
     pub fn is_valid(&self) -> bool {
-        self.x >= 0 && self.x < 8 && self.y >= 0 && self.y < 8
+        self.is_within(8, 8)
+    }
+    
+    /// `width` x `height` 보드 안인지
+    pub fn is_within(&self, width: i32, height: i32) -> bool {
+        self.x >= 0 && self.x < width && self.y >= 0 && self.y < height
+    }
+    
+    /// 두 칸 사이(양 끝 제외)의 칸들을 순서대로 반환
+    /// 같은 랭크/파일/대각선 위에 있지 않으면 빈 반복자
+    pub fn line_to(&self, other: Square) -> impl Iterator<Item = Square> {
+        let dx = other.x - self.x;
+        let dy = other.y - self.y;
+        let aligned = dx == 0 || dy == 0 || dx.abs() == dy.abs();
+        let steps = if aligned { dx.abs().max(dy.abs()) } else { 0 };
+        let (step_x, step_y) = (dx.signum(), dy.signum());
+        let start = *self;
+        (1..steps).map(move |i| Square::new(start.x + step_x * i, start.y + step_y * i))
+    }
+}
+
+/// 지원하는 가장 큰 보드의 가로/세로 칸 수 (`Action::encode`와 칸 직렬화가 구분하는 범위)
+pub const MAX_BOARD_SIZE: i32 = 16;
+
+/// 칸은 "e4" 같은 표기 문자열로 직렬화 (JSON 맵 키로도 쓰이므로)
+///
+/// 범위는 `MAX_BOARD_SIZE` 기준이며, 실제 보드 크기 안인지는 `GameState::validate_invariants`가 검사합니다.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Square {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !self.is_within(MAX_BOARD_SIZE, MAX_BOARD_SIZE) {
+            return Err(serde::ser::Error::custom(format!(
+                "{}x{} 보드 밖 칸은 직렬화할 수 없습니다", MAX_BOARD_SIZE, MAX_BOARD_SIZE,
+            )));
+        }
+        serializer.serialize_str(&self.to_notation())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Square {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let notation = String::deserialize(deserializer)?;
+        Square::from_notation(&notation)
+            .filter(|sq| sq.is_within(MAX_BOARD_SIZE, MAX_BOARD_SIZE))
+            .ok_or_else(|| serde::de::Error::custom(format!("잘못된 칸 표기입니다: {}", notation)))
     }
 }
 
 /// 기물 종류
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceKind {
     Pawn,
     King,
@@ -97,7 +144,160 @@ impl PieceKind {
             PieceKind::TempestRook => 7,
             PieceKind::Cannon => 5,
             PieceKind::Experiment => 1, //실험용 기물.
-            PieceKind::Custom(_) => 3, // 기본값
+            PieceKind::Custom(_) => 3, // 기본값 (등록된 점수는 `GameState::score_of`)
+        }
+    }
+    
+    /// 압축 인코딩용 기물 코드 (Custom은 코드 없음)
+    fn code(&self) -> Option<u32> {
+        let code = match self {
+            PieceKind::Pawn => 0,
+            PieceKind::King => 1,
+            PieceKind::Queen => 2,
+            PieceKind::Rook => 3,
+            PieceKind::Knight => 4,
+            PieceKind::Bishop => 5,
+            PieceKind::Amazon => 6,
+            PieceKind::Grasshopper => 7,
+            PieceKind::Knightrider => 8,
+            PieceKind::Archbishop => 9,
+            PieceKind::Dabbaba => 10,
+            PieceKind::Alfil => 11,
+            PieceKind::Ferz => 12,
+            PieceKind::Centaur => 13,
+            PieceKind::Camel => 14,
+            PieceKind::TempestRook => 15,
+            PieceKind::Cannon => 16,
+            PieceKind::Experiment => 17,
+            PieceKind::Custom(_) => return None,
+        };
+        Some(code)
+    }
+    
+    /// 기물 코드에서 복원
+    fn from_code(code: u32) -> Option<PieceKind> {
+        Self::all().into_iter().find(|kind| kind.code() == Some(code))
+    }
+    
+    /// 직렬화용 기물 표기 (코드 번호, Custom은 "~이름")
+    fn token(&self) -> String {
+        match self {
+            PieceKind::Custom(name) => format!("~{}", name),
+            kind => kind.code().unwrap_or_default().to_string(),
+        }
+    }
+    
+    /// `token` 표기에서 복원
+    fn from_token(token: &str) -> Option<PieceKind> {
+        match token.strip_prefix('~') {
+            Some(name) => Some(PieceKind::Custom(name.to_string())),
+            None => Self::from_code(token.parse().ok()?),
+        }
+    }
+    
+    /// 보드 표기(`GameState::to_notation`)용 기물 글자 (백 기준 대소문자, Custom은 `U'이름'`)
+    fn letters(&self) -> String {
+        let letters = match self {
+            PieceKind::Pawn => "P",
+            PieceKind::King => "K",
+            PieceKind::Queen => "Q",
+            PieceKind::Rook => "R",
+            PieceKind::Knight => "N",
+            PieceKind::Bishop => "B",
+            PieceKind::Amazon => "Am",
+            PieceKind::Grasshopper => "G",
+            PieceKind::Knightrider => "Nr",
+            PieceKind::Archbishop => "Ab",
+            PieceKind::Dabbaba => "D",
+            PieceKind::Alfil => "Al",
+            PieceKind::Ferz => "F",
+            PieceKind::Centaur => "C",
+            PieceKind::Camel => "Cm",
+            PieceKind::TempestRook => "Tr",
+            PieceKind::Cannon => "Ca",
+            PieceKind::Experiment => "X",
+            PieceKind::Custom(name) => return format!("U'{}'", name),
+        };
+        letters.to_string()
+    }
+    
+    /// `letters` 표기에서 복원 (대소문자 무시, Custom 제외)
+    fn from_letters(letters: &str) -> Option<PieceKind> {
+        PieceKind::all().into_iter().find(|kind| kind.letters().eq_ignore_ascii_case(letters))
+    }
+    
+    /// 스크립트의 기물 이름에서 변환 (대소문자 무시, 모르는 이름은 None)
+    pub fn from_name(name: &str) -> Option<PieceKind> {
+        let kind = match name.to_lowercase().as_str() {
+            "pawn" => PieceKind::Pawn,
+            "king" => PieceKind::King,
+            "queen" => PieceKind::Queen,
+            "rook" => PieceKind::Rook,
+            "knight" => PieceKind::Knight,
+            "bishop" => PieceKind::Bishop,
+            "amazon" => PieceKind::Amazon,
+            "grasshopper" => PieceKind::Grasshopper,
+            "knightrider" => PieceKind::Knightrider,
+            "archbishop" => PieceKind::Archbishop,
+            "dabbaba" => PieceKind::Dabbaba,
+            "alfil" => PieceKind::Alfil,
+            "ferz" => PieceKind::Ferz,
+            "centaur" => PieceKind::Centaur,
+            "camel" => PieceKind::Camel,
+            "tempestrook" => PieceKind::TempestRook,
+            "cannon" => PieceKind::Cannon,
+            "experiment" => PieceKind::Experiment,
+            _ => return None,
+        };
+        Some(kind)
+    }
+    
+    /// Custom을 제외한 모든 기물 종류 (페어리/실험용 기물 포함)
+    pub fn all() -> Vec<PieceKind> {
+        vec![
+            PieceKind::Pawn,
+            PieceKind::King,
+            PieceKind::Queen,
+            PieceKind::Rook,
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Amazon,
+            PieceKind::Grasshopper,
+            PieceKind::Knightrider,
+            PieceKind::Archbishop,
+            PieceKind::Dabbaba,
+            PieceKind::Alfil,
+            PieceKind::Ferz,
+            PieceKind::Centaur,
+            PieceKind::Camel,
+            PieceKind::TempestRook,
+            PieceKind::Cannon,
+            PieceKind::Experiment,
+        ]
+    }
+    
+    /// 기물 행마 설명 (UI 카탈로그용)
+    pub fn description(&self) -> &'static str {
+        match self {
+            PieceKind::Pawn => "앞으로 한 칸 이동, 대각선 앞으로 잡기",
+            PieceKind::King => "모든 방향으로 한 칸",
+            PieceKind::Queen => "가로/세로/대각선으로 슬라이드",
+            PieceKind::Rook => "가로/세로로 슬라이드",
+            PieceKind::Knight => "L자 도약",
+            PieceKind::Bishop => "대각선으로 슬라이드",
+            PieceKind::Amazon => "퀸 + 나이트",
+            PieceKind::Grasshopper => "한 줄 위의 기물을 뛰어넘어 바로 뒤 칸에 착지",
+            PieceKind::Knightrider => "나이트 도약을 같은 방향으로 반복",
+            PieceKind::Archbishop => "비숍 + 나이트",
+            PieceKind::Dabbaba => "가로/세로로 두 칸 도약",
+            PieceKind::Alfil => "대각선으로 두 칸 도약",
+            PieceKind::Ferz => "대각선으로 한 칸",
+            PieceKind::Centaur => "킹 + 나이트",
+            PieceKind::Camel => "(3, 1) 도약",
+            PieceKind::TempestRook => "대각선 한 칸 이동 후 가로/세로로 슬라이드",
+            PieceKind::Cannon => "가로/세로로 기물을 뛰어넘으며 잡기",
+            PieceKind::Experiment => "실험용 기물",
+            PieceKind::Custom(_) => "사용자 정의 기물",
         }
     }
     
@@ -152,6 +352,13 @@ impl PieceKind {
         }
     }
     
+    /// 빈 보드 기준 고정 행마 오프셋 (보드 상태에 의존하는 기물은 None)
+    pub fn static_offsets(&self, is_white: bool) -> Option<Vec<(i32, i32, MoveType)>> {
+        let mut interpreter = Interpreter::new();
+        interpreter.parse(self.chessembly_script(is_white));
+        interpreter.static_offsets()
+    }
+    
     /// 기물의 Chessembly 행마법 스크립트 반환
     pub fn chessembly_script(&self, is_white: bool) -> &'static str {
         // 백은 +y 방향이 전진, 흑은 -y 방향이 전진
@@ -204,15 +411,8 @@ impl PieceKind {
             }
             PieceKind::Grasshopper => {
                 // 그라스호퍼: 직선으로 기물 넘어서 바로 뒤에 착지
-                // (간단히 구현 - 실제로는 더 복잡한 로직 필요)
-                "do peek(1, 0) while take-move(1, 0);
-                 do peek(-1, 0) while take-move(-1, 0);
-                 do peek(0, 1) while take-move(0, 1);
-                 do peek(0, -1) while take-move(0, -1);
-                 do peek(1, 1) while take-move(1, 1);
-                 do peek(1, -1) while take-move(1, -1);
-                 do peek(-1, 1) while take-move(-1, 1);
-                 do peek(-1, -1) while take-move(-1, -1);"
+                "hop(1, 0); hop(-1, 0); hop(0, 1); hop(0, -1);
+                 hop(1, 1); hop(1, -1); hop(-1, 1); hop(-1, -1);"
             }
             PieceKind::Knightrider => {
                 // 나이트라이더: 나이트 방향으로 슬라이드
@@ -260,10 +460,11 @@ impl PieceKind {
                  take-move(-1, -1) { take-move(-1, 0) repeat(1) } { take-move(0, -1) repeat(1) };"
             }
             PieceKind::Cannon => {
-                "do take(1, 0) enemy(0, 0) not while jump(1, 0) repeat(1);
-                 do take(-1, 0) enemy(0, 0) not while jump(-1, 0) repeat(1);
-                 do take(0, 1) enemy(0, 0) not while jump(0, 1) repeat(1);
-                 do take(0, -1) enemy(0, 0) not while jump(0, -1) repeat(1);
+                // 캐논: 적을 뛰어넘어 잡고 그 뒤 빈 칸에 착지, 아군을 뛰어넘어 이동
+                "hop-catch(1, 0) jump(1, 0) repeat(1);
+                 hop-catch(-1, 0) jump(-1, 0) repeat(1);
+                 hop-catch(0, 1) jump(0, 1) repeat(1);
+                 hop-catch(0, -1) jump(0, -1) repeat(1);
                  do peek(1, 0) while friendly(0, 0) move(1, 0) repeat(1);
                  do peek(-1, 0) while friendly(0, 0) move(-1, 0) repeat(1);
                  do peek(0, 1) while friendly(0, 0) move(0, 1) repeat(1);
@@ -281,7 +482,7 @@ impl PieceKind {
                  "
             }
             PieceKind::Custom(_) => {
-                // 커스텀 기물: 기본적으로 킹처럼
+                // 커스텀 기물: 등록된 스크립트가 없으면 킹처럼 (`GameState::script_for`)
                 "take-move(1, 0); take-move(-1, 0); take-move(0, 1); take-move(0, -1);
                  take-move(1, 1); take-move(1, -1); take-move(-1, 1); take-move(-1, -1);"
             }
@@ -291,15 +492,18 @@ impl PieceKind {
 
 /// 기물
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
     pub id: PieceId,
     pub kind: PieceKind,
     pub owner: PlayerId,
     pub pos: Option<Square>,    // None == 포켓에 있음
     pub stun: i32,              // 스턴 스택 (양수면 움직일 수 없음)
-    pub move_stack: i32,        // 이동 스택 (한 턴에 이동 가능 횟수)
+    pub move_stack: i32,        // 이동 스택 (한 턴에 이동 가능 횟수, 항상 0 이상)
     pub is_royal: bool,         // 로얄 피스 여부
     pub disguise: Option<PieceKind>,  // 위장 (로얄 피스만)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub has_moved: bool,        // 보드 위에서 움직인 적이 있는지 (캐슬링 조건)
 }
 
 impl Piece {
@@ -313,6 +517,7 @@ impl Piece {
             move_stack: 0,
             is_royal: false,
             disguise: None,
+            has_moved: false,
         }
     }
     
@@ -321,11 +526,20 @@ impl Piece {
         self.disguise.as_ref().unwrap_or(&self.kind)
     }
     
-    /// 현재 기물 점수
+    /// 실제 기물 점수 (위장 무시)
+    ///
+    /// 기물 평가(`material`), 잡은 점수, 착수 스턴처럼 실제 종류가 기준인 곳에서 씁니다.
     pub fn score(&self) -> i32 {
         self.kind.score()
     }
     
+    /// 행마 기준 기물 점수 (위장 고려, `effective_kind`와 짝)
+    ///
+    /// 이동 스택 계산(위장, 턴 시작 재충전)과 상대에게 보이는 점수 표시에서 씁니다.
+    pub fn effective_score(&self) -> i32 {
+        self.effective_kind().score()
+    }
+    
     /// 이동 가능 여부
     pub fn can_move(&self) -> bool {
         self.stun == 0 && self.move_stack > 0
@@ -339,10 +553,11 @@ impl Piece {
 
 /// 플레이어가 수행할 수 있는 행동
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     /// 착수: 포켓에서 보드로 기물 배치
     Place {
-        piece_id: PieceId,
+        kind: PieceKind,
         target: Square,
     },
     /// 이동: 기물 이동 (한 턴에 같은 기물 여러 번 가능)
@@ -365,10 +580,142 @@ pub enum Action {
         piece_id: PieceId,
         amount: i32,
     },
+    /// 캐슬링: 킹이 룩 쪽으로 두 칸, 룩은 킹이 지나간 칸으로
+    Castle {
+        king_id: PieceId,
+        rook_id: PieceId,
+        side: CastleSide,
+    },
+}
+
+/// 캐슬링 방향
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CastleSide {
+    /// 킹보다 x가 큰 쪽의 룩
+    KingSide,
+    /// 킹보다 x가 작은 쪽의 룩
+    QueenSide,
+}
+
+// Action 압축 인코딩 비트 배치 (u32)
+//  0..6   출발 칸 (Move)
+//  6..12  도착 칸 (Move, Place)
+//  0..12  대상 기물 번호 (Disguise, Crown, Stun, Castle의 킹: "piece_N"의 N)
+// 12..15  스턴 양 (Stun), 방향 (Castle: 0 킹사이드, 1 퀸사이드)
+// 15..20  기물 코드 (Place, Disguise)
+// 20..23  액션 종류
+// 23..27  칸 좌표의 상위 비트 (출발 x, 출발 y, 도착 x, 도착 y: 8칸을 넘는 보드용)
+const SQUARE_MASK: u32 = 0b111111;
+const FROM_SHIFT: u32 = 0;
+const TO_SHIFT: u32 = 6;
+const PIECE_MASK: u32 = 0xFFF;
+const AMOUNT_SHIFT: u32 = 12;
+const AMOUNT_MASK: u32 = 0b111;
+const KIND_SHIFT: u32 = 15;
+const KIND_MASK: u32 = 0b11111;
+const ACTION_SHIFT: u32 = 20;
+const ACTION_MASK: u32 = 0b111;
+const FROM_HIGH_SHIFT: u32 = 23;
+const TO_HIGH_SHIFT: u32 = 25;
+const SQUARE_HIGH_MASK: u32 = 0b11;
+
+const ACTION_PLACE: u32 = 0;
+const ACTION_MOVE: u32 = 1;
+const ACTION_DISGUISE: u32 = 2;
+const ACTION_CROWN: u32 = 3;
+const ACTION_STUN: u32 = 4;
+const ACTION_CASTLE: u32 = 5;
+
+/// 칸의 하위 6비트 (각 좌표의 하위 3비트: 8x8 보드에서는 이것만으로 충분)
+fn encode_square(square: Square) -> u32 {
+    (((square.y & 7) * 8 + (square.x & 7)) as u32) & SQUARE_MASK
+}
+
+/// 칸 좌표의 상위 비트 (x가 8 이상이면 1번째, y가 8 이상이면 2번째 비트)
+fn encode_square_high(square: Square) -> u32 {
+    ((square.x >> 3) & 1 | ((square.y >> 3) & 1) << 1) as u32
+}
+
+fn decode_square(code: u32, high: u32) -> Square {
+    let index = (code & SQUARE_MASK) as i32;
+    let high = (high & SQUARE_HIGH_MASK) as i32;
+    Square::new(index % 8 + (high & 1) * 8, index / 8 + (high >> 1) * 8)
+}
+
+/// "piece_N" 형식 ID의 번호 (형식이 다르거나 범위를 넘으면 PIECE_MASK)
+fn encode_piece_id(piece_id: &PieceId) -> u32 {
+    piece_id.strip_prefix("piece_")
+        .and_then(|n| n.parse::<u32>().ok())
+        .filter(|n| *n < PIECE_MASK)
+        .unwrap_or(PIECE_MASK)
+}
+
+impl Action {
+    /// 액션을 u32 하나로 압축 (기보 저장/해싱용)
+    ///
+    /// 칸은 `MAX_BOARD_SIZE`(16x16) 보드까지 표현되며, 8x8 보드 안의 칸은 상위 비트가 0이라 기존 코드와 같습니다.
+    /// Custom 기물이나 번호가 4095 이상인 기물은 `decode`에서 복원되지 않습니다.
+    pub fn encode(&self) -> u32 {
+        let (action, body, amount, kind) = match self {
+            Action::Place { kind, target } => {
+                let body = encode_square(*target) << TO_SHIFT | encode_square_high(*target) << TO_HIGH_SHIFT;
+                (ACTION_PLACE, body, 0, kind.code())
+            }
+            Action::Move { from, to, .. } => {
+                let body = encode_square(*from) << FROM_SHIFT | encode_square(*to) << TO_SHIFT
+                    | encode_square_high(*from) << FROM_HIGH_SHIFT | encode_square_high(*to) << TO_HIGH_SHIFT;
+                (ACTION_MOVE, body, 0, Some(0))
+            }
+            Action::Disguise { piece_id, as_kind } => (ACTION_DISGUISE, encode_piece_id(piece_id), 0, as_kind.code()),
+            Action::Crown { piece_id } => (ACTION_CROWN, encode_piece_id(piece_id), 0, Some(0)),
+            Action::Stun { piece_id, amount } => {
+                (ACTION_STUN, encode_piece_id(piece_id), *amount as u32 & AMOUNT_MASK, Some(0))
+            }
+            Action::Castle { king_id, side, .. } => {
+                (ACTION_CASTLE, encode_piece_id(king_id), (*side == CastleSide::QueenSide) as u32, Some(0))
+            }
+        };
+        action << ACTION_SHIFT | kind.unwrap_or(KIND_MASK) << KIND_SHIFT | amount << AMOUNT_SHIFT | body
+    }
+    
+    /// `encode`로 압축된 액션 복원 (기물 ID는 보드 상태에서 조회)
+    pub fn decode(code: u32, state: &GameState) -> Option<Action> {
+        let from = decode_square(code >> FROM_SHIFT, code >> FROM_HIGH_SHIFT);
+        let to = decode_square(code >> TO_SHIFT, code >> TO_HIGH_SHIFT);
+        let amount = ((code >> AMOUNT_SHIFT) & AMOUNT_MASK) as i32;
+        let kind = PieceKind::from_code((code >> KIND_SHIFT) & KIND_MASK);
+        let target_id = || {
+            let id = format!("piece_{}", code & PIECE_MASK);
+            state.pieces.contains_key(&id).then_some(id)
+        };
+        
+        match (code >> ACTION_SHIFT) & ACTION_MASK {
+            ACTION_PLACE => Some(Action::Place { kind: kind?, target: to }),
+            ACTION_MOVE => Some(Action::Move { piece_id: state.board.get(&from)?.clone(), from, to }),
+            ACTION_DISGUISE => Some(Action::Disguise { piece_id: target_id()?, as_kind: kind? }),
+            ACTION_CROWN => Some(Action::Crown { piece_id: target_id()? }),
+            ACTION_STUN => Some(Action::Stun { piece_id: target_id()?, amount }),
+            ACTION_CASTLE => {
+                // 룩은 킹과 같은 줄에서 해당 방향으로 처음 만나는 기물
+                let king_id = target_id()?;
+                let king = state.pieces[&king_id].pos?;
+                let (side, dx) = if amount == 1 { (CastleSide::QueenSide, -1) } else { (CastleSide::KingSide, 1) };
+                let rook_id = (1..state.board_width)
+                    .map(|step| Square::new(king.x + dx * step, king.y))
+                    .take_while(|sq| state.contains_square(*sq))
+                    .find_map(|sq| state.board.get(&sq))?
+                    .clone();
+                Some(Action::Castle { king_id, rook_id, side })
+            }
+            _ => None,
+        }
+    }
 }
 
 /// 포켓에 있는 기물 스펙
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PieceSpec {
     pub kind: PieceKind,
 }
@@ -383,12 +730,305 @@ impl PieceSpec {
     }
 }
 
+/// 상태 변화 이벤트 (리플레이 애니메이션, 기록용)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent {
+    /// 기물이 보드 위에서 이동함 (Catch처럼 제자리면 `from == to`)
+    Moved { piece_id: PieceId, from: Square, to: Square },
+    /// 기물이 잡힘
+    Captured { piece_id: PieceId, kind: PieceKind, at: Square, by: PlayerId },
+    /// 포켓에서 착수함
+    Placed { piece_id: PieceId, kind: PieceKind, at: Square },
+    /// 프로모션함
+    Promoted { piece_id: PieceId, to_kind: PieceKind },
+    /// 턴이 끝나고 다음 플레이어로 넘어감
+    TurnEnded { next: PlayerId },
+}
+
 /// 게임 결과
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameResult {
     Ongoing,
     WhiteWins,
     BlackWins,
+    Draw,
+}
+
+/// 게임 규칙 위반/실패 사유
+///
+/// `Display`는 기존 한국어 오류 문구를 그대로 출력합니다.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    NotYourTurn,
+    /// 이미 착수/계승/위장/스턴을 함 (또는 이동 후 행동)
+    AlreadyActed,
+    /// 이미 다른 행동을 해서 이동 불가
+    OtherActionTaken,
+    /// 이동 중인 기물이 있어 착수 불가
+    PieceMoving,
+    /// 다른 기물이 이동 중이라 이 기물은 이동 불가
+    OtherPieceMoving,
+    SquareOccupied,
+    DropOnPromotionSquare,
+    NoPocket,
+    NotInPocket,
+    DropInCheck,
+    DropMustBlockCheck,
+    DropExposesRoyal,
+    PocketScoreExceeded { total: i32, limit: i32 },
+    PieceNotFound,
+    VictimNotFound,
+    NotYourPiece,
+    Stunned { remaining: i32 },
+    NoMoveStack,
+    /// 도착 칸이 행마 종류의 조건에 맞지 않음
+    IllegalMoveType(MoveType),
+    /// Catch/Shift 대상 칸이 비어 있음
+    MissingTarget(MoveType),
+    /// 출발 칸이 비어 있음
+    NoPieceAtSource,
+    /// 출발 칸에 다른 기물이 있음
+    PieceNotAtSource,
+    /// 해당 칸으로 가는 행마가 없음
+    IllegalMove,
+    UnknownTransition(String),
+    CrownOffBoard,
+    NotRoyal,
+    DisguiseAsKing,
+    NonStandardDisguise,
+    /// `ally`면 아군(1~3), 아니면 적(1)에게 허용되지 않는 스턴 양
+    InvalidStunAmount { ally: bool },
+    PromotionPending,
+    CannotPromote,
+    InvalidPromotionTarget,
+    NotOnBoard,
+    NotOnPromotionSquare,
+    TranscriptOutOfRange,
+    NothingToUndo,
+    NothingToRedo,
+    /// 캐슬링할 수 있는 킹(로얄)과 룩의 배치가 아님
+    InvalidCastle,
+    /// 킹이나 룩이 이미 움직임
+    CastlingPieceMoved,
+    /// 킹과 룩 사이 또는 도착 칸이 막힘
+    CastlingBlocked,
+    /// 킹이 공격받는 칸에서 출발하거나 지나가거나 도착함
+    CastlingThroughAttack,
+    /// 국면 문자열/표기/덤프를 읽지 못함
+    Parse(String),
+    /// `validate_invariants` 실패
+    InvariantViolation(String),
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::NotYourTurn => write!(f, "자신의 턴이 아닙니다"),
+            GameError::AlreadyActed => write!(f, "이번 턴에 이미 행동했습니다"),
+            GameError::OtherActionTaken => write!(f, "이번 턴에 이미 다른 행동을 했습니다"),
+            GameError::PieceMoving => write!(f, "이동 중인 기물이 있습니다"),
+            GameError::OtherPieceMoving => write!(f, "다른 기물이 이동 중입니다"),
+            GameError::SquareOccupied => write!(f, "해당 칸에 이미 기물이 있습니다"),
+            GameError::DropOnPromotionSquare => write!(f, "프로모션 기물은 프로모션 칸에 착수할 수 없습니다"),
+            GameError::NoPocket => write!(f, "포켓이 없습니다"),
+            GameError::NotInPocket => write!(f, "포켓에 해당 기물이 없습니다"),
+            GameError::DropInCheck => write!(f, "체크 상태에서는 착수할 수 없습니다"),
+            GameError::DropMustBlockCheck => write!(f, "체크 상태에서는 체크를 막는 칸에만 착수할 수 있습니다"),
+            GameError::DropExposesRoyal => write!(f, "자신의 로얄 피스가 공격받게 되는 칸에는 착수할 수 없습니다"),
+            GameError::PocketScoreExceeded { total, limit } => write!(f, "포켓 점수 {}점이 제한 {}점을 초과합니다", total, limit),
+            GameError::PieceNotFound => write!(f, "기물을 찾을 수 없습니다"),
+            GameError::VictimNotFound => write!(f, "피해자를 찾을 수 없습니다"),
+            GameError::NotYourPiece => write!(f, "자신의 기물이 아닙니다"),
+            GameError::Stunned { remaining } => write!(f, "스턴 상태입니다 (스턴: {})", remaining),
+            GameError::NoMoveStack => write!(f, "이동 스택이 없습니다"),
+            GameError::IllegalMoveType(move_type) => match move_type {
+                MoveType::Move => write!(f, "Move는 빈 칸으로만 이동할 수 있습니다"),
+                MoveType::Take => write!(f, "Take는 적이 있는 칸으로만 이동할 수 있습니다"),
+                MoveType::Catch => write!(f, "Catch는 적이 있는 칸만 선택할 수 있습니다"),
+                MoveType::Shift => write!(f, "Shift는 다른 기물이 있는 칸만 선택할 수 있습니다"),
+                MoveType::TakeMove => write!(f, "아군 기물이 있는 칸으로 이동할 수 없습니다"),
+                MoveType::Jump => write!(f, "Jump는 빈 칸으로만 이동할 수 있습니다"),
+            },
+            GameError::MissingTarget(move_type) => write!(f, "{:?} 대상이 없습니다", move_type),
+            GameError::NoPieceAtSource => write!(f, "출발 위치에 기물이 없습니다"),
+            GameError::PieceNotAtSource => write!(f, "출발 위치에 해당 기물이 없습니다"),
+            GameError::IllegalMove => write!(f, "이동할 수 없는 칸입니다"),
+            GameError::UnknownTransition(name) => write!(f, "알 수 없는 transition 기물입니다: {}", name),
+            GameError::CrownOffBoard => write!(f, "보드 위의 기물만 계승할 수 있습니다"),
+            GameError::NotRoyal => write!(f, "로얄 피스만 위장할 수 있습니다"),
+            GameError::DisguiseAsKing => write!(f, "킹으로는 위장할 수 없습니다"),
+            GameError::NonStandardDisguise => write!(f, "표준 기물로만 위장할 수 있습니다"),
+            GameError::InvalidStunAmount { ally: true } => write!(f, "아군에게는 1~3 스턴만 부여할 수 있습니다"),
+            GameError::InvalidStunAmount { ally: false } => write!(f, "적에게는 1 스턴만 부여할 수 있습니다"),
+            GameError::PromotionPending => write!(f, "프로모션할 기물을 먼저 선택해야 합니다"),
+            GameError::CannotPromote => write!(f, "프로모션할 수 없는 기물입니다"),
+            GameError::InvalidPromotionTarget => write!(f, "유효하지 않은 프로모션 대상입니다"),
+            GameError::NotOnBoard => write!(f, "보드 위에 없는 기물입니다"),
+            GameError::NotOnPromotionSquare => write!(f, "프로모션 칸에 있지 않습니다"),
+            GameError::TranscriptOutOfRange => write!(f, "기보 범위를 벗어났습니다"),
+            GameError::NothingToUndo => write!(f, "되돌릴 기록이 없습니다"),
+            GameError::NothingToRedo => write!(f, "다시 실행할 기록이 없습니다"),
+            GameError::InvalidCastle => write!(f, "캐슬링할 수 있는 킹과 룩이 아닙니다"),
+            GameError::CastlingPieceMoved => write!(f, "이미 움직인 킹이나 룩으로는 캐슬링할 수 없습니다"),
+            GameError::CastlingBlocked => write!(f, "킹과 룩 사이가 막혀 있습니다"),
+            GameError::CastlingThroughAttack => write!(f, "킹이 공격받는 칸을 지나는 캐슬링은 할 수 없습니다"),
+            GameError::Parse(message) | GameError::InvariantViolation(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// 게임 진행 단계 (평가 가중치, UI 표시용)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamePhase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+/// 진행 단계 판정 기준 (기물 점수는 로얄을 제외한 보드 + 포켓 양측 합계)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseThresholds {
+    /// 보드 위 로얄 외 기물이 이 수 이하이고 점수가 충분하면 오프닝
+    pub opening_max_board_pieces: usize,
+    /// 오프닝으로 보는 최소 기물 점수
+    pub opening_min_material: i32,
+    /// 기물 점수가 이 값 이하면 엔드게임
+    pub endgame_max_material: i32,
+}
+
+impl Default for PhaseThresholds {
+    fn default() -> Self {
+        Self {
+            opening_max_board_pieces: 4,
+            opening_min_material: 40,
+            endgame_max_material: 20,
+        }
+    }
+}
+
+/// 규칙 수치 설정 (점수별 이동 스택, 포켓 점수 제한, 착수 스턴)
+///
+/// 기본값은 stack.md/rule.md의 규칙입니다. 변형 규칙은 `GameState::rules`를 바꿔 만듭니다.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleConfig {
+    /// 점수 구간별 이동 스택 `(최소 점수, 이동 스택)` (최소 점수 오름차순, 점수 이하인 마지막 구간 적용)
+    pub move_stack_table: Vec<(i32, i32)>,
+    /// 어느 구간에도 들지 않는 점수(가장 낮은 구간보다 낮은 점수)의 이동 스택
+    pub default_move_stack: i32,
+    /// 포켓 점수 제한
+    pub max_pocket_score: i32,
+    /// 프로모션할 수 없는 기물의 착수 스턴 (기물 점수 → 스턴, 직렬화하지 않으며 읽을 때 기본값)
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_placement_stun_fn"))]
+    pub placement_stun_fn: fn(i32) -> i32,
+}
+
+fn default_placement_stun_fn() -> fn(i32) -> i32 {
+    |score| score
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self {
+            move_stack_table: vec![(1, 5), (3, 3), (6, 2), (8, 1)],
+            default_move_stack: 1,
+            max_pocket_score: MAX_POCKET_SCORE,
+            placement_stun_fn: default_placement_stun_fn(),
+        }
+    }
+}
+
+impl RuleConfig {
+    /// 점수에 따른 이동 스택
+    pub fn move_stack(&self, score: i32) -> i32 {
+        self.move_stack_table.iter()
+            .take_while(|(min_score, _)| *min_score <= score)
+            .last()
+            .map_or(self.default_move_stack, |(_, stack)| *stack)
+    }
+}
+
+/// 캡처 처리 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaptureRule {
+    /// 잡힌 기물의 이동/스턴 스택을 공격자에게 더함 (stack.md 기본 규칙)
+    #[default]
+    StackTransfer,
+    /// 잡힌 기물을 제거만 함 (스택 이전 없음)
+    Remove,
+    /// 잡힌 기물을 원래 주인의 포켓으로 되돌림 (스택 이전 없음)
+    ReturnToPocket,
+}
+
+/// 체크 상태에서의 착수 허용 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DropInCheckPolicy {
+    /// 체크를 가로막는 칸에만 착수 가능
+    #[default]
+    BlockingOnly,
+    /// 체크 중에는 착수 불가
+    Forbidden,
+    /// 체크와 무관하게 착수 가능 (자기 체크가 되는 착수도 허용)
+    Allowed,
+}
+
+/// 한 턴 안에서 이동과 행동(착수/계승/위장/스턴)의 병행 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TurnActionPolicy {
+    /// 이동과 행동 중 하나만 가능
+    #[default]
+    Exclusive,
+    /// 기물 하나의 이동과 행동 1회를 순서와 무관하게 함께 가능
+    MoveAndAction,
+}
+
+/// 턴 시작 시 이동 스택 재충전 방식
+///
+/// stack.md 규칙은 턴 시작마다 이동 스택을 새로 부여(덮어쓰기)하는 것이라 기본값은 `Full`입니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StackRefillRule {
+    /// 스턴과 무관하게 초기 이동 스택으로 채움
+    #[default]
+    Full,
+    /// 스턴이 남아 있으면 초기 이동 스택을 (스턴 + 1)로 나눈 만큼만 채움 (올림)
+    ProportionalToStun,
+    /// 초기 이동 스택보다 적을 때만 채움 (잡기로 쌓인 스택은 유지)
+    TopUp,
+}
+
+impl StackRefillRule {
+    /// 초기 이동 스택, 현재 스턴과 이동 스택으로 재충전할 이동 스택 계산
+    pub fn refill(&self, initial: i32, stun: i32, current: i32) -> i32 {
+        match self {
+            StackRefillRule::Full => initial,
+            StackRefillRule::TopUp => current.max(initial),
+            StackRefillRule::ProportionalToStun => {
+                let divisor = stun.max(0) + 1;
+                (initial + divisor - 1) / divisor
+            }
+        }
+    }
+}
+
+/// 사용자 정의 기물(`PieceKind::Custom`) 등록 정보
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomPiece {
+    /// 백 기준 행마 스크립트
+    pub white_script: String,
+    /// 흑 기준 행마 스크립트 (백 스크립트를 뒤집은 것)
+    pub black_script: String,
+    /// 기물 점수 (이동 스택, 착수 스턴, 포켓 제한, 평가에 사용)
+    pub score: i32,
 }
 
 /// 유효한 이동 정보
@@ -399,12 +1039,80 @@ pub struct LegalMove {
     pub move_type: MoveType,
     pub is_capture: bool,
     pub tags: Vec<chessembly::ActionTag>,
-    pub catch_to: Square,
+    /// Jump가 잡는 칸 (잡을 대상이 없으면 None)
+    pub catch_to: Option<Square>,
+    /// Catch 후 전진할 칸 (catch-advance)
+    pub advance_to: Option<Square>,
+}
+
+/// 되돌리기 기록: 행동 직전의 국면과 턴 진행 상태
+///
+/// 잡힌 기물(스택 포함)과 턴 종료 시의 스턴 감소/스택 재충전까지 그대로 되돌리도록
+/// 변경분 대신 국면 전체를 담습니다. 이벤트 로그는 길이만 기억해 잘라냅니다.
+#[derive(Debug, Clone)]
+struct MoveRecord {
+    board: HashMap<Square, PieceId>,
+    pockets: HashMap<PlayerId, Vec<PieceSpec>>,
+    pieces: HashMap<PieceId, Piece>,
+    turn: PlayerId,
+    global_state: HashMap<String, i32>,
+    active_piece: Option<PieceId>,
+    action_taken: bool,
+    pending_promotion: Option<PieceId>,
+    captured_value: HashMap<PlayerId, i32>,
+    turns_played: u32,
+    en_passant_target: Option<Square>,
+    en_passant_pending: Option<Square>,
+    next_piece_id: u32,
+    repetitions: HashMap<u64, u32>,
+    events_len: usize,
+    /// 되돌릴 때 잘라낸 이벤트 (다시 실행 기록에서만 사용)
+    events_tail: Vec<GameEvent>,
+}
+
+#[cfg(feature = "serde")]
+fn default_history_enabled() -> bool {
+    true
+}
+
+/// 직렬화(`serde`) 형식 버전 (필드가 바뀌면 올리고, 다른 버전은 읽기를 거부)
+pub const SERDE_VERSION: u32 = 1;
+
+/// `GameState` 직렬화 결과의 `version` 필드 (쓸 때 `SERDE_VERSION`을 적고 읽을 때 검사)
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy)]
+struct SerdeVersion;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SerdeVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(SERDE_VERSION)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SerdeVersion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = u32::deserialize(deserializer)?;
+        if version != SERDE_VERSION {
+            return Err(serde::de::Error::custom(format!(
+                "지원하지 않는 형식 버전입니다: {} (지원: {})", version, SERDE_VERSION,
+            )));
+        }
+        Ok(SerdeVersion)
+    }
 }
 
 /// 게임 상태
+///
+/// `serde` 기능을 켜면 직렬화할 수 있습니다. 평가 설정(`piece_square_tables`, `promotion_stun_fn`)과
+/// 캐시는 저장하지 않으며 읽을 때 기본값이 되고, Zobrist 해시는 읽은 국면에서 다시 계산합니다.
+/// `version` 필드가 `SERDE_VERSION`과 다르거나 없으면 읽기를 거부합니다.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(remote = "Self"))]
 pub struct GameState {
+    #[cfg(feature = "serde")]
+    version: SerdeVersion,
     pub board: HashMap<Square, PieceId>,
     pub pockets: HashMap<PlayerId, Vec<PieceSpec>>,
     pub pieces: HashMap<PieceId, Piece>,
@@ -413,15 +1121,131 @@ pub struct GameState {
     pub active_piece: Option<PieceId>,  // 현재 턴에 이동 중인 기물
     pub action_taken: bool,              // 이번 턴에 행동했는지 (이동 제외)
     pub debug_mode: bool,                // Chessembly 디버그 모드
+    /// 플레이어별 프로모션 구역 (랭크 y값 목록, 기본: 백 y=7, 흑 y=0)
+    pub promotion_zones: HashMap<PlayerId, Vec<i32>>,
+    /// 캡처 처리 방식
+    pub capture_rule: CaptureRule,
+    /// 체크 상태에서의 착수 허용 방식
+    pub drop_in_check: DropInCheckPolicy,
+    /// 이동으로 프로모션 칸에 도달해 프로모션을 기다리는 기물
+    pub pending_promotion: Option<PieceId>,
+    /// 턴 시작 시 이동 스택 재충전 방식
+    pub stack_refill: StackRefillRule,
+    /// 한 턴 안에서 이동과 행동의 병행 방식
+    pub action_policy: TurnActionPolicy,
+    /// 플레이어별 이번 게임에서 잡은 기물 점수 누계
+    pub captured_value: HashMap<PlayerId, i32>,
+    /// 기물 종류별 칸 보너스 (인덱스 `y * 8 + x`, y는 자기 진영 기준 랭크; 없으면 중앙 보너스)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub piece_square_tables: HashMap<PieceKind, [i32; 64]>,
+    /// 프로모션 직후 새 기물에 추가할 스턴 (None이면 스택만 계승)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub promotion_stun_fn: Option<fn(&PieceKind) -> i32>,
+    /// 최대 턴 수 (도달하면 `game_status`가 기물 점수로 판정, None이면 제한 없음)
+    pub max_turns: Option<u32>,
+    /// 보드 가로 칸 수 (기본 8, `with_size`로 지정)
+    pub board_width: i32,
+    /// 보드 세로 칸 수 (기본 8, `with_size`로 지정)
+    pub board_height: i32,
+    /// 이름별 사용자 정의 기물 (`register_custom_piece`로 등록, 없으면 킹처럼 이동하고 3점)
+    pub custom_pieces: HashMap<String, CustomPiece>,
+    /// 알 수 없는 transition 기물 이름을 오류로 처리 (false면 무시, 디버그 모드에서 경고 출력)
+    pub strict_transitions: bool,
+    /// 로얄 기물이 적이 공격하는 칸으로 이동하지 못하게 함 (체크 여부와 무관)
+    pub royal_safety: bool,
+    /// 활성 기물이 더 움직일 수 없게 되면 이동 직후 턴을 자동으로 넘김 (프로모션 대기 중이면 넘기지 않음)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub auto_end_turn: bool,
+    /// 진행 단계 판정 기준
+    pub phase_thresholds: PhaseThresholds,
+    /// 규칙 수치 (점수별 이동 스택, 포켓 점수 제한, 착수 스턴)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules: RuleConfig,
+    /// 지금까지 끝난 턴 수
+    pub turns_played: u32,
+    /// 지금까지 일어난 상태 변화 (`clone_bare`는 비움)
+    pub events: Vec<GameEvent>,
+    /// 이번 턴에 앙파상으로 잡을 수 있는 칸 (직전 턴에 폰이 두 칸 이상 전진하며 지나친 칸)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub en_passant_target: Option<Square>,
+    /// 이번 턴에 폰이 두 칸 이상 전진하며 지나친 칸 (턴 종료 시 `en_passant_target`이 됨)
+    #[cfg_attr(feature = "serde", serde(default))]
+    en_passant_pending: Option<Square>,
     next_piece_id: u32,
+    /// 되돌리기/다시 실행 기록 (복제 비용을 줄이려고 공유, 스레드 간에 넘길 수 있게 `Arc`)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: Vec<Arc<MoveRecord>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    redo_stack: Vec<Arc<MoveRecord>>,
+    /// 행동을 기록할지 (탐색용 복사본은 끔)
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_history_enabled"))]
+    history_enabled: bool,
+    /// 행동 실행기가 증분 갱신하는 Zobrist 해시 (`zobrist_key`)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    zobrist: u64,
+    /// 턴 시작 국면(`zobrist_key`)별 등장 횟수 (되돌릴 수 없는 행동이 있으면 비움)
+    repetitions: HashMap<u64, u32>,
+    /// 플레이어별 공격 칸 캐시 (보드 변경/턴 종료 시 무효화)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attacked_by: OnceCell<HashMap<PlayerId, HashSet<Square>>>,
+    /// 공격 칸 캐시를 새로 계산한 횟수 (디버그 빌드 전용)
+    #[cfg(debug_assertions)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attack_computations: Cell<usize>,
+}
+
+// `serde(remote = "Self")`로 만든 함수를 감싸 읽은 뒤 저장하지 않는 Zobrist 해시를 다시 계산
+#[cfg(feature = "serde")]
+impl serde::Serialize for GameState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameState::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for GameState {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut state = GameState::deserialize(deserializer)?;
+        state.refresh_zobrist();
+        Ok(state)
+    }
 }
 
-/// 포켓 점수 제한
+// 탐색 작업 등 다른 스레드로 국면을 넘길 수 있어야 하므로 `GameState`가 `Send`인지 컴파일 시점에 검사
+const _: () = {
+    fn assert_send<T: Send>() {}
+    let _ = assert_send::<GameState>;
+};
+
+/// 국면 비교용 칸별 기물 상태 (칸, 종류, 주인, 로얄, 위장, 스턴, 이동 스택)
+type PieceSignature = (Square, PieceKind, PlayerId, bool, Option<PieceKind>, i32, i32, bool);
+
+/// 기본 포켓 점수 제한 (`RuleConfig::max_pocket_score`의 기본값)
 pub const MAX_POCKET_SCORE: i32 = 39;
 
+/// 국면 문자열 형식 버전 (형식이 바뀌면 올리고, 다른 버전은 읽기를 거부)
+pub const FORMAT_VERSION: u32 = 3;
+
+/// `가로x세로` 보드 크기 표기 읽기 (각각 1 ~ `MAX_BOARD_SIZE`)
+fn parse_board_size(text: &str) -> Result<(i32, i32), String> {
+    text.split_once('x')
+        .and_then(|(w, h)| Some((w.parse::<i32>().ok()?, h.parse::<i32>().ok()?)))
+        .filter(|(w, h)| (1..=MAX_BOARD_SIZE).contains(w) && (1..=MAX_BOARD_SIZE).contains(h))
+        .ok_or(format!("보드 크기 표기가 올바르지 않습니다: {}", text))
+}
+
 impl GameState {
     pub fn new(starting_player: PlayerId) -> Self {
+        Self::with_size(8, 8, starting_player)
+    }
+    
+    /// 크기를 지정한 보드로 새 게임 (킹은 첫/마지막 랭크의 가운데, 프로모션 구역은 끝 랭크)
+    ///
+    /// 국면 표기와 국면 문자열, 직렬화, `Action::encode`는 `MAX_BOARD_SIZE`(16x16)까지의 보드를 지원합니다.
+    pub fn with_size(width: i32, height: i32, starting_player: PlayerId) -> Self {
         let mut state = Self {
+            #[cfg(feature = "serde")]
+            version: SerdeVersion,
             board: HashMap::new(),
             pockets: HashMap::new(),
             pieces: HashMap::new(),
@@ -430,29 +1254,64 @@ impl GameState {
             active_piece: None,
             action_taken: false,
             debug_mode: false,
+            promotion_zones: HashMap::from([(0, vec![height - 1]), (1, vec![0])]),
+            capture_rule: CaptureRule::default(),
+            drop_in_check: DropInCheckPolicy::default(),
+            pending_promotion: None,
+            stack_refill: StackRefillRule::default(),
+            action_policy: TurnActionPolicy::default(),
+            captured_value: HashMap::new(),
+            piece_square_tables: HashMap::new(),
+            promotion_stun_fn: None,
+            max_turns: None,
+            board_width: width,
+            board_height: height,
+            custom_pieces: HashMap::new(),
+            strict_transitions: false,
+            royal_safety: false,
+            auto_end_turn: false,
+            phase_thresholds: PhaseThresholds::default(),
+            rules: RuleConfig::default(),
+            turns_played: 0,
+            events: Vec::new(),
+            en_passant_target: None,
+            en_passant_pending: None,
             next_piece_id: 0,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            history_enabled: true,
+            zobrist: 0,
+            repetitions: HashMap::new(),
+            attacked_by: OnceCell::new(),
+            #[cfg(debug_assertions)]
+            attack_computations: Cell::new(0),
         };
         
         // 초기 킹 배치 (rule.md: e1(백), e8(흑))
         state.setup_initial_kings();
+        state.refresh_zobrist();
+        state.reset_repetitions();
         state
     }
     
     fn setup_initial_kings(&mut self) {
-        // 백 킹 (e1)
+        let file = self.board_width / 2;
+        
+        // 백 킹 (8x8에서 e1)
         let white_king = self.create_piece(PieceKind::King, 0);
         let white_king_id = white_king.id.clone();
         self.pieces.insert(white_king_id.clone(), white_king);
-        self.place_king(&white_king_id, Square::new(4, 0)); // e1
+        self.place_king(&white_king_id, Square::new(file, 0));
         
-        // 흑 킹 (e8)
+        // 흑 킹 (8x8에서 e8)
         let black_king = self.create_piece(PieceKind::King, 1);
         let black_king_id = black_king.id.clone();
         self.pieces.insert(black_king_id.clone(), black_king);
-        self.place_king(&black_king_id, Square::new(4, 7)); // e8
+        self.place_king(&black_king_id, Square::new(file, self.board_height - 1));
     }
     
     fn place_king(&mut self, piece_id: &PieceId, square: Square) {
+        self.invalidate_caches();
         if let Some(piece) = self.pieces.get_mut(piece_id) {
             piece.pos = Some(square);
             piece.is_royal = true;
@@ -470,32 +1329,27 @@ impl GameState {
     }
     
     /// 포켓 초기화 (점수 합계 검증)
-    pub fn setup_pocket(&mut self, player: PlayerId, specs: Vec<PieceSpec>) -> Result<(), String> {
-        let total_score: i32 = specs.iter().map(|s| s.score()).sum();
-        if total_score > MAX_POCKET_SCORE {
-            return Err(format!(
-                "포켓 점수 {}점이 제한 {}점을 초과합니다",
-                total_score, MAX_POCKET_SCORE
-            ));
+    pub fn setup_pocket(&mut self, player: PlayerId, specs: Vec<PieceSpec>) -> Result<(), GameError> {
+        let total_score: i32 = specs.iter().map(|s| self.score_of(&s.kind)).sum();
+        if total_score > self.rules.max_pocket_score {
+            return Err(GameError::PocketScoreExceeded { total: total_score, limit: self.rules.max_pocket_score });
         }
+        self.toggle_zobrist(&[], &[player]);
         self.pockets.insert(player, specs);
+        self.toggle_zobrist(&[], &[player]);
         Ok(())
     }
 
     /// 점수 제한 없이 포켓 설정 (실험용)
     pub fn setup_pocket_unchecked(&mut self, player: PlayerId, specs: Vec<PieceSpec>) {
+        self.toggle_zobrist(&[], &[player]);
         self.pockets.insert(player, specs);
+        self.toggle_zobrist(&[], &[player]);
     }
     
-    /// 점수에 따른 이동 스택 계산 (stack.md)
-    pub fn initial_move_stack(score: i32) -> i32 {
-        match score {
-            1..=2 => 5,
-            3..=5 => 3,
-            6..=7 => 2,
-            _ if score >= 8 => 1,
-            _ => 1,
-        }
+    /// 점수에 따른 이동 스택 계산 (`rules.move_stack_table`, 기본은 stack.md)
+    pub fn initial_move_stack(&self, score: i32) -> i32 {
+        self.rules.move_stack(score)
     }
     
     /// 착수 시 스턴 스택 계산
@@ -503,58 +1357,124 @@ impl GameState {
         let kind = &piece.kind;
         
         if kind.can_promote() {
-            // 프로모션 가능 기물: 거리에 따라 스턴 조정
-            let distance = kind.distance_to_promotion(square, piece.is_white());
+            // 프로모션 가능 기물: 가장 가까운 프로모션 칸과의 거리에 따라 스턴 조정
+            let distance = self.distance_to_promotion_zone(piece.owner, square);
             let max_stun = kind.max_promotion_stun();
             // 가까울수록 높은 스턴 (거리 0 = max, 거리 max = 0)
-            let max_distance = 7; // 폰 기준
+            let max_distance = (self.board_height - 1).max(1); // 폰 기준
             max_stun - (max_stun * distance / max_distance)
         } else {
-            // 일반 기물: 점수만큼 스턴
-            piece.score()
+            // 일반 기물: 점수에 따른 스턴 (기본은 점수만큼, 착수 시점에는 위장이 없어 실제 점수)
+            (self.rules.placement_stun_fn)(self.score_of(kind)).max(0)
+        }
+    }
+    
+    /// 전역 상태 값 설정 (스크립트의 `if-state`/`set-state`가 읽고 쓰는 값, 캐시 무효화)
+    pub fn set_global(&mut self, key: &str, value: i32) {
+        self.invalidate_caches();
+        self.global_state.insert(key.to_string(), value);
+    }
+    
+    /// 전역 상태 값 (설정되지 않은 키는 0)
+    pub fn get_global(&self, key: &str) -> i32 {
+        self.global_state.get(key).copied().unwrap_or(0)
+    }
+    
+    /// 설정된 전역 상태 키 목록 (정렬됨)
+    pub fn global_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.global_state.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+    
+    /// 프로모션 구역 설정 (랭크 y값 목록)
+    pub fn set_promotion_zone(&mut self, player: PlayerId, ranks: Vec<i32>) {
+        self.promotion_zones.insert(player, ranks);
+    }
+    
+    /// 해당 칸이 플레이어의 프로모션 구역 안인지 (구역 미설정 시 기본 랭크)
+    pub fn is_promotion_square(&self, kind: &PieceKind, square: Square, player: PlayerId) -> bool {
+        match self.promotion_zones.get(&player) {
+            Some(ranks) => kind.can_promote() && ranks.contains(&square.y),
+            None => kind.is_promotion_square(square, player == 0),
         }
     }
     
+    /// 가장 가까운 프로모션 랭크까지의 거리
+    fn distance_to_promotion_zone(&self, player: PlayerId, square: Square) -> i32 {
+        self.promotion_zones
+            .get(&player)
+            .and_then(|ranks| ranks.iter().map(|y| (y - square.y).abs()).min())
+            .unwrap_or_else(|| PieceKind::Pawn.distance_to_promotion(square, player == 0))
+    }
+    
     /// 착수 가능 여부 확인
-    pub fn can_place(&self, player: PlayerId, kind: &PieceKind, target: Square) -> Result<(), String> {
+    pub fn can_place(&self, player: PlayerId, kind: &PieceKind, target: Square) -> Result<(), GameError> {
         // 자신의 턴인지
         if self.turn != player {
-            return Err("자신의 턴이 아닙니다".to_string());
+            return Err(GameError::NotYourTurn);
         }
         
         // 이미 다른 행동을 했는지
         if self.action_taken {
-            return Err("이번 턴에 이미 행동했습니다".to_string());
+            return Err(GameError::AlreadyActed);
         }
         
-        // 이동 중인 기물이 있는지
-        if self.active_piece.is_some() {
-            return Err("이동 중인 기물이 있습니다".to_string());
+        // 이동 중인 기물이 있는지 (병행 허용 시 무시)
+        if self.active_piece.is_some() && self.action_policy == TurnActionPolicy::Exclusive {
+            return Err(GameError::PieceMoving);
         }
         
         // 해당 칸이 비어있는지
         if self.board.contains_key(&target) {
-            return Err("해당 칸에 이미 기물이 있습니다".to_string());
+            return Err(GameError::SquareOccupied);
         }
         
         // 프로모션 기물은 프로모션 칸에 착수 불가
-        let is_white = player == 0;
-        if kind.is_promotion_square(target, is_white) {
-            return Err("프로모션 기물은 프로모션 칸에 착수할 수 없습니다".to_string());
+        if self.is_promotion_square(kind, target, player) {
+            return Err(GameError::DropOnPromotionSquare);
         }
         
         // 포켓에 해당 기물이 있는지
-        let pocket = self.pockets.get(&player).ok_or("포켓이 없습니다")?;
+        let pocket = self.pockets.get(&player).ok_or(GameError::NoPocket)?;
         if !pocket.iter().any(|s| &s.kind == kind) {
-            return Err("포켓에 해당 기물이 없습니다".to_string());
+            return Err(GameError::NotInPocket);
+        }
+        
+        // 체크 상태에서의 착수 제한
+        if self.drop_in_check != DropInCheckPolicy::Allowed && self.is_check(player) {
+            match self.drop_in_check {
+                DropInCheckPolicy::Forbidden => {
+                    return Err(GameError::DropInCheck);
+                }
+                DropInCheckPolicy::BlockingOnly => {
+                    if !self.check_blocking_squares(player).contains(&target) {
+                        return Err(GameError::DropMustBlockCheck);
+                    }
+                }
+                DropInCheckPolicy::Allowed => {}
+            }
+        }
+        
+        // 착수한 기물이 도약 기물의 발판이 되어 자신의 로얄 피스가 공격받게 되는 경우
+        if self.drop_in_check != DropInCheckPolicy::Allowed
+            && self.with_virtual_piece(kind.clone(), player, target).is_check(player)
+        {
+            return Err(GameError::DropExposesRoyal);
         }
         
         Ok(())
     }
     
     /// 착수 실행
-    pub fn place_piece(&mut self, player: PlayerId, kind: PieceKind, target: Square) -> Result<PieceId, String> {
+    ///
+    /// 착수는 행동으로 취급되어(`action_taken`), 기본 정책에서는 스턴 0으로 놓인 기물을 포함해
+    /// 그 턴에 어떤 기물도 이동할 수 없습니다. 착수 후 이동을 허용하려면 `TurnActionPolicy::MoveAndAction`을 씁니다.
+    pub fn place_piece(&mut self, player: PlayerId, kind: PieceKind, target: Square) -> Result<PieceId, GameError> {
         self.can_place(player, &kind, target)?;
+        let record = self.history_record();
+        self.invalidate_caches();
+        self.toggle_zobrist(&[], &[player]);
         
         // 포켓에서 기물 제거
         if let Some(pocket) = self.pockets.get_mut(&player) {
@@ -567,51 +1487,55 @@ impl GameState {
         let mut piece = self.create_piece(kind, player);
         let piece_id = piece.id.clone();
         
-        // 스택 초기화
+        // 스택 초기화 (착수 직후에는 위장이 없으므로 실제 점수 기준)
         piece.stun = self.calculate_placement_stun(&piece, target);
-        piece.move_stack = Self::initial_move_stack(piece.score());
+        piece.move_stack = self.initial_move_stack(self.score_of(&piece.kind));
         piece.pos = Some(target);
         
+        self.events.push(GameEvent::Placed { piece_id: piece_id.clone(), kind: piece.kind.clone(), at: target });
         self.pieces.insert(piece_id.clone(), piece);
         self.board.insert(target, piece_id.clone());
         self.action_taken = true;
+        self.toggle_zobrist(std::slice::from_ref(&piece_id), &[player]);
+        self.repetitions.clear();
         
+        self.push_history(record);
         Ok(piece_id)
     }
     
     /// 이동 가능 여부 확인
-    pub fn can_move_piece(&self, player: PlayerId, piece_id: &PieceId, _from: Square, to: Square, move_type: MoveType) -> Result<(), String> {
+    pub fn can_move_piece(&self, player: PlayerId, piece_id: &PieceId, _from: Square, to: Square, move_type: MoveType) -> Result<(), GameError> {
         // 자신의 턴인지
         if self.turn != player {
-            return Err("자신의 턴이 아닙니다".to_string());
+            return Err(GameError::NotYourTurn);
         }
         
-        // 다른 행동을 했는지 (이동은 예외)
-        if self.action_taken {
-            return Err("이번 턴에 이미 다른 행동을 했습니다".to_string());
+        // 다른 행동을 했는지 (이동은 예외, 병행 허용 시 무시)
+        if self.action_taken && self.action_policy == TurnActionPolicy::Exclusive {
+            return Err(GameError::OtherActionTaken);
         }
         
         // 이미 다른 기물이 이동 중인지
         if let Some(ref active) = self.active_piece {
             if active != piece_id {
-                return Err("다른 기물이 이동 중입니다".to_string());
+                return Err(GameError::OtherPieceMoving);
             }
         }
         
         // 기물 존재 확인
-        let piece = self.pieces.get(piece_id).ok_or("기물을 찾을 수 없습니다")?;
+        let piece = self.pieces.get(piece_id).ok_or(GameError::PieceNotFound)?;
         
         // 자신의 기물인지
         if piece.owner != player {
-            return Err("자신의 기물이 아닙니다".to_string());
+            return Err(GameError::NotYourPiece);
         }
         
         // 이동 가능한지 (스턴 0, 이동 스택 > 0)
         if !piece.can_move() {
             if piece.stun > 0 {
-                return Err(format!("스턴 상태입니다 (스턴: {})", piece.stun));
+                return Err(GameError::Stunned { remaining: piece.stun });
             } else {
-                return Err("이동 스택이 없습니다".to_string());
+                return Err(GameError::NoMoveStack);
             }
         }
         
@@ -640,37 +1564,37 @@ impl GameState {
             MoveType::Move => {
                 // Move: 빈 칸으로만 이동 가능
                 if !is_target_empty {
-                    return Err("Move는 빈 칸으로만 이동할 수 있습니다".to_string());
+                    return Err(GameError::IllegalMoveType(MoveType::Move));
                 }
             }
             MoveType::Take => {
                 // Take: 적이 있는 칸으로만 이동 가능
                 if !has_enemy {
-                    return Err("Take는 적이 있는 칸으로만 이동할 수 있습니다".to_string());
+                    return Err(GameError::IllegalMoveType(MoveType::Take));
                 }
             }
             MoveType::Catch => {
                 // Catch: 적이 있어야 함 (제자리에서 잡기)
                 if !has_enemy {
-                    return Err("Catch는 적이 있는 칸만 선택할 수 있습니다".to_string());
+                    return Err(GameError::IllegalMoveType(MoveType::Catch));
                 }
             }
             MoveType::Shift => {
                 // Shift: 아군 또는 적이 있어야 함
                 if is_target_empty {
-                    return Err("Shift는 다른 기물이 있는 칸만 선택할 수 있습니다".to_string());
+                    return Err(GameError::IllegalMoveType(MoveType::Shift));
                 }
             }
             MoveType::TakeMove => {
                 // TakeMove: 빈 칸 또는 적
                 if has_friendly {
-                    return Err("아군 기물이 있는 칸으로 이동할 수 없습니다".to_string());
+                    return Err(GameError::IllegalMoveType(MoveType::TakeMove));
                 }
             }
             MoveType::Jump => {
                 // Jump: 빈 칸으로만 이동 (take-jump 조합용)
                 if !is_target_empty {
-                    return Err("Jump는 빈 칸으로만 이동할 수 있습니다".to_string());
+                    return Err(GameError::IllegalMoveType(MoveType::Jump));
                 }
             }
         }
@@ -680,39 +1604,25 @@ impl GameState {
 
     /// 액션 태그 처리 (이동 후 적용)
     fn apply_action_tags(&mut self, piece_id: &PieceId, tags: &[chessembly::ActionTag]) {
+        self.invalidate_caches();
         for tag in tags {
             match tag.tag_type {
                 chessembly::ActionTagType::Transition => {
                     // 기물 변환
                     if let Some(piece_name) = &tag.piece_name {
                         if let Some(piece) = self.pieces.get_mut(piece_id) {
-                            // 문자열을 PieceKind로 변환
-                            let new_kind = match piece_name.to_lowercase().as_str() {
-                                "pawn" => PieceKind::Pawn,
-                                "king" => PieceKind::King,
-                                "queen" => PieceKind::Queen,
-                                "rook" => PieceKind::Rook,
-                                "knight" => PieceKind::Knight,
-                                "bishop" => PieceKind::Bishop,
-                                "amazon" => PieceKind::Amazon,
-                                "grasshopper" => PieceKind::Grasshopper,
-                                "knightrider" => PieceKind::Knightrider,
-                                "archbishop" => PieceKind::Archbishop,
-                                "dabbaba" => PieceKind::Dabbaba,
-                                "alfil" => PieceKind::Alfil,
-                                "ferz" => PieceKind::Ferz,
-                                "centaur" => PieceKind::Centaur,
-                                "camel" => PieceKind::Camel,
-                                "tempestrook" => PieceKind::TempestRook,
-                                "cannon" => PieceKind::Cannon,
-                                "experiment" => PieceKind::Experiment,
-                                _ => continue,
+                            // 문자열을 PieceKind로 변환 (엄격 모드에서는 이동 전에 이미 거부됨)
+                            let Some(new_kind) = PieceKind::from_name(piece_name) else {
+                                if self.debug_mode {
+                                    chessembly::log_debug(&format!("[Engine] 알 수 없는 transition 기물 무시: {}", piece_name));
+                                }
+                                continue;
                             };
                             
                             // 기물 종류 변환
                             piece.kind = new_kind.clone();
                             // 이동 스택도 새 기물 점수에 맞게 조정
-                            piece.move_stack = Self::initial_move_stack(new_kind.score());
+                            piece.move_stack = self.rules.move_stack(Self::score_in(&self.custom_pieces, &new_kind));
                         }
                     }
                 }
@@ -720,22 +1630,54 @@ impl GameState {
                     // 전역 상태 설정
                     self.global_state.insert(tag.key.clone(), tag.value);
                 }
+                chessembly::ActionTagType::AddState => {
+                    let value = self.global_state.entry(tag.key.clone()).or_insert(0);
+                    *value = value.saturating_add(tag.value);
+                }
+                chessembly::ActionTagType::SubState => {
+                    let value = self.global_state.entry(tag.key.clone()).or_insert(0);
+                    *value = value.saturating_sub(tag.value);
+                }
             }
         }
     }
 
-    pub fn move_piece_by_legal_moves(&mut self, mv: LegalMove) -> Result<Option<PieceId>, String> {
+    /// `LegalMove`로 이동 실행 (캡처, 액션 태그, 프로모션 대기 처리까지 담당하는 기준 경로)
+    pub fn move_piece_by_legal_moves(&mut self, mv: LegalMove) -> Result<Option<PieceId>, GameError> {
         let from = mv.from;
         let to = mv.to;
         let tags = mv.tags.clone(); // 태그 복사
     
         // 출발 위치의 기물 확인
-        let piece_id = self.board.get(&from).cloned().ok_or("출발 위치에 기물이 없습니다")?;
-        let piece = self.pieces.get(&piece_id).cloned().ok_or("기물을 찾을 수 없습니다")?;
+        let piece_id = self.board.get(&from).cloned().ok_or(GameError::NoPieceAtSource)?;
+        let piece = self.pieces.get(&piece_id).cloned().ok_or(GameError::PieceNotFound)?;
         let player = piece.owner;
     
         // 이동 가능성 검사 (기존 검증 로직 재사용)
         self.can_move_piece(player, &piece_id, from, to, mv.move_type)?;
+        if self.strict_transitions {
+            let unknown = tags.iter()
+                .filter(|tag| tag.tag_type == chessembly::ActionTagType::Transition)
+                .filter_map(|tag| tag.piece_name.as_ref())
+                .find(|name| PieceKind::from_name(name).is_none());
+            if let Some(name) = unknown {
+                return Err(GameError::UnknownTransition(name.clone()));
+            }
+        }
+        let record = self.history_record();
+        self.invalidate_caches();
+        // 이번 턴에 이미 움직인 폰이 다시 곧장 전진하면 두 칸 이상 전진한 것
+        let forward = if piece.is_white() { 1 } else { -1 };
+        let double_step = piece.effective_kind() == &PieceKind::Pawn
+            && self.active_piece.as_ref() == Some(&piece_id)
+            && mv.move_type == MoveType::Move
+            && to == Square::new(from.x, from.y + forward);
+        
+        // 이동으로 바뀔 수 있는 기물(이동 기물, 도착/잡기 칸의 기물)과 포켓의 해시를 빼고 끝에서 다시 더함
+        let mut touched = vec![piece_id.clone()];
+        touched.extend(self.board.get(&to).cloned());
+        touched.extend(mv.catch_to.and_then(|sq| self.board.get(&sq).cloned()));
+        self.toggle_zobrist(&touched, &[0, 1]);
     
         let mut captured_id: Option<PieceId> = None;
     
@@ -747,12 +1689,13 @@ impl GameState {
                     p.pos = Some(to);
                     p.move_stack -= 1;
                 }
+                self.debug_assert_piece_at(&piece_id, to);
             }
     
             MoveType::Take | MoveType::TakeMove => {
                 if let Some(victim_id) = self.board.get(&to).cloned() {
                     captured_id = Some(victim_id.clone());
-                    self.capture(&piece_id, &victim_id)?;
+                    self.capture_inner(&piece_id, &victim_id)?;
                 }
     
                 self.board.remove(&from);
@@ -764,17 +1707,29 @@ impl GameState {
                         p.move_stack -= 1;
                     }
                 }
+                self.debug_assert_piece_at(&piece_id, to);
             }
     
             MoveType::Catch => {
                 // 제자리에서의 잡기: 대상은 `to` 칸에 있어야 함
                 if let Some(victim_id) = self.board.get(&to).cloned() {
                     captured_id = Some(victim_id.clone());
-                    self.capture(&piece_id, &victim_id)?;
+                    self.capture_inner(&piece_id, &victim_id)?;
                     // 공격자는 자리 이동하지 않음 (capture()가 스택 갱신 및 제거 처리)
                 } else {
-                    return Err("Catch 대상이 없습니다".to_string());
+                    return Err(GameError::MissingTarget(MoveType::Catch));
+                }
+                
+                // catch-advance: 잡은 뒤 빈 칸으로 전진 (추가 이동 스택 소모 없음)
+                if let Some(advance) = mv.advance_to.filter(|sq| self.contains_square(*sq) && !self.board.contains_key(sq)) {
+                    self.board.remove(&from);
+                    self.board.insert(advance, piece_id.clone());
+                    if let Some(p) = self.pieces.get_mut(&piece_id) {
+                        p.pos = Some(advance);
+                    }
                 }
+                let stay = self.pieces.get(&piece_id).and_then(|p| p.pos).unwrap_or(from);
+                self.debug_assert_piece_at(&piece_id, stay);
             }
     
             MoveType::Shift => {
@@ -791,9 +1746,12 @@ impl GameState {
                     }
                     if let Some(tp) = self.pieces.get_mut(&target_piece_id) {
                         tp.pos = Some(from);
+                        tp.has_moved = true;
                     }
+                    self.debug_assert_piece_at(&piece_id, to);
+                    self.debug_assert_piece_at(&target_piece_id, from);
                 } else {
-                    return Err("Shift 대상이 없습니다".to_string());
+                    return Err(GameError::MissingTarget(MoveType::Shift));
                 }
             }
     
@@ -806,242 +1764,412 @@ impl GameState {
                     p.move_stack -= 1;
                 }
     
-                // 만약 `catch_to`에 캡처 대상 좌표가 담겨있다면 그 칸의 기물을 제거
-                // (현재 코드에서 빈 값을 (0,0)으로 처리하고 있으므로 정확한 sentinel 처리 필요)
-                if mv.catch_to.is_valid() {
-                    if let Some(victim_id) = self.board.get(&mv.catch_to).cloned() {
+                // `catch_to`에 캡처 대상 좌표가 담겨있다면 그 칸의 기물을 제거
+                if let Some(catch_to) = mv.catch_to.filter(|sq| self.contains_square(*sq)) {
+                    if let Some(victim_id) = self.board.get(&catch_to).cloned() {
                         // 캡처 규칙 적용
                         captured_id = Some(victim_id.clone());
-                        self.capture(&piece_id, &victim_id)?;
+                        self.capture_inner(&piece_id, &victim_id)?;
                     }
                 }
+                self.debug_assert_piece_at(&piece_id, to);
             }
         }
     
+        if let Some(p) = self.pieces.get_mut(&piece_id) {
+            if let Some(landed) = p.pos {
+                p.has_moved |= landed != from;
+                self.events.push(GameEvent::Moved { piece_id: piece_id.clone(), from, to: landed });
+            }
+        }
+        
         // 활성 이동 기물 설정
         self.active_piece = Some(piece_id.clone());
+        if double_step {
+            self.en_passant_pending = Some(from);
+        }
         
         // 액션 태그 적용 (이동 완료 후)
         self.apply_action_tags(&piece_id, &tags);
+        self.update_pending_promotion(&piece_id);
+        self.toggle_zobrist(&touched, &[0, 1]);
     
+        self.push_history(record);
+        self.auto_end_if_exhausted();
         Ok(captured_id)
     }
     
-    /// 이동 실행 (캡처 포함)
-    pub fn move_piece(&mut self, player: PlayerId, piece_id: &PieceId, from: Square, to: Square, move_type: MoveType) -> Result<Option<PieceId>, String> {
-        self.can_move_piece(player, piece_id, from, to, move_type)?;
+    /// 캐슬링 검사 후 (킹 출발, 킹 도착, 룩 출발, 룩 도착) 칸
+    ///
+    /// 킹은 로얄 피스, 룩은 `PieceKind::Rook`이어야 하며 둘 다 움직인 적이 없어야 합니다.
+    /// 둘 사이와 두 도착 칸은 (서로를 제외하고) 비어 있어야 하고, 킹의 출발/경유/도착 칸은
+    /// 상대가 공격하는 칸(스크립트의 danger 칸)이 아니어야 합니다.
+    fn castle_squares(&self, player: PlayerId, king_id: &PieceId, rook_id: &PieceId, side: CastleSide) -> Result<(Square, Square, Square, Square), GameError> {
+        if self.turn != player {
+            return Err(GameError::NotYourTurn);
+        }
+        if self.action_taken && self.action_policy == TurnActionPolicy::Exclusive {
+            return Err(GameError::OtherActionTaken);
+        }
+        if self.active_piece.is_some() {
+            return Err(GameError::PieceMoving);
+        }
         
-        let mut captured_id: Option<PieceId> = None;
+        let king = self.pieces.get(king_id).ok_or(GameError::PieceNotFound)?;
+        let rook = self.pieces.get(rook_id).ok_or(GameError::PieceNotFound)?;
+        if king.owner != player || rook.owner != player {
+            return Err(GameError::NotYourPiece);
+        }
+        if !king.can_move() {
+            return Err(if king.stun > 0 { GameError::Stunned { remaining: king.stun } } else { GameError::NoMoveStack });
+        }
+        let (Some(king_from), Some(rook_from)) = (king.pos, rook.pos) else {
+            return Err(GameError::InvalidCastle);
+        };
+        let dx = match side {
+            CastleSide::KingSide => 1,
+            CastleSide::QueenSide => -1,
+        };
+        let king_to = Square::new(king_from.x + 2 * dx, king_from.y);
+        let rook_to = Square::new(king_from.x + dx, king_from.y);
+        if !king.is_royal || rook.kind != PieceKind::Rook || king_from.y != rook_from.y
+            || (rook_from.x - king_from.x).signum() != dx || !self.contains_square(king_to) {
+            return Err(GameError::InvalidCastle);
+        }
+        if king.has_moved || rook.has_moved {
+            return Err(GameError::CastlingPieceMoved);
+        }
         
-        match move_type {
-            MoveType::Move => {
-                // Move: 빈 칸으로 이동만
-                self.board.remove(&from);
-                self.board.insert(to, piece_id.clone());
-                
-                if let Some(piece) = self.pieces.get_mut(piece_id) {
-                    piece.pos = Some(to);
-                    piece.move_stack -= 1;
+        let xs = [king_from.x, rook_from.x, king_to.x];
+        let blocked = (*xs.iter().min().unwrap()..=*xs.iter().max().unwrap())
+            .map(|x| Square::new(x, king_from.y))
+            .any(|sq| self.board.get(&sq).is_some_and(|id| id != king_id && id != rook_id));
+        if blocked {
+            return Err(GameError::CastlingBlocked);
+        }
+        let attacked = self.attacked_squares(1 - player);
+        if [king_from, rook_to, king_to].iter().any(|sq| attacked.contains(sq)) {
+            return Err(GameError::CastlingThroughAttack);
+        }
+        Ok((king_from, king_to, rook_from, rook_to))
+    }
+    
+    /// 캐슬링 (킹의 이동 스택 1 소모, 이후 같은 턴에는 킹만 이어서 이동 가능)
+    pub fn castle(&mut self, player: PlayerId, king_id: &PieceId, rook_id: &PieceId, side: CastleSide) -> Result<(), GameError> {
+        let (king_from, king_to, rook_from, rook_to) = self.castle_squares(player, king_id, rook_id, side)?;
+        let record = self.history_record();
+        self.invalidate_caches();
+        
+        let touched = [king_id.clone(), rook_id.clone()];
+        self.toggle_zobrist(&touched, &[]);
+        self.board.remove(&king_from);
+        self.board.remove(&rook_from);
+        self.board.insert(king_to, king_id.clone());
+        self.board.insert(rook_to, rook_id.clone());
+        if let Some(king) = self.pieces.get_mut(king_id) {
+            king.pos = Some(king_to);
+            king.move_stack -= 1;
+            king.has_moved = true;
+        }
+        if let Some(rook) = self.pieces.get_mut(rook_id) {
+            rook.pos = Some(rook_to);
+            rook.has_moved = true;
+        }
+        self.toggle_zobrist(&touched, &[]);
+        self.debug_assert_piece_at(king_id, king_to);
+        self.debug_assert_piece_at(rook_id, rook_to);
+        
+        self.events.push(GameEvent::Moved { piece_id: king_id.clone(), from: king_from, to: king_to });
+        self.events.push(GameEvent::Moved { piece_id: rook_id.clone(), from: rook_from, to: rook_to });
+        self.active_piece = Some(king_id.clone());
+        self.push_history(record);
+        self.auto_end_if_exhausted();
+        Ok(())
+    }
+    
+    /// 턴 플레이어가 지금 할 수 있는 캐슬링 (`Action::encode` 순)
+    pub fn legal_castles(&self) -> Vec<Action> {
+        let player = self.turn;
+        let unmoved = |p: &&Piece| p.owner == player && p.pos.is_some() && !p.has_moved;
+        let mut actions = Vec::new();
+        for king in self.pieces.values().filter(unmoved).filter(|p| p.is_royal) {
+            for rook in self.pieces.values().filter(unmoved).filter(|p| p.kind == PieceKind::Rook) {
+                for side in [CastleSide::KingSide, CastleSide::QueenSide] {
+                    if self.castle_squares(player, &king.id, &rook.id, side).is_ok() {
+                        actions.push(Action::Castle { king_id: king.id.clone(), rook_id: rook.id.clone(), side });
+                    }
                 }
             }
-            MoveType::Take | MoveType::TakeMove => {
-                // Take/TakeMove: 잡기 또는 이동
-                if let Some(victim_id) = self.board.get(&to).cloned() {
-                    captured_id = Some(victim_id.clone());
-                    self.capture(piece_id, &victim_id)?;
+        }
+        actions.sort_by_key(Action::encode);
+        actions
+    }
+    
+    /// 보드와 기물 위치가 서로 일치하는지 확인 (디버그 빌드 전용)
+    fn debug_assert_piece_at(&self, piece_id: &PieceId, square: Square) {
+        debug_assert_eq!(self.board.get(&square), Some(piece_id), "보드의 {} 칸이 기물과 일치하지 않습니다", square.to_notation());
+        debug_assert_eq!(self.pieces.get(piece_id).and_then(|p| p.pos), Some(square), "기물 {}의 위치가 보드와 일치하지 않습니다", piece_id);
+    }
+    
+    /// 이동 실행 (캡처 포함)
+    ///
+    /// 기물의 행마 목록에서 `to`/`move_type`이 일치하는 `LegalMove`를 찾아
+    /// `move_piece_by_legal_moves`로 실행하므로 transition/상태 태그도 그대로 적용됩니다.
+    /// 일치하는 행마가 없으면 `GameError::IllegalMove`를 반환합니다.
+    pub fn move_piece(&mut self, player: PlayerId, piece_id: &PieceId, from: Square, to: Square, move_type: MoveType) -> Result<Option<PieceId>, GameError> {
+        self.can_move_piece(player, piece_id, from, to, move_type)?;
+        if self.board.get(&from) != Some(piece_id) {
+            return Err(GameError::PieceNotAtSource);
+        }
+        
+        let mv = self.get_legal_moves(piece_id).into_iter()
+            .find(|mv| mv.from == from && mv.to == to && mv.move_type == move_type)
+            .ok_or(GameError::IllegalMove)?;
+        self.move_piece_by_legal_moves(mv)
+    }
+    
+    /// 이동으로 프로모션 칸에 도달해 `promote`를 기다리는 기물
+    pub fn pending_promotion(&self) -> Option<PieceId> {
+        self.pending_promotion.clone()
+    }
+    
+    /// 이동한 기물이 프로모션 칸에 도달했으면 프로모션 대기로 표시
+    fn update_pending_promotion(&mut self, piece_id: &PieceId) {
+        let Some(piece) = self.pieces.get(piece_id) else { return };
+        let reached = piece.pos
+            .is_some_and(|pos| self.is_promotion_square(&piece.kind, pos, piece.owner));
+        if reached {
+            self.pending_promotion = Some(piece_id.clone());
+        } else if self.pending_promotion.as_ref() == Some(piece_id) {
+            self.pending_promotion = None;
+        }
+    }
+    
+    /// 캡처 처리 (`capture_rule`에 따라 스택 이전/포켓 반환)
+    pub fn capture(&mut self, attacker_id: &PieceId, victim_id: &PieceId) -> Result<(), GameError> {
+        let record = self.history_record();
+        let touched = [attacker_id.clone(), victim_id.clone()];
+        self.toggle_zobrist(&touched, &[0, 1]);
+        let result = self.capture_inner(attacker_id, victim_id);
+        self.toggle_zobrist(&touched, &[0, 1]);
+        result?;
+        self.push_history(record);
+        Ok(())
+    }
+    
+    /// 기록 없이 캡처 처리 (이동 안에서의 잡기는 이동 기록에 포함됨)
+    fn capture_inner(&mut self, attacker_id: &PieceId, victim_id: &PieceId) -> Result<(), GameError> {
+        self.invalidate_caches();
+        // 피해자 정보 복사
+        let victim = self.pieces.get(victim_id).ok_or(GameError::VictimNotFound)?.clone();
+        
+        // 잡은 점수 누계
+        let captor = self.pieces.get(attacker_id).map_or(1 - victim.owner, |a| a.owner);
+        *self.captured_value.entry(captor).or_default() += self.score_of(&victim.kind);
+        
+        // 공격자 스택 업데이트
+        if let Some(attacker) = self.pieces.get_mut(attacker_id) {
+            match self.capture_rule {
+                CaptureRule::StackTransfer => {
+                    // 이동 스택: -1 (이동 소비) + 피해자 스택 (음수가 되지 않도록 0에서 멈춤)
+                    attacker.move_stack = (attacker.move_stack - 1 + victim.move_stack).max(0);
+                    // 스턴 스택: 피해자 스택 추가
+                    attacker.stun += victim.stun;
                 }
-                
-                self.board.remove(&from);
-                self.board.insert(to, piece_id.clone());
-                
-                if let Some(piece) = self.pieces.get_mut(piece_id) {
-                    piece.pos = Some(to);
-                    if captured_id.is_none() {
-                        piece.move_stack -= 1;
-                    }
-                    // capture에서 이미 move_stack 처리됨
+                CaptureRule::Remove | CaptureRule::ReturnToPocket => {
+                    // 이동 소비만
+                    attacker.move_stack = (attacker.move_stack - 1).max(0);
                 }
             }
-            MoveType::Catch => {
-                // Catch: 제자리에서 적 제거
-                if let Some(victim_id) = self.board.get(&to).cloned() {
-                    captured_id = Some(victim_id.clone());
-                    // 피해자 정보 복사
-                    let victim = self.pieces.get(&victim_id).ok_or("피해자를 찾을 수 없습니다")?.clone();
-                    
-                    // 공격자는 제자리에 머물지만 스택 업데이트
-                    if let Some(attacker) = self.pieces.get_mut(piece_id) {
-                        // Catch: 이동 스택 -1 + 피해자 스택
-                        attacker.move_stack = attacker.move_stack - 1 + victim.move_stack;
-                        // 스턴 스택: 피해자 스택 추가
-                        attacker.stun += victim.stun;
-                    }
-                    
-                    // 피해자 제거
-                    self.board.remove(&to);
-                    self.pieces.remove(&victim_id);
-                } else {
-                    return Err("Catch 대상이 없습니다".to_string());
-                }
-            }
-            MoveType::Shift => {
-                // Shift: 자리 바꾸기
-                if let Some(target_piece_id) = self.board.get(&to).cloned() {
-                    // 두 기물의 위치 교환
-                    self.board.remove(&from);
-                    self.board.remove(&to);
-                    self.board.insert(from, target_piece_id.clone());
-                    self.board.insert(to, piece_id.clone());
-                    
-                    // 위치 업데이트
-                    if let Some(piece) = self.pieces.get_mut(piece_id) {
-                        piece.pos = Some(to);
-                        piece.move_stack -= 1;
-                    }
-                    if let Some(target_piece) = self.pieces.get_mut(&target_piece_id) {
-                        target_piece.pos = Some(from);
-                    }
-                } else {
-                    return Err("Shift 대상이 없습니다".to_string());
-                }
-            }
-            MoveType::Jump => {
-                // Jump: take-jump 조합 (빈 칸으로 이동)
-                self.board.remove(&from);
-                self.board.insert(to, piece_id.clone());
-                
-                if let Some(piece) = self.pieces.get_mut(piece_id) {
-                    piece.pos = Some(to);
-                    piece.move_stack -= 1;
-                }
-            }
-        }
-        
-        // 이동 중인 기물 설정
-        self.active_piece = Some(piece_id.clone());
-        
-        Ok(captured_id)
-    }
-    
-    /// 캡처 처리 (stack.md 규칙)
-    pub fn capture(&mut self, attacker_id: &PieceId, victim_id: &PieceId) -> Result<(), String> {
-        // 피해자 정보 복사
-        let victim = self.pieces.get(victim_id).ok_or("피해자를 찾을 수 없습니다")?.clone();
-        
-        // 공격자 스택 업데이트
-        if let Some(attacker) = self.pieces.get_mut(attacker_id) {
-            // 이동 스택: -1 (이동 소비) + 피해자 스택
-            attacker.move_stack = attacker.move_stack - 1 + victim.move_stack;
-            // 스턴 스택: 피해자 스택 추가
-            attacker.stun += victim.stun;
         }
         
         // 피해자 제거
         if let Some(pos) = victim.pos {
             self.board.remove(&pos);
+            self.events.push(GameEvent::Captured { piece_id: victim.id.clone(), kind: victim.kind.clone(), at: pos, by: captor });
         }
         self.pieces.remove(victim_id);
         
+        if self.capture_rule == CaptureRule::ReturnToPocket {
+            self.pockets.entry(victim.owner).or_default().push(PieceSpec::new(victim.kind));
+        }
+        self.repetitions.clear();
+        
         Ok(())
     }
     
-    /// 계승 (기물을 로얄 피스로)
-    pub fn crown_piece(&mut self, player: PlayerId, piece_id: &PieceId) -> Result<(), String> {
+    /// 계승/위장/스턴 행동이 가능한지 (`action_policy`에 따라 이동 후 허용)
+    fn can_take_action(&self, player: PlayerId) -> Result<(), GameError> {
         if self.turn != player {
-            return Err("자신의 턴이 아닙니다".to_string());
+            return Err(GameError::NotYourTurn);
         }
-        if self.action_taken || self.active_piece.is_some() {
-            return Err("이번 턴에 이미 행동했습니다".to_string());
+        let moved = self.active_piece.is_some() && self.action_policy == TurnActionPolicy::Exclusive;
+        if self.action_taken || moved {
+            return Err(GameError::AlreadyActed);
         }
+        Ok(())
+    }
+    
+    /// 계승 (기물을 로얄 피스로)
+    pub fn crown_piece(&mut self, player: PlayerId, piece_id: &PieceId) -> Result<(), GameError> {
+        self.invalidate_caches();
+        self.can_take_action(player)?;
+        let record = self.history_record();
         
-        let piece = self.pieces.get_mut(piece_id).ok_or("기물을 찾을 수 없습니다")?;
+        let piece = self.pieces.get_mut(piece_id).ok_or(GameError::PieceNotFound)?;
         if piece.owner != player {
-            return Err("자신의 기물이 아닙니다".to_string());
+            return Err(GameError::NotYourPiece);
         }
         if piece.pos.is_none() {
-            return Err("보드 위의 기물만 계승할 수 있습니다".to_string());
+            return Err(GameError::CrownOffBoard);
         }
         
+        let old_hash = Self::piece_zobrist(piece);
         piece.is_royal = true;
+        self.zobrist ^= old_hash ^ Self::piece_zobrist(piece);
         self.action_taken = true;
+        self.push_history(record);
         Ok(())
     }
     
     /// 위장 (로얄 피스를 다른 기물로)
-    pub fn disguise_piece(&mut self, player: PlayerId, piece_id: &PieceId, as_kind: PieceKind) -> Result<(), String> {
-        if self.turn != player {
-            return Err("자신의 턴이 아닙니다".to_string());
-        }
-        if self.action_taken || self.active_piece.is_some() {
-            return Err("이번 턴에 이미 행동했습니다".to_string());
-        }
+    pub fn disguise_piece(&mut self, player: PlayerId, piece_id: &PieceId, as_kind: PieceKind) -> Result<(), GameError> {
+        self.invalidate_caches();
+        self.can_take_action(player)?;
+        let record = self.history_record();
         
-        let piece = self.pieces.get_mut(piece_id).ok_or("기물을 찾을 수 없습니다")?;
+        let piece = self.pieces.get_mut(piece_id).ok_or(GameError::PieceNotFound)?;
         if piece.owner != player {
-            return Err("자신의 기물이 아닙니다".to_string());
+            return Err(GameError::NotYourPiece);
         }
         if !piece.is_royal {
-            return Err("로얄 피스만 위장할 수 있습니다".to_string());
+            return Err(GameError::NotRoyal);
+        }
+        // 킹으로 위장하면 숨기는 의미가 없고, 실험/사용자 정의 기물은 상대가 행마를 알 수 없음
+        match as_kind {
+            PieceKind::King => return Err(GameError::DisguiseAsKing),
+            PieceKind::Experiment | PieceKind::Custom(_) => {
+                return Err(GameError::NonStandardDisguise);
+            }
+            _ => {}
         }
         
-        // 위장 시 이동 스택은 위장 기물 기준, 스턴은 유지
-        let new_score = as_kind.score();
-        piece.move_stack = Self::initial_move_stack(new_score);
+        // 위장 시 이동 스택은 위장 기물 기준 (`effective_score`), 스턴은 유지
+        let old_hash = Self::piece_zobrist(piece);
         piece.disguise = Some(as_kind);
+        piece.move_stack = self.rules.move_stack(Self::score_in(&self.custom_pieces, piece.effective_kind()));
+        self.zobrist ^= old_hash ^ Self::piece_zobrist(piece);
         self.action_taken = true;
+        self.push_history(record);
         Ok(())
     }
     
     /// 스턴 부여 (적 1, 아군 1~3)
-    pub fn apply_stun(&mut self, player: PlayerId, target_id: &PieceId, amount: i32) -> Result<(), String> {
-        if self.turn != player {
-            return Err("자신의 턴이 아닙니다".to_string());
-        }
-        if self.action_taken || self.active_piece.is_some() {
-            return Err("이번 턴에 이미 행동했습니다".to_string());
-        }
+    pub fn apply_stun(&mut self, player: PlayerId, target_id: &PieceId, amount: i32) -> Result<(), GameError> {
+        self.can_take_action(player)?;
+        let record = self.history_record();
         
-        let piece = self.pieces.get_mut(target_id).ok_or("기물을 찾을 수 없습니다")?;
+        let piece = self.pieces.get_mut(target_id).ok_or(GameError::PieceNotFound)?;
         
         if piece.owner == player {
             // 아군: 1~3 스택
-            if amount < 1 || amount > 3 {
-                return Err("아군에게는 1~3 스턴만 부여할 수 있습니다".to_string());
+            if !(1..=3).contains(&amount) {
+                return Err(GameError::InvalidStunAmount { ally: true });
             }
         } else {
             // 적: 1 스택만
             if amount != 1 {
-                return Err("적에게는 1 스턴만 부여할 수 있습니다".to_string());
+                return Err(GameError::InvalidStunAmount { ally: false });
             }
         }
         
+        let old_hash = Self::piece_zobrist(piece);
         piece.stun += amount;
+        self.zobrist ^= old_hash ^ Self::piece_zobrist(piece);
         self.action_taken = true;
+        self.push_history(record);
+        Ok(())
+    }
+    
+    /// 이번 턴에 움직인 기물이 더 움직일 수 없는지 (이동 스택 소진 또는 스턴)
+    ///
+    /// 아직 아무 기물도 움직이지 않았으면 false입니다. UI는 true일 때 보드를 잠그고 턴 종료만 받으면 됩니다.
+    pub fn is_turn_exhausted(&self) -> bool {
+        self.active_piece.as_ref()
+            .and_then(|id| self.pieces.get(id))
+            .is_some_and(|piece| piece.pos.is_none() || !piece.can_move())
+    }
+    
+    /// `auto_end_turn`이 켜져 있고 턴이 소진됐으면 턴 종료
+    fn auto_end_if_exhausted(&mut self) {
+        if self.auto_end_turn && self.is_turn_exhausted() && self.pending_promotion.is_none() {
+            let record = self.history_record();
+            self.advance_turn();
+            self.push_history(record);
+        }
+    }
+    
+    /// 턴 종료 (프로모션 대기 중이면 먼저 `promote`해야 함)
+    pub fn end_turn(&mut self) -> Result<(), GameError> {
+        if self.pending_promotion.is_some() {
+            return Err(GameError::PromotionPending);
+        }
+        let record = self.history_record();
+        self.advance_turn();
+        self.push_history(record);
         Ok(())
     }
     
-    /// 턴 종료
-    pub fn end_turn(&mut self) {
+    /// 프로모션 대기 여부와 무관하게 턴을 넘김 (탐색, 가상 국면용)
+    fn advance_turn(&mut self) {
+        self.invalidate_caches();
         // 현재 턴 기물만 스턴 1 감소
         for piece in self.pieces.values_mut() {
             if piece.owner == self.turn {
+                let old_hash = Self::piece_zobrist(piece);
                 piece.stun = (piece.stun - 1).max(0);
+                self.zobrist ^= old_hash ^ Self::piece_zobrist(piece);
             }
         }
         
-        // 다음 플레이어
+        // 다음 플레이어 (이번 턴에 생긴 앙파상 칸만 다음 턴에 유효)
+        self.zobrist ^= Self::turn_zobrist(self.turn) ^ Self::turn_zobrist(1 - self.turn);
+        self.zobrist ^= Self::en_passant_zobrist(self.en_passant_target);
+        self.en_passant_target = self.en_passant_pending.take();
+        self.zobrist ^= Self::en_passant_zobrist(self.en_passant_target);
         self.turn = 1 - self.turn;
+        self.turns_played += 1;
         
-        // 다음 턴 기물들 이동 스택 초기화
+        // 다음 턴 기물들 이동 스택 초기화 (`stack_refill`에 따라 스턴 반영)
+        // 위장한 기물은 위장 기물 기준 (실제 종류가 스택으로 드러나지 않도록)
         for piece in self.pieces.values_mut() {
             if piece.owner == self.turn && piece.pos.is_some() {
-                piece.move_stack = Self::initial_move_stack(piece.score());
+                let old_hash = Self::piece_zobrist(piece);
+                let initial = self.rules.move_stack(Self::score_in(&self.custom_pieces, piece.effective_kind()));
+                piece.move_stack = self.stack_refill.refill(initial, piece.stun, piece.move_stack);
+                self.zobrist ^= old_hash ^ Self::piece_zobrist(piece);
             }
         }
         
         // 턴 상태 초기화
         self.active_piece = None;
         self.action_taken = false;
+        self.pending_promotion = None;
+        *self.repetitions.entry(self.zobrist).or_insert(0) += 1;
+        self.events.push(GameEvent::TurnEnded { next: self.turn });
+    }
+    
+    /// 기보의 `ply`번째 턴(액션 목록)을 적용하고 턴을 넘긴 뒤, 그동안 생긴 이벤트를 반환
+    ///
+    /// 리플레이에서 한 턴씩 애니메이션할 때 씁니다. 실패하면 그 전까지 적용된 상태로 남습니다.
+    pub fn step_forward_with_events(&mut self, transcript: &[Vec<Action>], ply: usize) -> Result<Vec<GameEvent>, GameError> {
+        let actions = transcript.get(ply).ok_or(GameError::TranscriptOutOfRange)?;
+        let start = self.events.len();
+        for action in actions {
+            self.apply_action(action.clone())?;
+        }
+        self.end_turn()?;
+        Ok(self.events[start..].to_vec())
     }
     
     /// 승리 조건 확인
@@ -1068,13 +2196,213 @@ impl GameState {
         }
     }
     
+    /// `check_victory`에 기물 부족/체크메이트/스테일메이트 판정을 더한 결과
+    ///
+    /// 양쪽 모두 로얄 피스만 남고 포켓이 비었으면 무승부입니다.
+    /// 턴 플레이어에게 합법 수가 없을 때 체크 상태면 상대 승리, 아니면 무승부입니다.
+    /// 로얄 피스가 잡혀 승패가 난 경우는 그 결과를 그대로 돌려줍니다.
+    pub fn check_victory_full(&self) -> GameResult {
+        let result = self.check_victory();
+        if result != GameResult::Ongoing {
+            return result;
+        }
+        if self.is_insufficient_material() {
+            return GameResult::Draw;
+        }
+        if self.has_any_legal_move(self.turn) {
+            return result;
+        }
+        match (self.is_check(self.turn), self.turn) {
+            (false, _) => GameResult::Draw,
+            (true, 0) => GameResult::BlackWins,
+            (true, _) => GameResult::WhiteWins,
+        }
+    }
+    
+    /// 기물 부족: 양쪽 모두 보드에 로얄 피스만 있고 포켓이 비었음
+    ///
+    /// 한 턴에 여러 번 움직이고 스턴을 걸 수 있어 폰 하나로도 메이트가 가능할 수 있으므로,
+    /// 포켓에 기물이 하나라도 남아 있으면 종류와 무관하게 부족으로 보지 않습니다.
+    pub fn is_insufficient_material(&self) -> bool {
+        let only_royals = self.pieces.values()
+            .filter(|p| p.pos.is_some())
+            .all(|p| p.is_royal);
+        let pockets_empty = self.pockets.values().all(Vec::is_empty);
+        only_royals && pockets_empty
+    }
+    
+    /// 자신의 로얄 피스를 공격받게 두지 않는 수가 하나라도 있는지 (보드 위 기물 이동 + 포켓 착수)
+    pub fn has_any_legal_move(&self, player: PlayerId) -> bool {
+        let fork = self.fork_for_turn(player);
+        let has_move = fork.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some())
+            .any(|p| !fork.get_legal_moves_checked(&p.id).is_empty());
+        if has_move {
+            return true;
+        }
+        
+        fork.get_pocket(player).iter().any(|kind| {
+            fork.squares().any(|sq| {
+                if fork.can_place(player, kind, sq).is_err() {
+                    return false;
+                }
+                let mut next = fork.clone_bare();
+                next.place_piece(player, kind.clone(), sq).is_ok() && !next.is_check(player)
+            })
+        })
+    }
+    
+    /// 이동 후 자신의 로얄 피스가 공격받지 않는 이동만 (턴이 아니면 그 플레이어의 턴 기준)
+    pub fn get_legal_moves_checked(&self, piece_id: &PieceId) -> Vec<LegalMove> {
+        let Some(owner) = self.pieces.get(piece_id).map(|p| p.owner) else {
+            return Vec::new();
+        };
+        let fork = self.fork_for_turn(owner);
+        fork.get_legal_moves(piece_id).into_iter()
+            .filter(|mv| {
+                let mut next = fork.clone_bare();
+                next.move_piece_by_legal_moves(mv.clone()).is_ok() && !next.is_check(owner)
+            })
+            .collect()
+    }
+    
+    /// 게임 진행 상태 (`check_victory_full` + `max_turns` 도달 시 기물 점수 판정)
+    ///
+    /// 점수가 높은 쪽이 이기고, 같으면 무승부입니다.
+    pub fn game_status(&self) -> GameResult {
+        let result = self.check_victory_full();
+        if result != GameResult::Ongoing {
+            return result;
+        }
+        match self.max_turns {
+            Some(max) if self.turns_played >= max => match self.material(0).cmp(&self.material(1)) {
+                std::cmp::Ordering::Greater => GameResult::WhiteWins,
+                std::cmp::Ordering::Less => GameResult::BlackWins,
+                std::cmp::Ordering::Equal => GameResult::Draw,
+            },
+            _ => GameResult::Ongoing,
+        }
+    }
+    
+    /// 플레이어의 기물 점수 합 (보드 + 포켓)
+    pub fn material(&self, player: PlayerId) -> i32 {
+        let on_board: i32 = self.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some())
+            .map(|p| self.score_of(&p.kind))
+            .sum();
+        let in_pocket: i32 = self.pockets.get(&player).map_or(0, |specs| specs.iter().map(|s| self.score_of(&s.kind)).sum());
+        on_board + in_pocket
+    }
+    
+    /// 현재 진행 단계 (`phase_thresholds` 기준)
+    pub fn phase(&self) -> GamePhase {
+        let non_royal = || self.pieces.values().filter(|p| !p.is_royal && p.pos.is_some());
+        let board_pieces = non_royal().count();
+        let material: i32 = non_royal().map(|p| self.score_of(&p.kind)).sum::<i32>()
+            + self.pockets.values().flatten().map(|s| self.score_of(&s.kind)).sum::<i32>();
+        
+        let t = &self.phase_thresholds;
+        if material <= t.endgame_max_material {
+            GamePhase::Endgame
+        } else if board_pieces <= t.opening_max_board_pieces && material >= t.opening_min_material {
+            GamePhase::Opening
+        } else {
+            GamePhase::Middlegame
+        }
+    }
+    
     /// 특정 위치의 기물 가져오기
     pub fn get_piece_at(&self, square: Square) -> Option<&Piece> {
         self.board.get(&square).and_then(|id| self.pieces.get(id))
     }
     
+    /// 두 상태가 같은 국면인지 (기물 ID/기록 무시)
+    ///
+    /// 칸별 기물의 종류/주인/로얄/위장/스턴/이동 스택/이동 여부, 포켓 구성, 턴을 비교합니다.
+    pub fn same_position(&self, other: &GameState) -> bool {
+        self.turn == other.turn
+            && self.board_signature() == other.board_signature()
+            && self.pocket_signature() == other.pocket_signature()
+    }
+    
+    /// 칸 순서로 정렬한 기물 상태 목록 (국면 비교용)
+    fn board_signature(&self) -> Vec<PieceSignature> {
+        let mut signature: Vec<_> = self.board.iter()
+            .filter_map(|(sq, id)| {
+                let p = self.pieces.get(id)?;
+                Some((*sq, p.kind.clone(), p.owner, p.is_royal, p.disguise.clone(), p.stun, p.move_stack, p.has_moved))
+            })
+            .collect();
+        signature.sort_by_key(|entry| (entry.0.y, entry.0.x));
+        signature
+    }
+    
+    /// 플레이어별 포켓 기물 목록 (순서 무시)
+    fn pocket_signature(&self) -> Vec<(PlayerId, Vec<String>)> {
+        let mut signature: Vec<(PlayerId, Vec<String>)> = self.pockets.iter()
+            .filter(|(_, specs)| !specs.is_empty())
+            .map(|(player, specs)| {
+                let mut kinds: Vec<String> = specs.iter().map(|s| format!("{:?}", s.kind)).collect();
+                kinds.sort();
+                (*player, kinds)
+            })
+            .collect();
+        signature.sort();
+        signature
+    }
+    
+    /// 플레이어가 이번 게임에서 잡은 기물 점수 합계
+    pub fn captured_value(&self, player: PlayerId) -> i32 {
+        self.captured_value.get(&player).copied().unwrap_or(0)
+    }
+    
+    /// 플레이어가 보드 위에 가진 기물 수
+    pub fn piece_count(&self, player: PlayerId) -> usize {
+        self.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some())
+            .count()
+    }
+    
+    /// 플레이어가 보드 위에 가진 특정 종류의 기물 ID 목록 (위장 전 실제 종류 기준)
+    pub fn pieces_of_kind(&self, player: PlayerId, kind: &PieceKind) -> Vec<PieceId> {
+        let mut ids: Vec<PieceId> = self.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some() && &p.kind == kind)
+            .map(|p| p.id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+    
+    /// 플레이어가 보드 위에 가진 특정 종류의 기물 수
+    pub fn count_kind(&self, player: PlayerId, kind: &PieceKind) -> usize {
+        self.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some() && &p.kind == kind)
+            .count()
+    }
+    
+    /// 보드 위 전체 기물 수
+    pub fn total_piece_count(&self) -> usize {
+        self.board.len()
+    }
+    
+    /// 보드 안의 칸인지 (`board_width` x `board_height`)
+    pub fn contains_square(&self, square: Square) -> bool {
+        square.is_within(self.board_width, self.board_height)
+    }
+    
+    /// 보드의 모든 칸 (1랭크부터, 랭크 안에서는 a파일부터)
+    pub fn squares(&self) -> impl Iterator<Item = Square> {
+        let (width, height) = (self.board_width, self.board_height);
+        (0..height).flat_map(move |y| (0..width).map(move |x| Square::new(x, y)))
+    }
+    
+    /// 보드가 기물로 채워진 비율 (0.0 ~ 1.0)
+    pub fn board_fill_ratio(&self) -> f64 {
+        self.board.len() as f64 / (self.board_width * self.board_height) as f64
+    }
+    
     /// GameState를 ChessemblyBoard로 변환
-    fn to_chessembly_board(&self, piece_id: &PieceId) -> Option<ChessemblyBoard> {
+    fn to_chessembly_board(&self, piece_id: &PieceId, with_threats: bool) -> Option<ChessemblyBoard> {
         let piece = self.pieces.get(piece_id)?;
         let pos = piece.pos?;
         
@@ -1088,607 +2416,4401 @@ impl GameState {
             }
         }
         
+        // 위협 정보는 캐시에서 읽음 (공격 칸 계산 자체에는 쓰지 않아 재귀 없음)
+        let mut danger_squares = HashSet::new();
+        let mut in_check = false;
+        if with_threats {
+            let attacked = self.attacked_squares(1 - piece.owner);
+            danger_squares = attacked.iter().map(|sq| (sq.x, sq.y)).collect();
+            in_check = self.royal_squares(piece.owner).iter().any(|sq| attacked.contains(sq));
+        }
+        
         Some(ChessemblyBoard {
-            board_width: 8,
-            board_height: 8,
+            board_width: self.board_width,
+            board_height: self.board_height,
             piece_x: pos.x,
             piece_y: pos.y,
             piece_name: format!("{:?}", piece.effective_kind()),
             is_white: piece.is_white(),
             pieces: pieces_map,
             state: self.global_state.clone(),
-            danger_squares: HashSet::new(), // TODO: 위협 계산
-            in_check: false, // TODO: 체크 계산
+            danger_squares,
+            in_check,
         })
     }
     
-    /// 특정 기물의 이동 가능한 칸 목록 계산 (chessembly 사용)
-    pub fn get_legal_moves(&self, piece_id: &PieceId) -> Vec<LegalMove> {
-        let mut legal_moves = Vec::new();
-        
-        let piece = match self.pieces.get(piece_id) {
-            Some(p) => p,
-            None => return legal_moves,
+    /// 국면 문자열로 직렬화
+    ///
+    /// 형식: `버전;보드 크기;턴;기물들;백 포켓;흑 포켓;앙파상`
+    /// - 보드 크기: `가로x세로` (예: `8x8`)
+    /// - 턴: `w` 또는 `b`
+    /// - 기물: `칸:색:종류:이동스택:스턴:로얄(0/1):위장(없으면 -):이동 여부(0/1)`를 `,`로 연결
+    /// - 포켓: 기물 종류를 `.`으로 연결
+    /// - 앙파상: 앙파상 대상 칸, 없으면 `-`
+    ///
+    /// 턴 진행 중 상태(활성 기물, 프로모션 대기)와 규칙 설정은 포함하지 않습니다.
+    pub fn to_position_string(&self) -> String {
+        let color = |player: PlayerId| if player == 0 { "w" } else { "b" };
+        let mut placed: Vec<&Piece> = self.pieces.values().filter(|p| p.pos.is_some()).collect();
+        placed.sort_by_key(|p| p.pos.map(|sq| (sq.y, sq.x)));
+        let pieces: Vec<String> = placed.iter()
+            .map(|p| format!(
+                "{}:{}:{}:{}:{}:{}:{}:{}",
+                p.pos.unwrap().to_notation(),
+                color(p.owner),
+                p.kind.token(),
+                p.move_stack,
+                p.stun,
+                p.is_royal as u8,
+                p.disguise.as_ref().map_or("-".to_string(), |d| d.token()),
+                p.has_moved as u8,
+            ))
+            .collect();
+        let pocket = |player: PlayerId| {
+            self.get_pocket(player).iter().map(|k| k.token()).collect::<Vec<_>>().join(".")
         };
-        
-        // 이동 불가 상태 확인
-        if !piece.can_move() {
-            return legal_moves;
+        format!(
+            "{};{}x{};{};{};{};{};{}",
+            FORMAT_VERSION, self.board_width, self.board_height, color(self.turn), pieces.join(","), pocket(0), pocket(1),
+            self.en_passant_target.map_or("-".to_string(), |sq| sq.to_notation()),
+        )
+    }
+    
+    /// `to_position_string`으로 만든 문자열에서 국면 복원 (다른 형식 버전은 거부)
+    pub fn from_position_string(text: &str) -> Result<GameState, GameError> {
+        Self::parse_position_string(text).map_err(GameError::Parse)
+    }
+    
+    fn parse_position_string(text: &str) -> Result<GameState, String> {
+        let fields: Vec<&str> = text.trim().split(';').collect();
+        let version: u32 = fields[0].parse().map_err(|_| format!("형식 버전을 읽을 수 없습니다: {}", fields[0]))?;
+        if version != FORMAT_VERSION {
+            return Err(format!("지원하지 않는 형식 버전입니다: {} (지원: {})", version, FORMAT_VERSION));
         }
-        
-        let pos = match piece.pos {
-            Some(p) => p,
-            None => return legal_moves,
-        };
-        
-        // chessembly 보드 상태 생성
-        let mut board = match self.to_chessembly_board(piece_id) {
-            Some(b) => b,
-            None => return legal_moves,
+        if fields.len() != 7 {
+            return Err(format!("필드 수가 올바르지 않습니다: {} (필요: 7)", fields.len()));
+        }
+        let parse_color = |c: &str| match c {
+            "w" => Ok(0),
+            "b" => Ok(1),
+            _ => Err(format!("알 수 없는 색입니다: {}", c)),
         };
+        let parse_kind = |t: &str| PieceKind::from_token(t).ok_or(format!("알 수 없는 기물입니다: {}", t));
+        let parse_int = |t: &str| t.parse::<i32>().map_err(|_| format!("숫자가 아닙니다: {}", t));
         
-        // 행마법 스크립트 가져오기
-        let script = piece.effective_kind().chessembly_script(piece.is_white());
-        
-        // chessembly 인터프리터 실행
-        let mut interpreter = Interpreter::new();
-        interpreter.set_debug(self.debug_mode);
-        interpreter.parse(script);
-        let activations = interpreter.execute(&mut board);
+        let (width, height) = parse_board_size(fields[1])?;
+        let mut state = GameState::with_size(width, height, parse_color(fields[2])?);
+        state.board.clear();
+        state.pieces.clear();
         
-        // 활성화된 칸들을 LegalMove로 변환
-        for activation in activations {
-            let target = Square::new(pos.x + activation.dx, pos.y + activation.dy);
-            let mut takemove_sq = Square::new(0, 0);
-            if let Some((x, y)) = activation.catch_to {
-                takemove_sq = Square::new(pos.x + x, pos.y + y);
+        for entry in fields[3].split(',').filter(|e| !e.is_empty()) {
+            let parts: Vec<&str> = entry.split(':').collect();
+            if parts.len() != 8 {
+                return Err(format!("기물 표기가 올바르지 않습니다: {}", entry));
             }
-            
-            // 보드 범위 확인
-            if !target.is_valid() {
-                continue;
+            let square = Square::from_notation(parts[0])
+                .filter(|sq| state.contains_square(*sq))
+                .ok_or(format!("잘못된 칸입니다: {}", parts[0]))?;
+            if state.board.contains_key(&square) {
+                return Err(format!("칸에 기물이 중복되었습니다: {}", parts[0]));
             }
-            
-            let is_capture = self.board.contains_key(&target);
-            
-            legal_moves.push(LegalMove {
-                from: pos,
-                to: target,
-                move_type: activation.move_type,
-                is_capture,
-                tags: activation.tags,
-                catch_to: takemove_sq,
-            });
+            let mut piece = state.create_piece(parse_kind(parts[2])?, parse_color(parts[1])?);
+            piece.pos = Some(square);
+            piece.move_stack = parse_int(parts[3])?;
+            piece.stun = parse_int(parts[4])?;
+            piece.is_royal = parts[5] == "1";
+            piece.disguise = if parts[6] == "-" { None } else { Some(parse_kind(parts[6])?) };
+            piece.has_moved = parts[7] == "1";
+            state.board.insert(square, piece.id.clone());
+            state.pieces.insert(piece.id.clone(), piece);
         }
         
-        legal_moves
+        for (player, field) in [(0, fields[4]), (1, fields[5])] {
+            let specs = field.split('.')
+                .filter(|t| !t.is_empty())
+                .map(|t| parse_kind(t).map(PieceSpec::new))
+                .collect::<Result<Vec<_>, _>>()?;
+            state.pockets.insert(player, specs);
+        }
+        state.en_passant_target = match fields[6] {
+            "-" => None,
+            t => Some(Square::from_notation(t)
+                .filter(|sq| state.contains_square(*sq))
+                .ok_or(format!("잘못된 앙파상 칸입니다: {}", t))?),
+        };
+        state.refresh_zobrist();
+        state.reset_repetitions();
+        
+        Ok(state)
     }
     
-    /// 이동이 유효한지 확인 (chessembly 기반)
-    pub fn is_valid_move(&self, piece_id: &PieceId, from: Square, to: Square) -> bool {
-        let legal_moves = self.get_legal_moves(piece_id);
-        legal_moves.iter().any(|m| m.from == from && m.to == to)
+    /// FEN 비슷한 한 줄 국면 표기
+    ///
+    /// 형식: `보드 턴 캐슬링 앙파상 백포켓 흑포켓` (공백 구분)
+    /// - 보드: 마지막 랭크부터 1랭크까지 `/`로 구분, 빈 칸은 연속 개수 숫자 (랭크 수가 세로, 랭크 길이가 가로 크기)
+    /// - 기물: `글자[*][~위장글자]{스턴,이동스택}` (`*`는 로얄)
+    ///   - 글자는 백이면 첫 글자 대문자(`K`, `Am`), 흑이면 모두 소문자(`k`, `am`)
+    ///   - Custom은 `U'이름'` (이름에 `'`, 공백, `,`는 쓸 수 없음)
+    /// - 턴: `w` 또는 `b`
+    /// - 캐슬링: 움직인 적이 있는 기물의 칸을 `,`로 연결 (캐슬링 조건), 없으면 `-`
+    /// - 앙파상: 앙파상 대상 칸, 없으면 `-`
+    /// - 포켓: 기물 글자를 `,`로 연결, 비었으면 `-`
+    ///
+    /// 예: `4k*{0,3}3/8/8/8/8/8/8/4K*{0,3}3 w - - Q,Am -`
+    ///
+    /// `to_position_string`과 같은 내용을 담으며 사람이 읽고 붙여넣기 쉬운 형태입니다.
+    pub fn to_notation(&self) -> String {
+        let piece_token = |p: &Piece| {
+            let cased = |kind: &PieceKind| if p.is_white() { kind.letters() } else { kind.letters().to_lowercase() };
+            let mut token = cased(&p.kind);
+            if p.is_royal {
+                token.push('*');
+            }
+            if let Some(disguise) = &p.disguise {
+                token.push('~');
+                token.push_str(&cased(disguise));
+            }
+            token + &format!("{{{},{}}}", p.stun, p.move_stack)
+        };
+        
+        let ranks: Vec<String> = (0..self.board_height).rev().map(|y| {
+            let mut rank = String::new();
+            let mut empty = 0;
+            for x in 0..self.board_width {
+                match self.get_piece_at(Square::new(x, y)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            rank.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        rank.push_str(&piece_token(piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            rank
+        }).collect();
+        
+        let pocket = |player: PlayerId| {
+            let kinds = self.get_pocket(player);
+            if kinds.is_empty() {
+                "-".to_string()
+            } else {
+                kinds.iter().map(PieceKind::letters).collect::<Vec<_>>().join(",")
+            }
+        };
+        let mut moved: Vec<Square> = self.pieces.values()
+            .filter(|p| p.has_moved)
+            .filter_map(|p| p.pos)
+            .collect();
+        moved.sort_by_key(|sq| (sq.y, sq.x));
+        let moved = if moved.is_empty() {
+            "-".to_string()
+        } else {
+            moved.iter().map(|sq| sq.to_notation()).collect::<Vec<_>>().join(",")
+        };
+        let en_passant = self.en_passant_target.map_or("-".to_string(), |sq| sq.to_notation());
+        format!(
+            "{} {} {} {} {} {}",
+            ranks.join("/"), if self.turn == 0 { "w" } else { "b" }, moved, en_passant, pocket(0), pocket(1),
+        )
     }
     
-    /// 이동의 MoveType 찾기
-    pub fn get_move_type(&self, piece_id: &PieceId, from: Square, to: Square) -> Option<MoveType> {
-        let legal_moves = self.get_legal_moves(piece_id);
-        legal_moves.iter()
-            .find(|m| m.from == from && m.to == to)
-            .map(|m| m.move_type)
+    /// `to_notation` 표기에서 국면 복원
+    pub fn from_notation(text: &str) -> Result<GameState, GameError> {
+        Self::parse_notation(text).map_err(GameError::Parse)
     }
     
-    /// 프로모션 실행
-    pub fn promote(&mut self, piece_id: &PieceId, to_kind: PieceKind) -> Result<(), String> {
-        let piece = self.pieces.get(piece_id).ok_or("기물을 찾을 수 없습니다")?;
+    fn parse_notation(text: &str) -> Result<GameState, String> {
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(format!("필드 수가 올바르지 않습니다: {} (필요: 6)", fields.len()));
+        }
+        let turn = match fields[1] {
+            "w" => 0,
+            "b" => 1,
+            other => return Err(format!("알 수 없는 색입니다: {}", other)),
+        };
         
-        // 프로모션 가능한 기물인지
-        if !piece.kind.can_promote() {
-            return Err("프로모션할 수 없는 기물입니다".to_string());
+        // 기물 글자 하나 읽기: (종류, 첫 글자가 대문자인지, 남은 문자열)
+        fn read_kind(text: &str) -> Result<(PieceKind, bool, &str), String> {
+            let is_upper = text.starts_with(|c: char| c.is_ascii_uppercase());
+            if let Some(rest) = text.strip_prefix(['U', 'u']).and_then(|r| r.strip_prefix('\'')) {
+                let end = rest.find('\'').ok_or(format!("Custom 기물 이름이 닫히지 않았습니다: {}", text))?;
+                return Ok((PieceKind::Custom(rest[..end].to_string()), is_upper, &rest[end + 1..]));
+            }
+            let end = text.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(text.len());
+            let kind = PieceKind::from_letters(&text[..end]).ok_or(format!("알 수 없는 기물입니다: {}", &text[..end]))?;
+            Ok((kind, is_upper, &text[end..]))
         }
         
-        // 유효한 프로모션 대상인지
-        if !piece.kind.promotion_targets().contains(&to_kind) {
-            return Err("유효하지 않은 프로모션 대상입니다".to_string());
+        // 빈 칸 수 읽기: 10칸 이상은 여러 자리 숫자
+        fn read_count(text: &str) -> Option<(i32, &str)> {
+            let end = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+            Some((text[..end].parse().ok()?, &text[end..]))
         }
         
-        // 프로모션 칸에 있는지
-        let pos = piece.pos.ok_or("보드 위에 없는 기물입니다")?;
-        if !piece.kind.is_promotion_square(pos, piece.is_white()) {
-            return Err("프로모션 칸에 있지 않습니다".to_string());
+        // 랭크마다 기물을 읽은 뒤 랭크 수와 길이로 보드 크기를 정함
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        let mut width = None;
+        let mut placed = Vec::new();
+        for (i, rank) in ranks.iter().enumerate() {
+            let mut x = 0;
+            let mut rest = *rank;
+            while !rest.is_empty() {
+                if let Some((count, after)) = read_count(rest) {
+                    x += count;
+                    rest = after;
+                    continue;
+                }
+                let (kind, is_white, after) = read_kind(rest)?;
+                let (is_royal, after) = match after.strip_prefix('*') {
+                    Some(after) => (true, after),
+                    None => (false, after),
+                };
+                let (disguise, after) = match after.strip_prefix('~') {
+                    Some(after) => {
+                        let (kind, _, after) = read_kind(after)?;
+                        (Some(kind), after)
+                    }
+                    None => (None, after),
+                };
+                let close = after.find('}').filter(|_| after.starts_with('{'))
+                    .ok_or(format!("스택 표기가 없습니다: {}", rank))?;
+                let (stun, move_stack) = after[1..close].split_once(',')
+                    .and_then(|(a, b)| Some((a.parse::<i32>().ok()?, b.parse::<i32>().ok()?)))
+                    .ok_or(format!("스택 표기가 올바르지 않습니다: {}", &after[..=close]))?;
+                rest = &after[close + 1..];
+                placed.push((x, i, kind, is_white, is_royal, disguise, stun, move_stack));
+                x += 1;
+            }
+            match width {
+                None => width = Some(x),
+                Some(width) if width != x => return Err(format!("랭크 길이가 {}이(가) 아닙니다: {}", width, rank)),
+                Some(_) => {}
+            }
+        }
+        let (width, height) = (width.unwrap_or(0), ranks.len() as i32);
+        if !(1..=MAX_BOARD_SIZE).contains(&width) || !(1..=MAX_BOARD_SIZE).contains(&height) {
+            return Err(format!("보드 크기가 올바르지 않습니다: {}x{}", width, height));
         }
         
-        // 프로모션 실행 (스택 계승)
-        if let Some(piece) = self.pieces.get_mut(piece_id) {
-            piece.kind = to_kind;
-            // 스택은 유지 (promotion.md: 이전 기물의 모든 스택값이 계승)
+        let mut state = GameState::with_size(width, height, turn);
+        state.board.clear();
+        state.pieces.clear();
+        for (x, i, kind, is_white, is_royal, disguise, stun, move_stack) in placed {
+            let square = Square::new(x, height - 1 - i as i32);
+            let mut piece = state.create_piece(kind, if is_white { 0 } else { 1 });
+            piece.pos = Some(square);
+            piece.stun = stun;
+            piece.move_stack = move_stack;
+            piece.is_royal = is_royal;
+            piece.disguise = disguise;
+            state.board.insert(square, piece.id.clone());
+            state.pieces.insert(piece.id.clone(), piece);
         }
         
-        Ok(())
+        let parse_square = |t: &str| Square::from_notation(t)
+            .filter(|sq| sq.is_within(width, height))
+            .ok_or(format!("잘못된 칸입니다: {}", t));
+        for square in fields[2].split(',').filter(|t| *t != "-" && !t.is_empty()) {
+            let piece_id = state.board.get(&parse_square(square)?).cloned()
+                .ok_or(format!("{} 칸에 기물이 없습니다", square))?;
+            state.pieces.get_mut(&piece_id).unwrap().has_moved = true;
+        }
+        state.en_passant_target = match fields[3] {
+            "-" => None,
+            t => Some(parse_square(t)?),
+        };
+        
+        for (player, field) in [(0, fields[4]), (1, fields[5])] {
+            let mut specs = Vec::new();
+            for token in field.split(',').filter(|t| *t != "-" && !t.is_empty()) {
+                let (kind, _, rest) = read_kind(token)?;
+                if !rest.is_empty() {
+                    return Err(format!("포켓 표기가 올바르지 않습니다: {}", token));
+                }
+                specs.push(PieceSpec::new(kind));
+            }
+            state.pockets.insert(player, specs);
+        }
+        state.refresh_zobrist();
+        state.reset_repetitions();
+        
+        Ok(state)
     }
     
-    // === WASM용 추가 메서드들 ===
+    /// 버그 재현용 상태 덤프
+    ///
+    /// 국면 문자열에 더해 턴 진행 상태(턴, 활성 기물, 행동 여부, 프로모션 대기)와 전역 상태,
+    /// 이동 생성에 쓰이는 규칙 설정(로얄 안전, 캡처, 체크 중 착수, 행동 병행, 규칙 수치)과 사용자 정의 기물,
+    /// 보드 위 기물 종류별 행마 스크립트를 줄 단위로 담습니다. 기물 ID는 다시 읽을 때 바뀌므로
+    /// 활성 기물과 프로모션 대기 기물은 칸으로 적습니다. `script` 줄은 참고용이며 읽을 때 무시하고,
+    /// 함수로 지정하는 `RuleConfig::placement_stun_fn`은 담지 않아 기본값으로 읽힙니다.
+    pub fn to_debug_dump(&self) -> String {
+        let square_of = |id: &Option<PieceId>| {
+            id.as_ref()
+                .and_then(|id| self.pieces.get(id)?.pos)
+                .map_or("-".to_string(), |sq| sq.to_notation())
+        };
+        let globals: Vec<String> = self.global_keys().iter()
+            .map(|key| format!("{}={}", key, self.get_global(key)))
+            .collect();
+        let move_stack_table: Vec<String> = self.rules.move_stack_table.iter()
+            .map(|(score, stack)| format!("{}={}", score, stack))
+            .collect();
+        
+        let mut lines = vec![
+            format!("position: {}", self.to_position_string()),
+            format!("turn: {}", if self.turn == 0 { "w" } else { "b" }),
+            format!("active_piece: {}", square_of(&self.active_piece)),
+            format!("action_taken: {}", self.action_taken as u8),
+            format!("pending_promotion: {}", square_of(&self.pending_promotion)),
+            format!("global: {}", globals.join(",")),
+            format!("royal_safety: {}", self.royal_safety as u8),
+            format!("capture_rule: {:?}", self.capture_rule),
+            format!("drop_in_check: {:?}", self.drop_in_check),
+            format!("action_policy: {:?}", self.action_policy),
+            format!("move_stack_table: {}", move_stack_table.join(",")),
+            format!("default_move_stack: {}", self.rules.default_move_stack),
+            format!("max_pocket_score: {}", self.rules.max_pocket_score),
+        ];
+        let mut customs: Vec<(&String, &CustomPiece)> = self.custom_pieces.iter().collect();
+        customs.sort_by_key(|(name, _)| *name);
+        lines.extend(customs.into_iter().map(|(name, custom)| {
+            // 스크립트의 `#` 주석이 뒤를 가리지 않도록 줄바꿈을 이스케이프
+            let script = custom.white_script.replace('\\', "\\\\").replace('\n', "\\n");
+            format!("custom {}: {} {}", name, custom.score, script)
+        }));
+        let mut scripts: Vec<(String, String)> = self.pieces.values()
+            .filter(|p| p.pos.is_some())
+            .map(|p| {
+                let script = self.script_for(p.effective_kind(), p.is_white());
+                (format!("{:?}({})", p.effective_kind(), if p.is_white() { "w" } else { "b" }),
+                 script.split_whitespace().collect::<Vec<_>>().join(" "))
+            })
+            .collect();
+        scripts.sort();
+        scripts.dedup();
+        lines.extend(scripts.into_iter().map(|(name, script)| format!("script {}: {}", name, script)));
+        lines.join("\n")
+    }
     
-    /// 인자 없이 새 게임 생성
-    pub fn new_default() -> Self {
-        Self::new(0)
+    /// `to_debug_dump`으로 만든 덤프에서 상태 복원
+    pub fn from_debug_dump(text: &str) -> Result<GameState, GameError> {
+        Self::parse_debug_dump(text).map_err(GameError::Parse)
     }
     
-    /// 초기 포지션 설정 (킹 + 기본 포켓)
-    pub fn setup_initial_position(&mut self) {
-        // 킹은 이미 배치됨
-        // 기본 포켓 설정 (표준 체스 기물들)
-        let white_pocket = vec![
-            PieceSpec::new(PieceKind::Queen),
-            PieceSpec::new(PieceKind::Rook),
-            PieceSpec::new(PieceKind::Rook),
-            PieceSpec::new(PieceKind::Bishop),
-            PieceSpec::new(PieceKind::Bishop),
-            PieceSpec::new(PieceKind::Knight),
-            PieceSpec::new(PieceKind::Knight),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-        ];
-        let _ = self.setup_pocket(0, white_pocket);
+    fn parse_debug_dump(text: &str) -> Result<GameState, String> {
+        let mut fields: HashMap<&str, &str> = HashMap::new();
+        let mut customs: Vec<(&str, &str)> = Vec::new();
+        for line in text.lines().filter(|l| !l.trim().is_empty() && !l.starts_with("script ")) {
+            // 끝 공백이 잘린 "global:" 같은 빈 값은 허용
+            let (key, value) = line.split_once(':').ok_or(format!("덤프 줄 형식이 올바르지 않습니다: {}", line))?;
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match key.strip_prefix("custom ") {
+                Some(name) => customs.push((name, value)),
+                None => {
+                    fields.insert(key, value);
+                }
+            }
+        }
+        let field = |key: &str| fields.get(key).copied().ok_or(format!("덤프에 {} 항목이 없습니다", key));
+        let piece_at = |state: &GameState, value: &str| -> Result<Option<PieceId>, String> {
+            if value == "-" {
+                return Ok(None);
+            }
+            let square = Square::from_notation(value).ok_or(format!("잘못된 칸입니다: {}", value))?;
+            state.board.get(&square).cloned().map(Some).ok_or(format!("{} 칸에 기물이 없습니다", value))
+        };
+        let parse_int = |t: &str| t.parse::<i32>().map_err(|_| format!("숫자가 아닙니다: {}", t));
+        // 규칙 열거형은 `{:?}` 이름으로 적음
+        fn parse_variant<T: Copy + std::fmt::Debug>(key: &str, value: &str, variants: &[T]) -> Result<T, String> {
+            variants.iter().copied()
+                .find(|v| format!("{:?}", v) == value)
+                .ok_or(format!("알 수 없는 {} 값입니다: {}", key, value))
+        }
         
-        let black_pocket = vec![
-            PieceSpec::new(PieceKind::Queen),
-            PieceSpec::new(PieceKind::Rook),
-            PieceSpec::new(PieceKind::Rook),
-            PieceSpec::new(PieceKind::Bishop),
-            PieceSpec::new(PieceKind::Bishop),
-            PieceSpec::new(PieceKind::Knight),
-            PieceSpec::new(PieceKind::Knight),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-            PieceSpec::new(PieceKind::Pawn),
-        ];
-        let _ = self.setup_pocket(1, black_pocket);
+        // 이전 덤프에는 규칙 항목이 없음 (기본 규칙)
+        let mut state = GameState::parse_position_string(field("position")?)?;
+        state.turn = match field("turn")? {
+            "w" => 0,
+            "b" => 1,
+            other => return Err(format!("알 수 없는 색입니다: {}", other)),
+        };
+        state.active_piece = piece_at(&state, field("active_piece")?)?;
+        state.action_taken = field("action_taken")? == "1";
+        state.pending_promotion = piece_at(&state, field("pending_promotion")?)?;
+        for entry in field("global")?.split(',').filter(|e| !e.is_empty()) {
+            let (key, value) = entry.split_once('=').ok_or(format!("전역 상태 표기가 올바르지 않습니다: {}", entry))?;
+            let value = value.parse().map_err(|_| format!("숫자가 아닙니다: {}", value))?;
+            state.set_global(key, value);
+        }
+        if let Some(value) = fields.get("royal_safety").copied() {
+            state.royal_safety = value == "1";
+        }
+        if let Some(value) = fields.get("capture_rule").copied() {
+            state.capture_rule = parse_variant("capture_rule", value,
+                &[CaptureRule::StackTransfer, CaptureRule::Remove, CaptureRule::ReturnToPocket])?;
+        }
+        if let Some(value) = fields.get("drop_in_check").copied() {
+            state.drop_in_check = parse_variant("drop_in_check", value,
+                &[DropInCheckPolicy::BlockingOnly, DropInCheckPolicy::Forbidden, DropInCheckPolicy::Allowed])?;
+        }
+        if let Some(value) = fields.get("action_policy").copied() {
+            state.action_policy = parse_variant("action_policy", value,
+                &[TurnActionPolicy::Exclusive, TurnActionPolicy::MoveAndAction])?;
+        }
+        if let Some(value) = fields.get("move_stack_table").copied() {
+            state.rules.move_stack_table = value.split(',')
+                .filter(|e| !e.is_empty())
+                .map(|entry| {
+                    let (score, stack) = entry.split_once('=').ok_or(format!("이동 스택 구간 표기가 올바르지 않습니다: {}", entry))?;
+                    Ok((parse_int(score)?, parse_int(stack)?))
+                })
+                .collect::<Result<_, String>>()?;
+        }
+        if let Some(value) = fields.get("default_move_stack").copied() {
+            state.rules.default_move_stack = parse_int(value)?;
+        }
+        if let Some(value) = fields.get("max_pocket_score").copied() {
+            state.rules.max_pocket_score = parse_int(value)?;
+        }
+        for (name, value) in customs {
+            let (score, script) = value.split_once(' ').unwrap_or((value, ""));
+            let mut unescaped = String::with_capacity(script.len());
+            let mut chars = script.chars();
+            while let Some(ch) = chars.next() {
+                if ch != '\\' {
+                    unescaped.push(ch);
+                    continue;
+                }
+                match chars.next() {
+                    Some('n') => unescaped.push('\n'),
+                    Some('\\') => unescaped.push('\\'),
+                    _ => return Err(format!("{} 기물 스크립트의 이스케이프가 올바르지 않습니다", name)),
+                }
+            }
+            state.register_custom_piece(name.to_string(), unescaped, parse_int(score)?);
+        }
+        state.refresh_zobrist();
+        state.reset_repetitions();
+        Ok(state)
     }
     
-    /// 실험용 포켓 설정 (다양한 기물들)
-    pub fn setup_experimental_pocket(&mut self) {
-        // 백 실험용 포켓 (점수 무제한)
-        let white_pocket = vec![
-            PieceSpec::new(PieceKind::Amazon),
-            PieceSpec::new(PieceKind::Grasshopper),
-            PieceSpec::new(PieceKind::Knightrider),
-            PieceSpec::new(PieceKind::Archbishop),
-            PieceSpec::new(PieceKind::Dabbaba),
-            PieceSpec::new(PieceKind::Alfil),
-            PieceSpec::new(PieceKind::Ferz),
-            PieceSpec::new(PieceKind::Centaur),
-            PieceSpec::new(PieceKind::Camel),
-            PieceSpec::new(PieceKind::TempestRook),
-            PieceSpec::new(PieceKind::Cannon),
-            PieceSpec::new(PieceKind::Experiment),
-        ];
-        self.setup_pocket_unchecked(0, white_pocket);
-        
-        // 흑 실험용 포켓 (점수 무제한)
-        let black_pocket = vec![
-            PieceSpec::new(PieceKind::Amazon),
-            PieceSpec::new(PieceKind::Grasshopper),
-            PieceSpec::new(PieceKind::Knightrider),
-            PieceSpec::new(PieceKind::Archbishop),
-            PieceSpec::new(PieceKind::Dabbaba),
-            PieceSpec::new(PieceKind::Alfil),
-            PieceSpec::new(PieceKind::Ferz),
-            PieceSpec::new(PieceKind::Centaur),
-            PieceSpec::new(PieceKind::Camel),
-            PieceSpec::new(PieceKind::TempestRook),
-            PieceSpec::new(PieceKind::Cannon),
-            PieceSpec::new(PieceKind::Experiment),
-        ];
-        self.setup_pocket_unchecked(1, black_pocket);
+    /// 국면 빌더 생성
+    pub fn builder() -> GameStateBuilder {
+        GameStateBuilder::new()
     }
     
-    /// 현재 플레이어 가져오기
-    pub fn current_player(&self) -> PlayerId {
-        self.turn
+    /// 보드와 기물 목록의 일관성 검사 (칸마다 기물 하나, 위치 일치, 보드 크기 안, 스턴/스택 음수 없음)
+    pub fn validate_invariants(&self) -> Result<(), GameError> {
+        for (square, piece_id) in &self.board {
+            if !self.contains_square(*square) {
+                return Err(GameError::InvariantViolation(format!(
+                    "{} 칸이 {}x{} 보드 밖입니다", square.to_notation(), self.board_width, self.board_height,
+                )));
+            }
+            let piece = self.pieces.get(piece_id).ok_or_else(|| GameError::InvariantViolation(
+                format!("{} 칸의 기물 {}이(가) 목록에 없습니다", square.to_notation(), piece_id),
+            ))?;
+            if piece.pos != Some(*square) {
+                return Err(GameError::InvariantViolation(format!("기물 {}의 위치가 보드({})와 다릅니다", piece_id, square.to_notation())));
+            }
+        }
+        for piece in self.pieces.values() {
+            if let Some(pos) = piece.pos {
+                if self.board.get(&pos) != Some(&piece.id) {
+                    return Err(GameError::InvariantViolation(format!("기물 {}이(가) 보드의 {} 칸에 없습니다", piece.id, pos.to_notation())));
+                }
+            }
+            if piece.stun < 0 || piece.move_stack < 0 {
+                return Err(GameError::InvariantViolation(format!("기물 {}의 스택이 음수입니다", piece.id)));
+            }
+        }
+        Ok(())
     }
     
-    /// 모든 기물 가져오기 (보드 위의 기물만)
-    pub fn get_all_pieces(&self) -> Vec<PieceInfo> {
-        self.pieces.values()
-            .filter(|p| p.pos.is_some())
-            .map(|p| PieceInfo {
-                id: p.id.clone(),
-                kind: p.kind.clone(),
-                owner: p.owner,
-                pos: p.pos.unwrap(),
-                stun_stack: p.stun,
-                move_stack: p.move_stack,
-                is_royal: p.is_royal,
-            })
-            .collect()
+    /// 국면만 복사한 상태 (AI 탐색/체크 판정용, 캐시와 이벤트/되돌리기 기록은 비우고 이후 기록하거나 턴을 자동으로 넘기지 않음)
+    pub fn clone_bare(&self) -> GameState {
+        let mut bare = self.clone();
+        bare.invalidate_caches();
+        bare.events.clear();
+        bare.clear_history();
+        bare.history_enabled = false;
+        bare.auto_end_turn = false;
+        bare
     }
     
-    /// 특정 플레이어의 포켓 가져오기
-    pub fn get_pocket(&self, player: PlayerId) -> Vec<PieceKind> {
-        self.pockets.get(&player)
-            .map(|specs| specs.iter().map(|s| s.kind.clone()).collect())
-            .unwrap_or_default()
+    /// 현재 국면을 되돌리기 기록으로 (기록이 꺼져 있으면 None)
+    fn history_record(&self) -> Option<MoveRecord> {
+        self.history_enabled.then(|| MoveRecord {
+            board: self.board.clone(),
+            pockets: self.pockets.clone(),
+            pieces: self.pieces.clone(),
+            turn: self.turn,
+            global_state: self.global_state.clone(),
+            active_piece: self.active_piece.clone(),
+            action_taken: self.action_taken,
+            pending_promotion: self.pending_promotion.clone(),
+            captured_value: self.captured_value.clone(),
+            turns_played: self.turns_played,
+            en_passant_target: self.en_passant_target,
+            en_passant_pending: self.en_passant_pending,
+            next_piece_id: self.next_piece_id,
+            repetitions: self.repetitions.clone(),
+            events_len: self.events.len(),
+            events_tail: Vec::new(),
+        })
     }
     
-    /// 포켓에서 배치 가능한지 확인
-    pub fn can_place_from_pocket(&self, kind: &PieceKind, square: Square) -> bool {
-        self.can_place(self.turn, kind, square).is_ok()
+    /// 성공한 행동의 기록 추가 (새 행동이므로 다시 실행 기록은 버림)
+    fn push_history(&mut self, record: Option<MoveRecord>) {
+        if let Some(record) = record {
+            self.history.push(Arc::new(record));
+            self.redo_stack.clear();
+        }
     }
     
-    /// 특정 위치의 기물 이동 가능 칸 계산 (Square로 조회)
-    pub fn get_legal_moves_at(&self, square: Square) -> Vec<LegalMove> {
-        if let Some(piece_id) = self.board.get(&square) {
-            self.get_legal_moves(piece_id)
-        } else {
-            Vec::new()
-        }
+    /// 기록의 국면으로 되돌림 (이벤트는 호출하는 쪽에서 맞춤)
+    fn restore_record(&mut self, record: &MoveRecord) {
+        self.invalidate_caches();
+        self.board = record.board.clone();
+        self.pockets = record.pockets.clone();
+        self.pieces = record.pieces.clone();
+        self.turn = record.turn;
+        self.global_state = record.global_state.clone();
+        self.active_piece = record.active_piece.clone();
+        self.action_taken = record.action_taken;
+        self.pending_promotion = record.pending_promotion.clone();
+        self.captured_value = record.captured_value.clone();
+        self.turns_played = record.turns_played;
+        self.en_passant_target = record.en_passant_target;
+        self.en_passant_pending = record.en_passant_pending;
+        self.next_piece_id = record.next_piece_id;
+        self.refresh_zobrist();
+        self.repetitions = record.repetitions.clone();
     }
     
-    /// 이동 유효성 확인 (Square로 조회)
-    pub fn is_valid_move_at(&self, from: Square, to: Square) -> bool {
-        if let Some(piece_id) = self.board.get(&from) {
-            self.is_valid_move(piece_id, from, to)
-        } else {
-            false
+    /// 마지막 행동(착수/이동/캡처/계승/위장/스턴/프로모션/턴 종료) 되돌리기
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        let record = self.history.pop().ok_or(GameError::NothingToUndo)?;
+        let tail = self.events.split_off(record.events_len.min(self.events.len()));
+        if let Some(mut current) = self.history_record() {
+            current.events_tail = tail;
+            self.redo_stack.push(Arc::new(current));
         }
+        self.restore_record(&record);
+        Ok(())
     }
     
-    /// 기물에 스턴 부여
-    pub fn stun_piece(&mut self, piece_id: &PieceId, amount: i32) -> Result<(), String> {
-        let piece = self.pieces.get_mut(piece_id).ok_or("기물을 찾을 수 없습니다")?;
-        
-        // 아군: 1~3, 적: 1
-        let is_ally = piece.owner == self.turn;
-        if is_ally {
-            if amount < 1 || amount > 3 {
-                return Err("아군에게는 1~3 스턴만 부여할 수 있습니다".to_string());
-            }
-        } else {
-            if amount != 1 {
-                return Err("적에게는 1 스턴만 부여할 수 있습니다".to_string());
-            }
+    /// 되돌린 행동 다시 실행
+    pub fn redo(&mut self) -> Result<(), GameError> {
+        let record = self.redo_stack.pop().ok_or(GameError::NothingToRedo)?;
+        if let Some(current) = self.history_record() {
+            self.history.push(Arc::new(current));
         }
-        
-        piece.stun += amount;
-        self.action_taken = true;
+        self.restore_record(&record);
+        self.events.extend(record.events_tail.iter().cloned());
         Ok(())
     }
     
-    /// 액션 적용
-    pub fn apply_action(&mut self, action: Action) {
-        match action {
-            Action::Place { piece_id, target } => {
-                // 포켓에서 해당 기물 찾아서 배치
-                if let Some(piece) = self.pieces.get(&piece_id) {
-                    let _ = self.can_place(self.turn, &piece.kind, target);
-                    // TODO: 실제 배치 로직
-                }
-            }
-            Action::Move { piece_id, from, to } => {
-                // MoveType 찾기
-                let legal_moves = self.get_legal_moves_at(from);
-                for legal_move in legal_moves {
-                    if to == legal_move.to {
-                        let _ = self.move_piece_by_legal_moves(legal_move);
-                    } 
-                }
+    /// 되돌릴 수 있는 행동 수
+    pub fn undo_depth(&self) -> usize {
+        self.history.len()
+    }
+    
+    /// 다시 실행할 수 있는 행동 수
+    pub fn redo_depth(&self) -> usize {
+        self.redo_stack.len()
+    }
+    
+    /// 되돌리기/다시 실행 기록 비우기
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.redo_stack.clear();
+    }
+    
+    /// 기물 하나의 Zobrist 해시 (보드 밖 기물은 0)
+    fn piece_zobrist(piece: &Piece) -> u64 {
+        let Some(pos) = piece.pos else { return 0 };
+        let square = ((pos.y as u64) << 8) | pos.x as u64;
+        let mut hash = zobrist_key(ZOBRIST_PIECE, [zobrist_kind(&piece.kind), piece.owner as u64, square]);
+        if piece.is_royal {
+            hash ^= zobrist_key(ZOBRIST_ROYAL, [piece.owner as u64, square, 0]);
+        }
+        if let Some(disguise) = &piece.disguise {
+            hash ^= zobrist_key(ZOBRIST_DISGUISE, [zobrist_kind(disguise), square, 0]);
+        }
+        if piece.has_moved {
+            hash ^= zobrist_key(ZOBRIST_MOVED, [piece.owner as u64, square, 0]);
+        }
+        hash ^ zobrist_key(ZOBRIST_STACKS, [square, piece.stun as u64, piece.move_stack as u64])
+    }
+    
+    /// 플레이어 포켓의 Zobrist 해시 (같은 종류는 n번째 기물마다 다른 키라 순서와 무관)
+    fn pocket_zobrist(&self, player: PlayerId) -> u64 {
+        let mut seen: Vec<u64> = Vec::new();
+        let mut hash = 0;
+        for spec in self.pockets.get(&player).into_iter().flatten() {
+            let kind = zobrist_kind(&spec.kind);
+            let nth = seen.iter().filter(|&&k| k == kind).count() as u64;
+            seen.push(kind);
+            hash ^= zobrist_key(ZOBRIST_POCKET, [player as u64, kind, nth]);
+        }
+        hash
+    }
+    
+    fn turn_zobrist(turn: PlayerId) -> u64 {
+        zobrist_key(ZOBRIST_TURN, [turn as u64, 0, 0])
+    }
+    
+    fn en_passant_zobrist(target: Option<Square>) -> u64 {
+        target.map_or(0, |sq| zobrist_key(ZOBRIST_EN_PASSANT, [sq.x as u64, sq.y as u64, 0]))
+    }
+    
+    /// 국면 전체의 Zobrist 해시 (기물 종류/소유자/위치, 로얄/위장, 스턴/이동 스택, 턴, 포켓, 앙파상 칸)
+    ///
+    /// 키는 고정 시드에서 만들어 실행마다 같습니다. 활성 기물 등 턴 진행 상태와 전역 상태는 포함하지 않습니다.
+    /// 매번 보드 전체를 훑으므로 반복 조회에는 `zobrist_key`를 씁니다.
+    pub fn zobrist_hash(&self) -> u64 {
+        let pieces = self.pieces.values().fold(0, |hash, piece| hash ^ Self::piece_zobrist(piece));
+        let pockets = [0, 1].iter().fold(0, |hash, &player| hash ^ self.pocket_zobrist(player));
+        pieces ^ pockets ^ Self::turn_zobrist(self.turn) ^ Self::en_passant_zobrist(self.en_passant_target)
+    }
+    
+    /// 행동 실행기가 증분 갱신해 온 Zobrist 해시 (`zobrist_hash`와 같은 값)
+    ///
+    /// `board`/`pieces`/`pockets`를 직접 수정했다면 `refresh_zobrist`를 먼저 호출해야 합니다.
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist
+    }
+    
+    /// 증분 Zobrist 해시를 전체 계산으로 다시 맞춤
+    pub fn refresh_zobrist(&mut self) {
+        self.zobrist = self.zobrist_hash();
+    }
+    
+    /// 사용자 정의 기물(`PieceKind::Custom(name)`)의 행마 스크립트와 점수 등록
+    ///
+    /// 스크립트는 내장 기물처럼 백 기준(+y가 전진)으로 작성하며, 흑 스크립트는
+    /// `chessembly::mirror_script`로 만듭니다. 같은 이름으로 다시 등록하면 덮어씁니다.
+    /// 이미 보드에 있는 기물의 스택은 바뀌지 않고 다음 재충전부터 새 점수를 따릅니다.
+    pub fn register_custom_piece(&mut self, name: String, script: String, score: i32) {
+        self.invalidate_caches();
+        let black_script = chessembly::mirror_script(&script);
+        self.custom_pieces.insert(name, CustomPiece { white_script: script, black_script, score });
+    }
+    
+    /// 기물 종류의 행마 스크립트 (등록된 Custom 스크립트 우선)
+    pub fn script_for(&self, kind: &PieceKind, is_white: bool) -> &str {
+        if let PieceKind::Custom(name) = kind {
+            if let Some(custom) = self.custom_pieces.get(name) {
+                return if is_white { &custom.white_script } else { &custom.black_script };
             }
-            Action::Stun { piece_id, amount } => {
-                let _ = self.stun_piece(&piece_id, amount);
+        }
+        kind.chessembly_script(is_white)
+    }
+    
+    /// 기물 종류의 점수 (등록된 Custom 점수 우선, 등록되지 않은 Custom은 `PieceKind::score`의 3점)
+    pub fn score_of(&self, kind: &PieceKind) -> i32 {
+        Self::score_in(&self.custom_pieces, kind)
+    }
+    
+    /// `score_of`를 `pieces`를 빌린 채로 쓰기 위한 형태
+    fn score_in(custom_pieces: &HashMap<String, CustomPiece>, kind: &PieceKind) -> i32 {
+        match kind {
+            PieceKind::Custom(name) => custom_pieces.get(name).map_or_else(|| kind.score(), |c| c.score),
+            _ => kind.score(),
+        }
+    }
+    
+    /// 현재 국면의 반복 횟수만 1로 남기고 기록을 비움
+    ///
+    /// 착수/잡기/프로모션은 자동으로 기록을 비우며, 그 밖에 되돌릴 수 없는 변화를 준 뒤 직접 호출합니다.
+    pub fn reset_repetitions(&mut self) {
+        self.repetitions.clear();
+        self.repetitions.insert(self.zobrist, 1);
+    }
+    
+    /// 현재 국면이 턴 시작 시점 기준으로 나온 횟수
+    pub fn repetition_count(&self) -> u32 {
+        self.repetitions.get(&self.zobrist).copied().unwrap_or(0)
+    }
+    
+    /// 같은 국면(보드, 턴, 포켓, 스턴/이동 스택)이 세 번 나왔는지
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+    
+    /// 기물과 포켓의 해시를 증분 해시에 XOR (변경 전후로 한 번씩 호출)
+    fn toggle_zobrist(&mut self, piece_ids: &[PieceId], players: &[PlayerId]) {
+        for (i, piece_id) in piece_ids.iter().enumerate() {
+            if piece_ids[..i].contains(piece_id) {
+                continue;
             }
-            Action::Crown { piece_id } => {
-                if let Some(piece) = self.pieces.get_mut(&piece_id) {
-                    piece.is_royal = true;
-                }
+            if let Some(piece) = self.pieces.get(piece_id) {
+                self.zobrist ^= Self::piece_zobrist(piece);
             }
-            Action::Disguise { piece_id, as_kind } => {
-                if let Some(piece) = self.pieces.get_mut(&piece_id) {
-                    piece.disguise = Some(as_kind);
-                }
+        }
+        for &player in players {
+            self.zobrist ^= self.pocket_zobrist(player);
+        }
+    }
+    
+    /// 공격 칸 캐시 무효화 (`board`/`pieces`를 직접 수정한 뒤 호출)
+    pub fn invalidate_caches(&mut self) {
+        self.attacked_by.take();
+    }
+    
+    /// 플레이어가 공격하는 칸 (캐시가 없으면 양쪽 모두 계산해 저장)
+    ///
+    /// `is_check`와 스크립트의 danger/check 정보가 같은 계산 결과를 공유합니다.
+    pub fn attacked_squares(&self, by: PlayerId) -> &HashSet<Square> {
+        let cache = self.attacked_by.get_or_init(|| {
+            #[cfg(debug_assertions)]
+            self.attack_computations.set(self.attack_computations.get() + 1);
+            (0..2).map(|p| (p, self.compute_attacked_squares(p))).collect()
+        });
+        &cache[&by]
+    }
+    
+    /// `by`의 보드 위 기물이 그 칸을 잡을 수 있는지 (Take/TakeMove/Catch 도착 칸, Jump가 뛰어넘어 잡는 칸)
+    ///
+    /// 실제 보드에서 스크립트를 실행한 `attacked_squares`를 조회하므로 가로막힌 슬라이드나
+    /// 아군 기물이 있는 칸은 공격으로 세지 않습니다.
+    pub fn is_square_attacked(&self, square: Square, by: PlayerId) -> bool {
+        self.attacked_squares(by).contains(&square)
+    }
+    
+    /// 특정 기물의 이동 가능한 칸 목록 계산 (chessembly 사용)
+    pub fn get_legal_moves(&self, piece_id: &PieceId) -> Vec<LegalMove> {
+        // 이동 불가 상태 확인
+        match self.pieces.get(piece_id) {
+            Some(piece) if piece.can_move() => self.filter_royal_safety(piece, self.generate_moves(piece_id)),
+            _ => Vec::new(),
+        }
+    }
+    
+    /// `royal_safety`가 켜져 있으면 이동한 뒤 로얄 기물이 공격받는 이동을 제거
+    ///
+    /// 이동을 복사본에 실제로 적용해 판정하므로, 로얄이 비운 칸 너머로 이어지는 슬라이드와
+    /// 상대가 지키는 기물을 잡는 이동도 걸러집니다.
+    fn filter_royal_safety(&self, piece: &Piece, moves: Vec<LegalMove>) -> Vec<LegalMove> {
+        if !self.royal_safety || !piece.is_royal {
+            return moves;
+        }
+        let fork = self.fork_for_turn(piece.owner);
+        moves.into_iter()
+            .filter(|mv| {
+                let mut next = fork.clone_bare();
+                next.move_piece_by_legal_moves(mv.clone()).is_ok()
+                    && next.pieces.get(&piece.id).and_then(|p| p.pos)
+                        .is_some_and(|sq| !next.attacked_squares(1 - piece.owner).contains(&sq))
+            })
+            .collect()
+    }
+    
+    /// 이동 후 기물이 머무는 칸 (Catch는 제자리 또는 전진 칸)
+    fn landing_square(mv: &LegalMove) -> Square {
+        match mv.move_type {
+            MoveType::Catch => mv.advance_to.unwrap_or(mv.from),
+            _ => mv.to,
+        }
+    }
+    
+    /// `get_legal_moves`와 같으며 스크립트 실행 추적을 함께 반환 (이동 불가면 둘 다 빈 목록)
+    pub fn get_legal_moves_traced(&self, piece_id: &PieceId) -> (Vec<LegalMove>, Vec<TraceStep>) {
+        let mut trace = Vec::new();
+        let moves = match self.pieces.get(piece_id) {
+            Some(piece) if piece.can_move() => {
+                let mut moves = self.run_script(piece_id, true, Some(&mut trace));
+                moves.extend(self.en_passant_moves(piece_id));
+                self.filter_royal_safety(piece, moves)
             }
+            _ => Vec::new(),
+        };
+        (moves, trace)
+    }
+    
+    /// 기물의 행마법 스크립트를 실행해 이동 목록 생성 (스턴/이동 스택 검사 없음)
+    fn generate_moves(&self, piece_id: &PieceId) -> Vec<LegalMove> {
+        let mut moves = self.run_script(piece_id, true, None);
+        moves.extend(self.en_passant_moves(piece_id));
+        moves
+    }
+    
+    /// 폰의 앙파상 잡기 (`en_passant_target`으로 뛰고 그 칸을 지나친 상대 폰을 잡는 Jump)
+    fn en_passant_moves(&self, piece_id: &PieceId) -> Vec<LegalMove> {
+        let (Some(piece), Some(target)) = (self.pieces.get(piece_id), self.en_passant_target) else {
+            return Vec::new();
+        };
+        let Some(pos) = piece.pos else { return Vec::new() };
+        let forward = if piece.is_white() { 1 } else { -1 };
+        // 지나친 폰은 목표 칸 바로 앞, 즉 잡는 폰과 같은 랭크에 있음
+        let victim_square = Square::new(target.x, pos.y);
+        let is_enemy_pawn = self.board.get(&victim_square)
+            .and_then(|id| self.pieces.get(id))
+            .is_some_and(|victim| victim.owner != piece.owner && victim.effective_kind() == &PieceKind::Pawn);
+        if piece.effective_kind() != &PieceKind::Pawn || (target.x - pos.x).abs() != 1
+            || target.y != pos.y + forward || self.board.contains_key(&target) || !is_enemy_pawn {
+            return Vec::new();
         }
+        vec![LegalMove {
+            from: pos,
+            to: target,
+            move_type: MoveType::Jump,
+            is_capture: true,
+            tags: Vec::new(),
+            catch_to: Some(victim_square),
+            advance_to: None,
+        }]
     }
-}
-
-/// JS용 기물 정보 구조체
-#[derive(Debug, Clone)]
-pub struct PieceInfo {
-    pub id: PieceId,
-    pub kind: PieceKind,
-    pub owner: PlayerId,
-    pub pos: Square,
-    pub stun_stack: i32,
-    pub move_stack: i32,
-    pub is_royal: bool,
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
     
-    #[test]
-    fn test_initial_setup() {
-        let state = GameState::new(0);
+    /// 기물 스크립트를 실행해 LegalMove 목록 생성
+    /// `with_threats`가 false면 danger/check 정보 없이 실행 (공격 칸 계산용)
+    fn run_script(&self, piece_id: &PieceId, with_threats: bool, trace: Option<&mut Vec<TraceStep>>) -> Vec<LegalMove> {
+        let mut legal_moves = Vec::new();
         
-        // 킹 위치 확인
-        let white_king = state.get_piece_at(Square::new(4, 0));
-        assert!(white_king.is_some());
-        assert_eq!(white_king.unwrap().kind, PieceKind::King);
-        assert!(white_king.unwrap().is_royal);
-        assert_eq!(white_king.unwrap().stun, 0);
-        assert_eq!(white_king.unwrap().move_stack, 3);
+        let piece = match self.pieces.get(piece_id) {
+            Some(p) => p,
+            None => return legal_moves,
+        };
+        
+        let pos = match piece.pos {
+            Some(p) => p,
+            None => return legal_moves,
+        };
+        
+        // 행마법 스크립트 가져오기
+        let script = self.script_for(piece.effective_kind(), piece.is_white());
+        
+        // chessembly 보드 상태 생성 (위협 정보는 스크립트가 쓸 때만 계산)
+        let needs_threats = with_threats && (script.contains("danger") || script.contains("check"));
+        let mut board = match self.to_chessembly_board(piece_id, needs_threats) {
+            Some(b) => b,
+            None => return legal_moves,
+        };
+        
+        // chessembly 인터프리터 실행
+        let mut interpreter = Interpreter::new();
+        interpreter.set_debug(self.debug_mode);
+        interpreter.parse(script);
+        let result = match trace {
+            Some(trace) => interpreter.execute_traced(&mut board).map(|(activations, steps)| {
+                *trace = steps;
+                activations
+            }),
+            None => interpreter.execute(&mut board),
+        };
+        // 스크립트 오류는 게임을 멈추지 않고 이동 없음으로 처리
+        let activations = match result {
+            Ok(activations) => activations,
+            Err(err) => {
+                chessembly::log_debug(&format!("[Engine] {:?} 스크립트 오류: {}", piece.effective_kind(), err));
+                return legal_moves;
+            }
+        };
+        
+        // 활성화된 칸들을 LegalMove로 변환
+        for activation in activations {
+            let target = Square::new(pos.x + activation.dx, pos.y + activation.dy);
+            let catch_to = activation.catch_to.map(|(x, y)| Square::new(pos.x + x, pos.y + y));
+            
+            // 보드 범위 확인
+            if !self.contains_square(target) {
+                continue;
+            }
+            
+            let is_capture = self.board.contains_key(&target);
+            
+            legal_moves.push(LegalMove {
+                from: pos,
+                to: target,
+                move_type: activation.move_type,
+                is_capture,
+                tags: activation.tags,
+                catch_to,
+                advance_to: activation.advance_to.map(|(x, y)| Square::new(pos.x + x, pos.y + y)),
+            });
+        }
+        
+        // 같은 이동이 태그 없이도 활성화됐으면 태그가 붙은 쪽만 남김
+        // (예: `move(0, 1); edge-top(0, 2) transition(queen) move(0, 1);`의 마지막 랭크 전진은 승급)
+        let key = |mv: &LegalMove| (mv.to, mv.move_type, mv.catch_to, mv.advance_to);
+        let tagged: Vec<_> = legal_moves.iter().filter(|mv| !mv.tags.is_empty()).map(key).collect();
+        legal_moves.retain(|mv| !mv.tags.is_empty() || !tagged.contains(&key(mv)));
+        
+        legal_moves
+    }
+    
+    /// 이동이 잡을 수 있는 칸 (Jump는 뛰어넘는 칸)
+    fn capture_square(mv: &LegalMove) -> Option<Square> {
+        match mv.move_type {
+            MoveType::TakeMove | MoveType::Take | MoveType::Catch => Some(mv.to),
+            MoveType::Jump => mv.catch_to,
+            MoveType::Move | MoveType::Shift => None,
+        }
+    }
+    
+    /// 기물이 잡을 수 있는 칸 목록 (스턴/이동 스택과 무관한 행마 기준)
+    fn capture_targets(&self, piece_id: &PieceId) -> Vec<Square> {
+        self.run_script(piece_id, false, None).iter()
+            .filter_map(Self::capture_square)
+            .collect()
+    }
+    
+    /// 기물이 위협하는 모든 칸 (빈 칸/아군 칸 포함, 스턴 무관)
+    ///
+    /// 각 칸에 적 기물이 있다고 가정했을 때 잡을 수 있는지로 판정하므로,
+    /// 폰의 대각선처럼 지금은 비어 있는 칸에 대한 위협도 포함합니다.
+    pub fn attacks_of(&self, piece_id: &PieceId) -> Vec<Square> {
+        let Some(piece) = self.pieces.get(piece_id) else { return Vec::new() };
+        let Some(pos) = piece.pos else { return Vec::new() };
+        let enemy = 1 - piece.owner;
+        
+        let mut result = Vec::new();
+        for y in 0..self.board_height {
+            for x in 0..self.board_width {
+                let square = Square::new(x, y);
+                if square == pos {
+                    continue;
+                }
+                let fork = match self.board.get(&square) {
+                    Some(occupant_id) => {
+                        let mut fork = self.clone_bare();
+                        if let Some(occupant) = fork.pieces.get_mut(occupant_id) {
+                            occupant.owner = enemy;
+                        }
+                        fork
+                    }
+                    None => self.with_virtual_piece(PieceKind::Pawn, enemy, square),
+                };
+                if fork.capture_targets(piece_id).contains(&square) {
+                    result.push(square);
+                }
+            }
+        }
+        result
+    }
+    
+    /// 기물 주인의 턴에 최대 `steps`번 연속 이동하며 둘 수 있는 모든 수
+    fn move_sequences(&self, piece_id: &PieceId, steps: i32) -> Vec<LegalMove> {
+        let Some(owner) = self.pieces.get(piece_id).map(|p| p.owner) else {
+            return Vec::new();
+        };
+        
+        let mut result = Vec::new();
+        let mut seen = HashSet::new();
+        let mut frontier = vec![self.fork_for_turn(owner)];
+        for _ in 0..steps {
+            let mut next_frontier = Vec::new();
+            for state in &frontier {
+                for mv in state.get_legal_moves(piece_id) {
+                    result.push(mv.clone());
+                    let mut next = state.clone();
+                    if next.move_piece_by_legal_moves(mv).is_err() {
+                        continue;
+                    }
+                    // 같은 위치/스택/보드 기물 수에 도달한 경로는 한 번만 탐색
+                    if let Some(p) = next.pieces.get(piece_id) {
+                        if seen.insert((p.pos, p.move_stack, p.stun, next.board.len())) {
+                            next_frontier.push(next);
+                        }
+                    }
+                }
+            }
+            frontier = next_frontier;
+        }
+        result
+    }
+    
+    /// 기물이 주인의 턴에 최대 `steps`번 연속 이동으로 도달할 수 있는 칸
+    pub fn reachable_within(&self, piece_id: &PieceId, steps: i32) -> HashSet<Square> {
+        self.move_sequences(piece_id, steps).into_iter().map(|m| m.to).collect()
+    }
+    
+    /// 상대 턴에 이동 스택을 모두 써서 플레이어의 로얄 피스를 잡을 수 있는 (적 기물, 로얄 위치) 목록
+    ///
+    /// 한 번의 이동만 보는 체크와 달리, 이동 스택이 큰 기물의 여러 번 이동 경로까지 고려합니다.
+    /// 상대 턴이 아니면 턴을 넘긴 상태(이동 스택 재충전)를 기준으로 계산합니다.
+    pub fn royal_threats(&self, player: PlayerId) -> Vec<(PieceId, Square)> {
+        let royals = self.royal_squares(player);
+        let fork = self.fork_for_turn(1 - player);
+        
+        let mut enemy_ids: Vec<PieceId> = fork.pieces.values()
+            .filter(|p| p.owner != player && p.pos.is_some())
+            .map(|p| p.id.clone())
+            .collect();
+        enemy_ids.sort();
+        
+        let mut threats = Vec::new();
+        for enemy_id in enemy_ids {
+            let steps = fork.pieces[&enemy_id].move_stack;
+            for mv in fork.move_sequences(&enemy_id, steps) {
+                if let Some(target) = Self::capture_square(&mv) {
+                    if royals.contains(&target) && !threats.contains(&(enemy_id.clone(), target)) {
+                        threats.push((enemy_id.clone(), target));
+                    }
+                }
+            }
+        }
+        threats
+    }
+    
+    /// 플레이어의 기물들이 공격하는 칸 집합
+    fn compute_attacked_squares(&self, by: PlayerId) -> HashSet<Square> {
+        self.pieces.values()
+            .filter(|p| p.owner == by && p.pos.is_some())
+            .flat_map(|p| self.capture_targets(&p.id))
+            .collect()
+    }
+    
+    /// 플레이어의 로얄 피스 위치 목록 (랭크, 파일 순 정렬)
+    pub fn royal_squares(&self, player: PlayerId) -> Vec<Square> {
+        let mut squares: Vec<Square> = self.pieces.values()
+            .filter(|p| p.owner == player && p.is_royal)
+            .filter_map(|p| p.pos)
+            .collect();
+        squares.sort_by_key(|sq| (sq.y, sq.x));
+        squares
+    }
+    
+    /// 플레이어의 로얄 피스 중 하나라도 공격받고 있는지
+    pub fn is_check(&self, player: PlayerId) -> bool {
+        !self.checked_royals(player).is_empty()
+    }
+    
+    /// 이동을 적용하면 상대 로얄 피스가 공격받는지 (기보의 "+" 표기, AI 수 정렬용)
+    pub fn move_gives_check(&self, mv: &LegalMove) -> bool {
+        let Some(mover) = self.get_piece_at(mv.from) else {
+            return false;
+        };
+        let opponent = 1 - mover.owner;
+        let mut fork = self.clone_bare();
+        fork.move_piece_by_legal_moves(mv.clone()).is_ok() && fork.is_check(opponent)
+    }
+    
+    /// 상대 로얄 피스를 바로 공격하게 되는 포켓 착수 목록 (공격 AI, 퍼즐 제작용)
+    ///
+    /// 착수 스턴과 무관하게 놓인 기물의 위협으로 판정합니다.
+    pub fn checking_drops(&self, player: PlayerId) -> Vec<(PieceKind, Square)> {
+        let fork = self.fork_for_turn(player);
+        let mut kinds = fork.get_pocket(player);
+        kinds.dedup();
+        
+        let mut drops = Vec::new();
+        for kind in kinds {
+            for sq in fork.squares() {
+                if fork.can_place(player, &kind, sq).is_ok()
+                    && fork.with_virtual_piece(kind.clone(), player, sq).is_check(1 - player)
+                    && !drops.contains(&(kind.clone(), sq))
+                {
+                    drops.push((kind.clone(), sq));
+                }
+            }
+        }
+        drops
+    }
+    
+    /// 상대에게 공격받고 있는 플레이어의 로얄 피스 위치 (UI 강조 표시용)
+    pub fn checked_royals(&self, player: PlayerId) -> Vec<Square> {
+        let attacked = self.attacked_squares(1 - player);
+        self.royal_squares(player).into_iter().filter(|sq| attacked.contains(sq)).collect()
+    }
+    
+    /// 플레이어의 로얄 피스를 공격 중인 (적 기물 위치, 로얄 위치) 쌍
+    fn checkers(&self, player: PlayerId) -> Vec<(Square, Square)> {
+        let royals = self.royal_squares(player);
+        let mut result = Vec::new();
+        for piece in self.pieces.values().filter(|p| p.owner != player) {
+            let Some(pos) = piece.pos else { continue };
+            for target in self.capture_targets(&piece.id) {
+                if royals.contains(&target) {
+                    result.push((pos, target));
+                }
+            }
+        }
+        result
+    }
+    
+    /// 슬라이드 체크를 가로막을 수 있는 칸 목록
+    ///
+    /// 모든 체크 경로(`Square::line_to`)에 공통으로 놓인 빈 칸 중, 실제로 기물을 놓았을 때
+    /// 체크가 풀리는 칸만 반환합니다. 체크가 아니거나 도약 기물의 체크면 빈 목록입니다.
+    pub fn check_blocking_squares(&self, player: PlayerId) -> Vec<Square> {
+        let checkers = self.checkers(player);
+        let Some(((first_from, first_to), rest)) = checkers.split_first() else {
+            return Vec::new();
+        };
+        
+        let mut candidates: Vec<Square> = first_from.line_to(*first_to)
+            .filter(|sq| !self.board.contains_key(sq))
+            .collect();
+        for (from, to) in rest {
+            let line: Vec<Square> = from.line_to(*to).collect();
+            candidates.retain(|sq| line.contains(sq));
+        }
+        
+        // 가상의 차단 기물을 놓아 실제로 체크가 풀리는지 확인 (도약 행마 배제)
+        candidates.into_iter()
+            .filter(|&sq| !self.with_virtual_piece(PieceKind::Pawn, player, sq).is_check(player))
+            .collect()
+    }
+    
+    /// 빈 칸에 기물을 놓았다고 가정했을 때의 이동 목록 (상태 변경 없음, 착수 미리보기용)
+    ///
+    /// 착수 스턴과 무관하게 행마만 계산하며, 칸이 이미 차 있으면 빈 목록입니다.
+    pub fn legal_moves_hypothetical(&self, kind: PieceKind, square: Square, owner: PlayerId) -> Vec<LegalMove> {
+        if !self.contains_square(square) || self.board.contains_key(&square) {
+            return Vec::new();
+        }
+        let fork = self.with_virtual_piece(kind, owner, square);
+        match fork.board.get(&square) {
+            Some(piece_id) => fork.generate_moves(piece_id),
+            None => Vec::new(),
+        }
+    }
+    
+    /// 빈 칸에 가상의 기물을 놓은 복사본 (착수 결과 미리보기용)
+    fn with_virtual_piece(&self, kind: PieceKind, owner: PlayerId, square: Square) -> GameState {
+        let mut fork = self.clone_bare();
+        let piece = fork.create_piece(kind, owner);
+        let piece_id = piece.id.clone();
+        fork.pieces.insert(piece_id.clone(), Piece { pos: Some(square), ..piece });
+        fork.board.insert(square, piece_id);
+        fork.refresh_zobrist();
+        fork
+    }
+    
+    /// 플레이어의 턴이 시작된 상태의 복사본 (상대 턴이면 턴을 넘김)
+    fn fork_for_turn(&self, player: PlayerId) -> GameState {
+        let mut fork = self.clone();
+        if fork.turn != player {
+            fork.advance_turn();
+        }
+        fork
+    }
+    
+    /// 체크메이트 여부: 체크 상태이며 이동이나 차단 착수로 벗어날 수 없음
+    pub fn is_checkmate(&self, player: PlayerId) -> bool {
+        if !self.is_check(player) {
+            return false;
+        }
+        let fork = self.fork_for_turn(player);
+        
+        // 이동으로 체크 회피 (이동, 차단, 공격 기물 잡기)
+        let piece_ids: Vec<PieceId> = fork.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some())
+            .map(|p| p.id.clone())
+            .collect();
+        for piece_id in &piece_ids {
+            for mv in fork.get_legal_moves(piece_id) {
+                let mut next = fork.clone();
+                if next.move_piece_by_legal_moves(mv).is_ok() && !next.is_check(player) {
+                    return false;
+                }
+            }
+        }
+        
+        // 차단 칸에 착수해 체크 회피
+        let blocking = fork.check_blocking_squares(player);
+        for kind in fork.get_pocket(player) {
+            for &sq in &blocking {
+                let mut next = fork.clone();
+                if next.place_piece(player, kind.clone(), sq).is_ok() && !next.is_check(player) {
+                    return false;
+                }
+            }
+        }
+        
+        true
+    }
+    
+    /// 이동이 유효한지 확인 (chessembly 기반)
+    pub fn is_valid_move(&self, piece_id: &PieceId, from: Square, to: Square) -> bool {
+        let legal_moves = self.get_legal_moves(piece_id);
+        legal_moves.iter().any(|m| m.from == from && m.to == to)
+    }
+    
+    /// 이동의 MoveType 찾기
+    pub fn get_move_type(&self, piece_id: &PieceId, from: Square, to: Square) -> Option<MoveType> {
+        let legal_moves = self.get_legal_moves(piece_id);
+        legal_moves.iter()
+            .find(|m| m.from == from && m.to == to)
+            .map(|m| m.move_type)
+    }
+    
+    /// 프로모션 실행
+    pub fn promote(&mut self, piece_id: &PieceId, to_kind: PieceKind) -> Result<(), GameError> {
+        self.invalidate_caches();
+        let record = self.history_record();
+        let piece = self.pieces.get(piece_id).ok_or(GameError::PieceNotFound)?;
+        
+        // 프로모션 가능한 기물인지
+        if !piece.kind.can_promote() {
+            return Err(GameError::CannotPromote);
+        }
+        
+        // 유효한 프로모션 대상인지
+        if !piece.kind.promotion_targets().contains(&to_kind) {
+            return Err(GameError::InvalidPromotionTarget);
+        }
+        
+        // 프로모션 칸에 있는지
+        let pos = piece.pos.ok_or(GameError::NotOnBoard)?;
+        if !self.is_promotion_square(&piece.kind, pos, piece.owner) {
+            return Err(GameError::NotOnPromotionSquare);
+        }
+        
+        // 프로모션 실행 (스택 계승, `promotion_stun_fn`이 있으면 스턴 추가)
+        let extra_stun = self.promotion_stun_fn.map_or(0, |stun_for| stun_for(&to_kind).max(0));
+        if let Some(piece) = self.pieces.get_mut(piece_id) {
+            let old_hash = Self::piece_zobrist(piece);
+            piece.kind = to_kind.clone();
+            // 스택은 유지 (promotion.md: 이전 기물의 모든 스택값이 계승)
+            piece.stun += extra_stun;
+            self.zobrist ^= old_hash ^ Self::piece_zobrist(piece);
+        }
+        self.events.push(GameEvent::Promoted { piece_id: piece_id.clone(), to_kind });
+        self.repetitions.clear();
+        if self.pending_promotion.as_ref() == Some(piece_id) {
+            self.pending_promotion = None;
+        }
+        
+        self.push_history(record);
+        Ok(())
+    }
+    
+    /// 프로모션 후 스턴을 원래 기물의 `max_promotion_stun`까지 채움
+    ///
+    /// 프로모션 칸에 바로 착수했을 때의 스턴과 같아지므로, 걸어서 도달한 기물이
+    /// 착수보다 빨리 움직이지 않습니다. 이미 더 큰 스턴은 그대로 둡니다.
+    pub fn promote_with_stun(&mut self, piece_id: &PieceId, to_kind: PieceKind) -> Result<(), GameError> {
+        let max_stun = self.pieces.get(piece_id).map_or(0, |p| p.kind.max_promotion_stun());
+        self.promote(piece_id, to_kind)?;
+        if let Some(piece) = self.pieces.get_mut(piece_id) {
+            let old_hash = Self::piece_zobrist(piece);
+            piece.stun = piece.stun.max(max_stun);
+            self.zobrist ^= old_hash ^ Self::piece_zobrist(piece);
+        }
+        Ok(())
+    }
+    
+    // === WASM용 추가 메서드들 ===
+    
+    /// 인자 없이 새 게임 생성
+    pub fn new_default() -> Self {
+        Self::new(0)
+    }
+    
+    /// 초기 포지션 설정 (킹 + 기본 포켓)
+    pub fn setup_initial_position(&mut self) {
+        self.invalidate_caches();
+        // 킹은 이미 배치됨
+        // 기본 포켓 설정 (표준 체스 기물들)
+        let white_pocket = vec![
+            PieceSpec::new(PieceKind::Queen),
+            PieceSpec::new(PieceKind::Rook),
+            PieceSpec::new(PieceKind::Rook),
+            PieceSpec::new(PieceKind::Bishop),
+            PieceSpec::new(PieceKind::Bishop),
+            PieceSpec::new(PieceKind::Knight),
+            PieceSpec::new(PieceKind::Knight),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+        ];
+        let _ = self.setup_pocket(0, white_pocket);
+        
+        let black_pocket = vec![
+            PieceSpec::new(PieceKind::Queen),
+            PieceSpec::new(PieceKind::Rook),
+            PieceSpec::new(PieceKind::Rook),
+            PieceSpec::new(PieceKind::Bishop),
+            PieceSpec::new(PieceKind::Bishop),
+            PieceSpec::new(PieceKind::Knight),
+            PieceSpec::new(PieceKind::Knight),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+            PieceSpec::new(PieceKind::Pawn),
+        ];
+        let _ = self.setup_pocket(1, black_pocket);
+    }
+    
+    /// 실험용 포켓 설정 (다양한 기물들)
+    pub fn setup_experimental_pocket(&mut self) {
+        // 백 실험용 포켓 (점수 무제한)
+        let white_pocket = vec![
+            PieceSpec::new(PieceKind::Amazon),
+            PieceSpec::new(PieceKind::Grasshopper),
+            PieceSpec::new(PieceKind::Knightrider),
+            PieceSpec::new(PieceKind::Archbishop),
+            PieceSpec::new(PieceKind::Dabbaba),
+            PieceSpec::new(PieceKind::Alfil),
+            PieceSpec::new(PieceKind::Ferz),
+            PieceSpec::new(PieceKind::Centaur),
+            PieceSpec::new(PieceKind::Camel),
+            PieceSpec::new(PieceKind::TempestRook),
+            PieceSpec::new(PieceKind::Cannon),
+            PieceSpec::new(PieceKind::Experiment),
+        ];
+        self.setup_pocket_unchecked(0, white_pocket);
+        
+        // 흑 실험용 포켓 (점수 무제한)
+        let black_pocket = vec![
+            PieceSpec::new(PieceKind::Amazon),
+            PieceSpec::new(PieceKind::Grasshopper),
+            PieceSpec::new(PieceKind::Knightrider),
+            PieceSpec::new(PieceKind::Archbishop),
+            PieceSpec::new(PieceKind::Dabbaba),
+            PieceSpec::new(PieceKind::Alfil),
+            PieceSpec::new(PieceKind::Ferz),
+            PieceSpec::new(PieceKind::Centaur),
+            PieceSpec::new(PieceKind::Camel),
+            PieceSpec::new(PieceKind::TempestRook),
+            PieceSpec::new(PieceKind::Cannon),
+            PieceSpec::new(PieceKind::Experiment),
+        ];
+        self.setup_pocket_unchecked(1, black_pocket);
+    }
+    
+    /// 현재 플레이어 가져오기
+    pub fn current_player(&self) -> PlayerId {
+        self.turn
+    }
+    
+    /// 모든 기물 가져오기 (보드 위의 기물만)
+    pub fn get_all_pieces(&self) -> Vec<PieceInfo> {
+        self.pieces.values()
+            .filter(|p| p.pos.is_some())
+            .map(|p| PieceInfo {
+                id: p.id.clone(),
+                kind: p.kind.clone(),
+                owner: p.owner,
+                pos: p.pos.unwrap(),
+                stun_stack: p.stun,
+                move_stack: p.move_stack,
+                is_royal: p.is_royal,
+                score: self.score_of(p.effective_kind()),
+            })
+            .collect()
+    }
+    
+    /// 특정 플레이어의 포켓 가져오기
+    pub fn get_pocket(&self, player: PlayerId) -> Vec<PieceKind> {
+        self.pockets.get(&player)
+            .map(|specs| specs.iter().map(|s| s.kind.clone()).collect())
+            .unwrap_or_default()
+    }
+    
+    /// 포켓 점수 제한 안에서 더 추가할 수 있는 기물 종류 (드래프트 UI의 추가 메뉴용, 킹 제외)
+    pub fn affordable_additions(&self, player: PlayerId) -> Vec<PieceKind> {
+        let used: i32 = self.pockets.get(&player).map_or(0, |specs| specs.iter().map(|s| self.score_of(&s.kind)).sum());
+        let remaining = self.rules.max_pocket_score - used;
+        PieceKind::all().into_iter()
+            .filter(|kind| *kind != PieceKind::King && kind.score() <= remaining)
+            .collect()
+    }
+    
+    /// 포켓에서 배치 가능한지 확인
+    pub fn can_place_from_pocket(&self, kind: &PieceKind, square: Square) -> bool {
+        self.can_place(self.turn, kind, square).is_ok()
+    }
+    
+    /// 특정 위치의 기물 이동 가능 칸 계산 (Square로 조회)
+    pub fn get_legal_moves_at(&self, square: Square) -> Vec<LegalMove> {
+        if let Some(piece_id) = self.board.get(&square) {
+            self.get_legal_moves(piece_id)
+        } else {
+            Vec::new()
+        }
+    }
+    
+    /// 칸의 기물이 갈 수 있는 도착 칸만 (중복 제거, 보드 UI의 점 표시용)
+    pub fn legal_targets_at(&self, square: Square) -> Vec<Square> {
+        let mut targets: Vec<Square> = self.get_legal_moves_at(square).into_iter().map(|m| m.to).collect();
+        targets.sort_by_key(|sq| (sq.y, sq.x));
+        targets.dedup();
+        targets
+    }
+    
+    /// 이번 턴에 이동 중인 기물의 연속 이동 가능 칸 (이동 중인 기물이 없으면 빈 목록)
+    pub fn continuation_moves(&self) -> Vec<LegalMove> {
+        match &self.active_piece {
+            Some(piece_id) => self.get_legal_moves(piece_id),
+            None => Vec::new(),
+        }
+    }
+    
+    /// 이번 턴에 이동 중인 기물의 남은 이동 스택 (이동 중인 기물이 없으면 None)
+    ///
+    /// 캡처로 넘겨받은 스택이 누적되며 상한은 없습니다. 턴이 끝나면 `stack_refill`에 따라 다시 채워집니다.
+    pub fn active_move_budget(&self) -> Option<i32> {
+        self.active_piece.as_ref()
+            .and_then(|id| self.pieces.get(id))
+            .map(|p| p.move_stack)
+    }
+    
+    /// 플레이어의 보드 위 기물별 이동 가능 수 목록 (스턴/이동 스택 반영)
+    pub fn all_legal_moves(&self, player: PlayerId) -> HashMap<PieceId, Vec<LegalMove>> {
+        self.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some())
+            .map(|p| (p.id.clone(), self.get_legal_moves(&p.id)))
+            .collect()
+    }
+    
+    /// 기물의 이동 가능 칸을 MoveType별로 묶음 (UI에서 이동/잡기/특수 이동을 구분 표시)
+    pub fn legal_moves_grouped(&self, piece_id: &PieceId) -> HashMap<MoveType, Vec<Square>> {
+        let mut grouped: HashMap<MoveType, Vec<Square>> = HashMap::new();
+        for mv in self.get_legal_moves(piece_id) {
+            grouped.entry(mv.move_type).or_default().push(mv.to);
+        }
+        grouped
+    }
+    
+    /// 플레이어의 보드 위 기물별 이동 가능 수 개수 (UI 배지용, 스턴/스택 없으면 0)
+    pub fn move_counts(&self, player: PlayerId) -> HashMap<PieceId, usize> {
+        self.all_legal_moves(player).into_iter()
+            .map(|(id, moves)| (id, moves.len()))
+            .collect()
+    }
+    
+    /// 이동 유효성 확인 (Square로 조회)
+    pub fn is_valid_move_at(&self, from: Square, to: Square) -> bool {
+        if let Some(piece_id) = self.board.get(&from) {
+            self.is_valid_move(piece_id, from, to)
+        } else {
+            false
+        }
+    }
+    
+    /// 턴 플레이어가 기물에 스턴 부여 (`apply_stun`과 같은 검사)
+    pub fn stun_piece(&mut self, piece_id: &PieceId, amount: i32) -> Result<(), GameError> {
+        self.apply_stun(self.turn, piece_id, amount)
+    }
+    
+    /// 액션 적용 (실패하면 상태를 바꾸지 않고 오류 반환)
+    pub fn apply_action(&mut self, action: Action) -> Result<(), GameError> {
+        match action {
+            Action::Place { kind, target } => {
+                self.place_piece(self.turn, kind, target)?;
+            }
+            Action::Move { piece_id: _, from, to } => {
+                // 도착 칸이 일치하는 첫 행마로 이동
+                let legal_move = self.get_legal_moves_at(from).into_iter()
+                    .find(|mv| mv.to == to)
+                    .ok_or(GameError::IllegalMove)?;
+                self.move_piece_by_legal_moves(legal_move)?;
+            }
+            Action::Stun { piece_id, amount } => {
+                self.apply_stun(self.turn, &piece_id, amount)?;
+            }
+            Action::Castle { king_id, rook_id, side } => {
+                self.castle(self.turn, &king_id, &rook_id, side)?;
+            }
+            Action::Crown { piece_id } => {
+                self.crown_piece(self.turn, &piece_id)?;
+            }
+            Action::Disguise { piece_id, as_kind } => {
+                self.disguise_piece(self.turn, &piece_id, as_kind)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// 보드 위 기물의 칸 보너스 합 차이 (`player` 기준, 기물 점수 1 = 100)
+    pub fn positional_score(&self, player: PlayerId) -> i32 {
+        self.pieces.values()
+            .filter_map(|p| {
+                let sq = p.pos?;
+                let rank = if p.is_white() { sq.y } else { self.board_height - 1 - sq.y };
+                let index = (rank < 8 && sq.x < 8).then(|| (rank * 8 + sq.x) as usize);
+                let bonus = match (self.piece_square_tables.get(&p.kind), index) {
+                    (Some(table), Some(index)) => table[index],
+                    _ => central_bonus(sq, self.board_width, self.board_height),
+                };
+                Some(if p.owner == player { bonus } else { -bonus })
+            })
+            .sum()
+    }
+    
+    /// 국면 평가 (보드 + 포켓 기물 점수 차 ×100 + 칸 보너스, `player` 기준)
+    ///
+    /// 로얄이 모두 잡혀 승패가 난 상태는 `WIN_SCORE`로 평가합니다.
+    pub fn evaluate(&self, player: PlayerId) -> i32 {
+        match self.check_victory() {
+            GameResult::WhiteWins => return if player == 0 { WIN_SCORE } else { -WIN_SCORE },
+            GameResult::BlackWins => return if player == 1 { WIN_SCORE } else { -WIN_SCORE },
+            GameResult::Ongoing | GameResult::Draw => {}
+        }
+        
+        (self.material(player) - self.material(1 - player)) * 100 + self.positional_score(player)
+    }
+    
+    /// 현재 턴 플레이어의 한 수(이동 1회, 캐슬링 또는 착수 1회) 후 턴을 넘긴 상태들
+    ///
+    /// 프로모션 칸에 도달한 이동은 점수가 가장 높은 기물로 프로모션한 것으로 봅니다.
+    fn successors(&self) -> Vec<(Action, GameState)> {
+        let player = self.turn;
+        let mut result = Vec::new();
+        
+        let mut piece_ids: Vec<PieceId> = self.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some())
+            .map(|p| p.id.clone())
+            .collect();
+        piece_ids.sort();
+        for piece_id in &piece_ids {
+            for mv in self.get_legal_moves(piece_id) {
+                let action = Action::Move { piece_id: piece_id.clone(), from: mv.from, to: mv.to };
+                if let Some(next) = self.after_move(mv) {
+                    result.push((action, next));
+                }
+            }
+        }
+        
+        for action in self.legal_castles() {
+            let mut next = self.clone_bare();
+            if next.apply_action(action.clone()).is_ok() {
+                next.advance_turn();
+                result.push((action, next));
+            }
+        }
+        
+        let mut kinds = self.get_pocket(player);
+        kinds.dedup();
+        for kind in kinds {
+            for y in 0..self.board_height {
+                for x in 0..self.board_width {
+                    let target = Square::new(x, y);
+                    if self.can_place(player, &kind, target).is_err() {
+                        continue;
+                    }
+                    let mut next = self.clone_bare();
+                    if next.place_piece(player, kind.clone(), target).is_ok() {
+                        next.advance_turn();
+                        result.push((Action::Place { kind: kind.clone(), target }, next));
+                    }
+                }
+            }
+        }
+        
+        result
+    }
+    
+    /// 이동 후 턴을 넘긴 상태 (프로모션 칸에 도달하면 점수가 가장 높은 기물로 프로모션)
+    fn after_move(&self, mv: LegalMove) -> Option<GameState> {
+        let mut next = self.clone_bare();
+        next.move_piece_by_legal_moves(mv).ok()?;
+        if let Some(promoting) = next.pending_promotion() {
+            let best = next.pieces[&promoting].kind.promotion_targets().into_iter().max_by_key(PieceKind::score);
+            if let Some(kind) = best {
+                let _ = next.promote(&promoting, kind);
+            }
+        }
+        next.advance_turn();
+        Some(next)
+    }
+    
+    /// 턴 플레이어가 적 기물을 잡는 이동만 적용한 다음 상태들 (정지 탐색용)
+    fn capture_successors(&self) -> Vec<GameState> {
+        let player = self.turn;
+        let mut piece_ids: Vec<PieceId> = self.pieces.values()
+            .filter(|p| p.owner == player && p.pos.is_some())
+            .map(|p| p.id.clone())
+            .collect();
+        piece_ids.sort();
+        piece_ids.iter()
+            .flat_map(|piece_id| self.get_legal_moves(piece_id))
+            .filter(|mv| {
+                Self::capture_square(mv)
+                    .and_then(|sq| self.get_piece_at(sq))
+                    .is_some_and(|victim| victim.owner != player)
+            })
+            .filter_map(|mv| self.after_move(mv))
+            .collect()
+    }
+    
+    /// 정지 탐색: 잡기만 이어서 두다가 잡을 것이 없거나 잡지 않는 편이 나으면 평가
+    ///
+    /// 고정 깊이 탐색이 잡기 교환 도중에 멈춰 되잡힘을 놓치는 것을 막습니다.
+    /// 잡기가 스택을 옮기므로 교환이 길어질 수 있어 `QUIESCENCE_MAX_DEPTH`에서 끊습니다.
+    fn quiescence(&self, depth: u32) -> i32 {
+        let stand_pat = self.evaluate(self.turn);
+        if depth == 0 || self.check_victory() != GameResult::Ongoing {
+            return stand_pat;
+        }
+        self.capture_successors().iter()
+            .map(|next| -next.quiescence(depth - 1))
+            .fold(stand_pat, i32::max)
+    }
+    
+    /// 턴 플레이어의 결정적인 첫 합법 액션 (`Action::encode` 값이 가장 작은 이동/착수/캐슬링)
+    ///
+    /// 셀프 플레이 테스트나 단순 봇에서 무작위 대신 쓰는 선택기입니다. 착수가 이동보다, 이동이 캐슬링보다 먼저 옵니다.
+    pub fn first_legal_action(&self) -> Option<Action> {
+        let player = self.turn;
+        let mut actions = Vec::new();
+        
+        for kind in self.get_pocket(player) {
+            for y in 0..self.board_height {
+                for x in 0..self.board_width {
+                    let target = Square::new(x, y);
+                    if self.can_place(player, &kind, target).is_ok() {
+                        actions.push(Action::Place { kind: kind.clone(), target });
+                    }
+                }
+            }
+        }
+        for piece in self.pieces.values().filter(|p| p.owner == player && p.pos.is_some()) {
+            for mv in self.get_legal_moves(&piece.id) {
+                if self.can_move_piece(player, &piece.id, mv.from, mv.to, mv.move_type).is_ok() {
+                    actions.push(Action::Move { piece_id: piece.id.clone(), from: mv.from, to: mv.to });
+                }
+            }
+        }
+        actions.extend(self.legal_castles());
+        
+        actions.into_iter().min_by_key(Action::encode)
+    }
+    
+    /// 네가맥스 탐색 (턴 플레이어 기준 점수, 끝에서 정지 탐색)
+    fn negamax(&self, depth: u32) -> i32 {
+        if depth == 0 {
+            return self.quiescence(QUIESCENCE_MAX_DEPTH);
+        }
+        if self.check_victory() != GameResult::Ongoing {
+            return self.evaluate(self.turn);
+        }
+        self.successors().iter()
+            .map(|(_, next)| -next.negamax(depth - 1))
+            .max()
+            .unwrap_or_else(|| self.evaluate(self.turn))
+    }
+    
+    /// 턴 플레이어의 최선의 응수 (`depth` 수 앞까지 탐색, 0이면 1로 취급)
+    ///
+    /// 학습 모드에서 "X를 두면 상대가 Y로 응수한다"를 보여주는 용도입니다.
+    /// 이동, 캐슬링, 착수만 후보로 고려하며, 같은 점수면 먼저 생성된 후보를 고릅니다.
+    pub fn best_reply(&self, depth: u32) -> Option<Action> {
+        let mut best: Option<(i32, Action)> = None;
+        for (action, next) in self.successors() {
+            let score = -next.negamax(depth.max(1) - 1);
+            if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+                best = Some((score, action));
+            }
+        }
+        best.map(|(_, action)| action)
+    }
+    
+    /// 턴 안에서 할 수 있는 행동 하나씩을 적용한 다음 상태들 (perft용)
+    ///
+    /// 이동(같은 턴의 연속 이동 포함), 착수, 계승, 위장, 스턴, 턴 종료를 각각 한 단계로 봅니다.
+    /// 프로모션 대기 중이면 프로모션 선택만 가능합니다. 검증은 실제 실행 경로에 맡깁니다.
+    fn turn_action_successors(&self) -> Vec<GameState> {
+        let player = self.turn;
+        let mut result = Vec::new();
+        
+        if let Some(promoting) = self.pending_promotion() {
+            for kind in self.pieces[&promoting].kind.promotion_targets() {
+                let mut next = self.clone_bare();
+                if next.promote(&promoting, kind).is_ok() {
+                    result.push(next);
+                }
+            }
+            return result;
+        }
+        
+        let mut on_board: Vec<&Piece> = self.pieces.values().filter(|p| p.pos.is_some()).collect();
+        on_board.sort_by(|a, b| a.id.cmp(&b.id));
+        
+        for piece in on_board.iter().filter(|p| p.owner == player) {
+            for mv in self.get_legal_moves(&piece.id) {
+                let mut next = self.clone_bare();
+                if next.move_piece_by_legal_moves(mv).is_ok() {
+                    result.push(next);
+                }
+            }
+        }
+        for action in self.legal_castles() {
+            let mut next = self.clone_bare();
+            if next.apply_action(action).is_ok() {
+                result.push(next);
+            }
+        }
+        
+        let mut kinds: Vec<PieceKind> = Vec::new();
+        for kind in self.get_pocket(player) {
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        for kind in kinds {
+            for y in 0..self.board_height {
+                for x in 0..self.board_width {
+                    let mut next = self.clone_bare();
+                    if next.place_piece(player, kind.clone(), Square::new(x, y)).is_ok() {
+                        result.push(next);
+                    }
+                }
+            }
+        }
+        
+        if self.can_take_action(player).is_ok() {
+            for piece in &on_board {
+                if piece.owner == player && !piece.is_royal {
+                    let mut next = self.clone_bare();
+                    if next.crown_piece(player, &piece.id).is_ok() {
+                        result.push(next);
+                    }
+                }
+                if piece.owner == player && piece.is_royal {
+                    for kind in PieceKind::all() {
+                        let mut next = self.clone_bare();
+                        if next.disguise_piece(player, &piece.id, kind).is_ok() {
+                            result.push(next);
+                        }
+                    }
+                }
+                let amounts = if piece.owner == player { 1..=3 } else { 1..=1 };
+                for amount in amounts {
+                    let mut next = self.clone_bare();
+                    if next.apply_stun(player, &piece.id, amount).is_ok() {
+                        result.push(next);
+                    }
+                }
+            }
+        }
+        
+        let mut next = self.clone_bare();
+        if next.end_turn().is_ok() {
+            result.push(next);
+        }
+        result
+    }
+    
+    /// `depth` 단계까지의 모든 행동 순서 수 (규칙 검증용 perft)
+    ///
+    /// 한 단계는 `turn_action_successors`의 행동 하나이므로, 한 턴의 연속 이동은
+    /// `active_piece`와 이동 스택이 허용하는 만큼 여러 단계로 나뉘고 턴 종료도 한 단계입니다.
+    /// 승패가 난 국면에서는 더 이어가지 않습니다.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.check_victory() != GameResult::Ongoing {
+            return 0;
+        }
+        let successors = self.turn_action_successors();
+        if depth == 1 {
+            return successors.len() as u64;
+        }
+        successors.iter().map(|next| next.perft(depth - 1)).sum()
+    }
+}
+
+/// 탐색에서 승패가 난 상태의 평가 점수
+const WIN_SCORE: i32 = 100_000;
+
+/// Zobrist 키 시드 (실행과 플랫폼이 달라도 같은 해시가 나오도록 고정)
+const ZOBRIST_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Zobrist 키의 특징 종류
+const ZOBRIST_PIECE: u64 = 1;
+const ZOBRIST_ROYAL: u64 = 2;
+const ZOBRIST_DISGUISE: u64 = 3;
+const ZOBRIST_STACKS: u64 = 4;
+const ZOBRIST_POCKET: u64 = 5;
+const ZOBRIST_TURN: u64 = 6;
+const ZOBRIST_MOVED: u64 = 7;
+const ZOBRIST_EN_PASSANT: u64 = 8;
+
+/// splitmix64 한 단계
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// 특징 종류와 값으로 정해지는 Zobrist 키 (표 대신 시드 해시로 만들어 스택 값에 상한이 없음)
+fn zobrist_key(feature: u64, values: [u64; 3]) -> u64 {
+    values.iter().fold(splitmix64(ZOBRIST_SEED ^ feature), |key, &value| splitmix64(key ^ value))
+}
+
+/// Zobrist 키용 기물 종류 번호 (Custom은 이름의 FNV-1a 해시)
+fn zobrist_kind(kind: &PieceKind) -> u64 {
+    match kind {
+        PieceKind::Custom(name) => name.bytes()
+            .fold(0xCBF2_9CE4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01B3)),
+        _ => kind.code().map_or(0, u64::from),
+    }
+}
+
+/// 정지 탐색에서 이어 볼 최대 잡기 수
+const QUIESCENCE_MAX_DEPTH: u32 = 6;
+
+/// 기본 칸 보너스: 가장자리 0, 안쪽 고리마다 5 (8x8에서 중앙 4칸 15)
+fn central_bonus(square: Square, width: i32, height: i32) -> i32 {
+    let ring = |v: i32, size: i32| v.min(size - 1 - v);
+    ring(square.x, width).min(ring(square.y, height)) * 5
+}
+
+/// 테스트/퍼즐용 국면 빌더
+///
+/// `royal`/`stun`/`move_stack`은 마지막으로 추가한 기물에 적용됩니다.
+/// 같은 칸에 다시 기물을 추가하면 이전 기물을 대체합니다.
+#[derive(Debug, Clone, Default)]
+pub struct GameStateBuilder {
+    pieces: Vec<Piece>,
+    turn: PlayerId,
+    pockets: HashMap<PlayerId, Vec<PieceSpec>>,
+}
+
+impl GameStateBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 보드에 기물 추가 (스턴 0, 이동 스택은 기본 규칙의 점수 기준 초기값)
+    pub fn piece(mut self, kind: PieceKind, owner: PlayerId, square: Square) -> Self {
+        self.pieces.retain(|p| p.pos != Some(square));
+        let mut piece = Piece::new(String::new(), kind, owner);
+        piece.pos = Some(square);
+        piece.move_stack = RuleConfig::default().move_stack(piece.score());
+        self.pieces.push(piece);
+        self
+    }
+    
+    /// 마지막 기물을 로얄 피스로 지정
+    pub fn royal(mut self) -> Self {
+        if let Some(piece) = self.pieces.last_mut() {
+            piece.is_royal = true;
+        }
+        self
+    }
+    
+    /// 마지막 기물의 스턴 스택 지정
+    pub fn stun(mut self, amount: i32) -> Self {
+        if let Some(piece) = self.pieces.last_mut() {
+            piece.stun = amount;
+        }
+        self
+    }
+    
+    /// 마지막 기물의 이동 스택 지정
+    pub fn move_stack(mut self, amount: i32) -> Self {
+        if let Some(piece) = self.pieces.last_mut() {
+            piece.move_stack = amount;
+        }
+        self
+    }
+    
+    /// 시작 플레이어 지정 (기본: 백)
+    pub fn turn(mut self, player: PlayerId) -> Self {
+        self.turn = player;
+        self
+    }
+    
+    /// 플레이어 포켓 지정 (점수 제한은 검사하지 않음)
+    pub fn pocket(mut self, player: PlayerId, kinds: Vec<PieceKind>) -> Self {
+        self.pockets.insert(player, kinds.into_iter().map(PieceSpec::new).collect());
+        self
+    }
+    
+    /// 국면 생성 (기물 ID는 추가한 순서대로 부여)
+    pub fn build(self) -> GameState {
+        let mut state = GameState::new(self.turn);
+        state.board.clear();
+        state.pieces.clear();
+        state.next_piece_id = 0;
+        for template in self.pieces {
+            let mut piece = state.create_piece(template.kind.clone(), template.owner);
+            piece.pos = template.pos;
+            piece.stun = template.stun;
+            piece.move_stack = template.move_stack;
+            piece.is_royal = template.is_royal;
+            if let Some(square) = piece.pos {
+                state.board.insert(square, piece.id.clone());
+            }
+            state.pieces.insert(piece.id.clone(), piece);
+        }
+        state.pockets.extend(self.pockets);
+        state.refresh_zobrist();
+        state.reset_repetitions();
+        state
+    }
+}
+
+/// JS용 기물 정보 구조체
+#[derive(Debug, Clone)]
+pub struct PieceInfo {
+    pub id: PieceId,
+    pub kind: PieceKind,
+    pub owner: PlayerId,
+    pub pos: Square,
+    pub stun_stack: i32,
+    pub move_stack: i32,
+    pub is_royal: bool,
+    pub score: i32, // 보이는 점수 (위장 기준)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 테스트용 기물 직접 배치 (스턴 0, 이동 스택은 점수 기준)
+    fn put_piece(state: &mut GameState, kind: PieceKind, owner: PlayerId, square: Square) -> PieceId {
+        let mut piece = state.create_piece(kind, owner);
+        let piece_id = piece.id.clone();
+        piece.pos = Some(square);
+        piece.move_stack = state.initial_move_stack(piece.score());
+        state.pieces.insert(piece_id.clone(), piece);
+        state.board.insert(square, piece_id.clone());
+        state.invalidate_caches();
+        piece_id
+    }
+    
+    fn targets(moves: &[LegalMove]) -> HashSet<Square> {
+        moves.iter().map(|m| m.to).collect()
+    }
+    
+    #[test]
+    fn test_initial_setup() {
+        let state = GameState::new(0);
+        
+        // 킹 위치 확인
+        let white_king = state.get_piece_at(Square::new(4, 0));
+        assert!(white_king.is_some());
+        assert_eq!(white_king.unwrap().kind, PieceKind::King);
+        assert!(white_king.unwrap().is_royal);
+        assert_eq!(white_king.unwrap().stun, 0);
+        assert_eq!(white_king.unwrap().move_stack, 3);
+        
+        let black_king = state.get_piece_at(Square::new(4, 7));
+        assert!(black_king.is_some());
+        assert_eq!(black_king.unwrap().kind, PieceKind::King);
+    }
+    
+    #[test]
+    fn test_move_stack_calculation() {
+        let state = GameState::new(0);
+        assert_eq!(state.initial_move_stack(1), 5);  // 폰
+        assert_eq!(state.initial_move_stack(2), 5);  // 다바바, 알필
+        assert_eq!(state.initial_move_stack(3), 3);  // 나이트, 비숍
+        assert_eq!(state.initial_move_stack(5), 3);  // 룩
+        assert_eq!(state.initial_move_stack(7), 2);  // 나이트라이더
+        assert_eq!(state.initial_move_stack(9), 1);  // 퀸
+        assert_eq!(state.initial_move_stack(13), 1); // 아마존
+    }
+    
+    #[test]
+    fn test_custom_move_stack_table() {
+        let mut state = GameState::new(0);
+        // 모든 기물이 5스택인 빠른 변형
+        state.rules = RuleConfig { move_stack_table: Vec::new(), default_move_stack: 5, ..RuleConfig::default() };
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Queen)]);
+        let queen = state.place_piece(0, PieceKind::Queen, Square::new(3, 0)).unwrap();
+        assert_eq!(state.pieces[&queen].move_stack, 5);
+        
+        // 구간 표: 퀸(9점)은 8점 이상 구간
+        state.rules.move_stack_table = vec![(1, 4), (8, 2)];
+        assert_eq!(state.initial_move_stack(PieceKind::Queen.score()), 2);
+        assert_eq!(state.initial_move_stack(PieceKind::Pawn.score()), 4);
+        assert_eq!(state.initial_move_stack(0), 5);
+    }
+    
+    #[test]
+    fn test_configurable_pocket_limit() {
+        let mut state = GameState::new(0);
+        state.rules.max_pocket_score = 20;
+        // 퀸 9 + 룩 5 + 나이트 3 + 비숍 3 = 20
+        let budget: Vec<PieceSpec> = [PieceKind::Queen, PieceKind::Rook, PieceKind::Knight, PieceKind::Bishop]
+            .into_iter().map(PieceSpec::new).collect();
+        let mut over = budget.clone();
+        over.push(PieceSpec::new(PieceKind::Pawn));
+        
+        assert_eq!(state.setup_pocket(0, over), Err(GameError::PocketScoreExceeded { total: 21, limit: 20 }));
+        assert_eq!(state.setup_pocket(0, budget), Ok(()));
+        assert!(state.affordable_additions(0).is_empty());
+    }
+    
+    #[test]
+    fn test_piece_scores() {
+        assert_eq!(PieceKind::Pawn.score(), 1);
+        assert_eq!(PieceKind::King.score(), 4);
+        assert_eq!(PieceKind::Queen.score(), 9);
+        assert_eq!(PieceKind::Rook.score(), 5);
+        assert_eq!(PieceKind::Knight.score(), 3);
+        assert_eq!(PieceKind::Bishop.score(), 3);
+        assert_eq!(PieceKind::Amazon.score(), 13);
+    }
+    
+    #[test]
+    fn test_pocket_score_limit() {
+        let mut state = GameState::new(0);
+        
+        // 39점 이하는 OK
+        let valid_pocket = vec![
+            PieceSpec { kind: PieceKind::Queen },  // 9
+            PieceSpec { kind: PieceKind::Rook },   // 5
+            PieceSpec { kind: PieceKind::Rook },   // 5
+            PieceSpec { kind: PieceKind::Bishop }, // 3
+            PieceSpec { kind: PieceKind::Bishop }, // 3
+            PieceSpec { kind: PieceKind::Knight }, // 3
+            PieceSpec { kind: PieceKind::Knight }, // 3
+            PieceSpec { kind: PieceKind::Pawn },   // 1
+            PieceSpec { kind: PieceKind::Pawn },   // 1
+            PieceSpec { kind: PieceKind::Pawn },   // 1
+            PieceSpec { kind: PieceKind::Pawn },   // 1
+            PieceSpec { kind: PieceKind::Pawn },   // 1
+            PieceSpec { kind: PieceKind::Pawn },   // 1
+            PieceSpec { kind: PieceKind::Pawn },   // 1
+            PieceSpec { kind: PieceKind::Pawn },   // 1
+        ]; // 총 39점
+        assert!(state.setup_pocket(0, valid_pocket).is_ok());
+        
+        // 39점 초과는 에러
+        let invalid_pocket = vec![
+            PieceSpec { kind: PieceKind::Amazon }, // 13
+            PieceSpec { kind: PieceKind::Queen },  // 9
+            PieceSpec { kind: PieceKind::Queen },  // 9
+            PieceSpec { kind: PieceKind::Queen },  // 9
+        ]; // 총 40점
+        assert!(state.setup_pocket(1, invalid_pocket).is_err());
+    }
+    
+    #[test]
+    fn test_capture_stack_transfer() {
+        let mut state = GameState::new(0);
+        
+        // 공격자 배치 (나이트: 3점, 이동3)
+        let attacker = state.create_piece(PieceKind::Knight, 0);
+        let attacker_id = attacker.id.clone();
+        state.pieces.insert(attacker_id.clone(), attacker);
+        if let Some(p) = state.pieces.get_mut(&attacker_id) {
+            p.pos = Some(Square::new(0, 0));
+            p.move_stack = 3;
+            p.stun = 0;
+        }
+        state.board.insert(Square::new(0, 0), attacker_id.clone());
+        
+        // 피해자 배치 (룩: 5점, 이동3, 스턴2)
+        let victim = state.create_piece(PieceKind::Rook, 1);
+        let victim_id = victim.id.clone();
+        state.pieces.insert(victim_id.clone(), victim);
+        if let Some(p) = state.pieces.get_mut(&victim_id) {
+            p.pos = Some(Square::new(2, 1));
+            p.move_stack = 3;
+            p.stun = 2;
+        }
+        state.board.insert(Square::new(2, 1), victim_id.clone());
+        
+        // 캡처 실행
+        state.capture(&attacker_id, &victim_id).unwrap();
+        
+        // 결과 확인
+        let attacker = state.pieces.get(&attacker_id).unwrap();
+        // 이동 스택: 3 - 1 + 3 = 5
+        assert_eq!(attacker.move_stack, 5);
+        // 스턴 스택: 0 + 2 = 2
+        assert_eq!(attacker.stun, 2);
+        
+        // 피해자 제거됨
+        assert!(!state.pieces.contains_key(&victim_id));
+    }
+    
+    #[test]
+    fn test_capture_chain_accumulates_stack() {
+        let mut state = GameState::new(0);
+        state.board.clear();
+        state.pieces.clear();
+        for (owner, square) in [(0, Square::new(7, 0)), (1, Square::new(7, 7))] {
+            let king = put_piece(&mut state, PieceKind::King, owner, square);
+            state.pieces.get_mut(&king).unwrap().is_royal = true;
+        }
+        let rook = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        for y in [2, 4, 6] {
+            put_piece(&mut state, PieceKind::Knight, 1, Square::new(0, y));
+        }
+        assert_eq!(state.active_move_budget(), None);
+        
+        // 나이트(스택 3)를 잡을 때마다 3 - 1 + 3, 상한 없이 누적
+        let mut expected = 3;
+        for y in [2, 4, 6] {
+            let from = state.pieces[&rook].pos.unwrap();
+            let mv = state.get_legal_moves(&rook).into_iter()
+                .find(|m| m.to == Square::new(0, y))
+                .unwrap();
+            assert!(state.move_piece_by_legal_moves(mv).unwrap().is_some());
+            expected += 2;
+            assert_eq!(state.active_move_budget(), Some(expected));
+            assert_eq!(state.pieces[&rook].pos, Some(Square::new(0, y)));
+            assert!(!state.board.contains_key(&from));
+        }
+        assert_eq!(state.active_move_budget(), Some(9));
+        
+        // 누적된 스택은 턴 경계에서 초기값으로 돌아감
+        state.end_turn().unwrap();
+        state.end_turn().unwrap();
+        assert_eq!(state.pieces[&rook].move_stack, state.initial_move_stack(PieceKind::Rook.score()));
+        assert_eq!(state.active_move_budget(), None);
+    }
+    
+    #[test]
+    fn test_capture_rule_remove_without_transfer() {
+        let mut state = GameState::new(0);
+        state.capture_rule = CaptureRule::Remove;
+        
+        let attacker_id = put_piece(&mut state, PieceKind::Knight, 0, Square::new(0, 0));
+        let victim_id = put_piece(&mut state, PieceKind::Rook, 1, Square::new(2, 1));
+        state.pieces.get_mut(&victim_id).unwrap().stun = 2;
+        
+        state.capture(&attacker_id, &victim_id).unwrap();
+        
+        let attacker = &state.pieces[&attacker_id];
+        // 이동 스택: 3 - 1 (이전 없음), 스턴 그대로
+        assert_eq!(attacker.move_stack, 2);
+        assert_eq!(attacker.stun, 0);
+        assert!(!state.pieces.contains_key(&victim_id));
+        assert!(!state.board.contains_key(&Square::new(2, 1)));
+    }
+    
+    #[test]
+    fn test_capture_rule_return_to_pocket() {
+        let mut state = GameState::new(0);
+        state.capture_rule = CaptureRule::ReturnToPocket;
+        
+        let attacker_id = put_piece(&mut state, PieceKind::Knight, 0, Square::new(0, 0));
+        let victim_id = put_piece(&mut state, PieceKind::Rook, 1, Square::new(2, 1));
+        
+        state.capture(&attacker_id, &victim_id).unwrap();
+        
+        assert_eq!(state.get_pocket(1), vec![PieceKind::Rook]);
+        assert!(!state.pieces.contains_key(&victim_id));
+    }
+    
+    #[test]
+    fn test_victory_condition() {
+        let mut state = GameState::new(0);
+        assert_eq!(state.check_victory(), GameResult::Ongoing);
+        
+        // 흑 킹 제거
+        let black_king_id = state.board.get(&Square::new(4, 7)).cloned();
+        if let Some(id) = black_king_id {
+            state.board.remove(&Square::new(4, 7));
+            state.pieces.remove(&id);
+        }
+        
+        assert_eq!(state.check_victory(), GameResult::WhiteWins);
+    }
+    
+    #[test]
+    fn test_square_notation() {
+        let e4 = Square::from_notation("e4").unwrap();
+        assert_eq!(e4.x, 4);
+        assert_eq!(e4.y, 3);
+        assert_eq!(e4.to_notation(), "e4");
+        
+        let a1 = Square::from_notation("a1").unwrap();
+        assert_eq!(a1.x, 0);
+        assert_eq!(a1.y, 0);
+        
+        let h8 = Square::from_notation("h8").unwrap();
+        assert_eq!(h8.x, 7);
+        assert_eq!(h8.y, 7);
+        
+        // 큰 보드의 두 자리 랭크
+        let j10 = Square::from_notation("j10").unwrap();
+        assert_eq!((j10.x, j10.y), (9, 9));
+        assert_eq!(j10.to_notation(), "j10");
+        
+        for bad in ["", "e", "E4", "e0", "e04", "4e", "e4x"] {
+            assert_eq!(Square::from_notation(bad), None, "{}", bad);
+        }
+    }
+    
+    #[test]
+    fn test_line_to() {
+        let sq = |n: &str| Square::from_notation(n).unwrap();
+        
+        // 파일: a1 -> a5
+        let file: Vec<Square> = sq("a1").line_to(sq("a5")).collect();
+        assert_eq!(file, vec![sq("a2"), sq("a3"), sq("a4")]);
+        
+        // 대각선 (역방향): f6 -> c3
+        let diagonal: Vec<Square> = sq("f6").line_to(sq("c3")).collect();
+        assert_eq!(diagonal, vec![sq("e5"), sq("d4")]);
+        
+        // 인접한 칸, 같은 칸: 사이 없음
+        assert_eq!(sq("e1").line_to(sq("e2")).count(), 0);
+        assert_eq!(sq("e1").line_to(sq("e1")).count(), 0);
+        
+        // 정렬되지 않은 칸: 빈 반복자
+        assert_eq!(sq("a1").line_to(sq("b3")).count(), 0);
+    }
+    
+    #[test]
+    fn test_pawn_promotion_stun() {
+        let state = GameState::new(0);
+        
+        // 폰 생성
+        let pawn = Piece::new("pawn1".to_string(), PieceKind::Pawn, 0);
+        
+        // 1랭크(y=0) 착수: 스턴 0
+        let stun_at_1 = state.calculate_placement_stun(&pawn, Square::new(0, 0));
+        assert_eq!(stun_at_1, 0);
+        
+        // 7랭크(y=6) 착수: 스턴 ~7 (거리 1)
+        let stun_at_7 = state.calculate_placement_stun(&pawn, Square::new(0, 6));
+        assert!(stun_at_7 > 0);
+    }
+    
+    #[test]
+    fn test_crown_piece() {
+        let mut state = GameState::new(0);
+        
+        // 폰 배치
+        state.pockets.insert(0, vec![PieceSpec { kind: PieceKind::Pawn }]);
+        let pawn_id = state.place_piece(0, PieceKind::Pawn, Square::new(0, 1)).unwrap();
+        
+        // 턴 종료 후 계승
+        state.end_turn().unwrap();
+        state.end_turn().unwrap();
+        state.action_taken = false;
+        
+        assert!(state.crown_piece(0, &pawn_id).is_ok());
+        assert!(state.pieces.get(&pawn_id).unwrap().is_royal);
+    }
+    
+    #[test]
+    fn test_pawn_cannot_place_on_promotion_rank() {
+        let mut state = GameState::new(0);
+        state.pockets.insert(0, vec![PieceSpec { kind: PieceKind::Pawn }]);
+        
+        // 8랭크(y=7)에 폰 착수 시도 - 실패해야 함
+        let result = state.place_piece(0, PieceKind::Pawn, Square::new(0, 7));
+        assert!(result.is_err());
+    }
+    
+    #[test]
+    fn test_promotion_zone_last_three_ranks() {
+        let mut state = GameState::new(0);
+        state.set_promotion_zone(0, vec![5, 6, 7]);
+        
+        // 구역 안(6랭크, y=5)에는 착수 불가
+        state.pockets.insert(0, vec![PieceSpec::new(PieceKind::Pawn), PieceSpec::new(PieceKind::Pawn)]);
+        assert!(state.place_piece(0, PieceKind::Pawn, Square::new(0, 5)).is_err());
+        
+        // 구역 밖(5랭크, y=4)에 착수 후 한 칸 전진하면 프로모션 가능
+        let pawn_id = state.place_piece(0, PieceKind::Pawn, Square::new(0, 4)).unwrap();
+        assert!(state.promote(&pawn_id, PieceKind::Queen).is_err());
+        
+        let pawn = state.pieces.get_mut(&pawn_id).unwrap();
+        pawn.pos = Some(Square::new(0, 5));
+        state.board.remove(&Square::new(0, 4));
+        state.board.insert(Square::new(0, 5), pawn_id.clone());
+        
+        assert!(state.promote(&pawn_id, PieceKind::Queen).is_ok());
+        assert_eq!(state.pieces[&pawn_id].kind, PieceKind::Queen);
+        
+        // 기본 구역(흑)은 그대로 1랭크
+        assert!(state.is_promotion_square(&PieceKind::Pawn, Square::new(3, 0), 1));
+        assert!(!state.is_promotion_square(&PieceKind::Pawn, Square::new(3, 1), 1));
+    }
+    
+    #[test]
+    fn test_king_legal_moves() {
+        let state = GameState::new(0);
+        
+        // 백 킹 (e1)의 이동 가능 칸 확인
+        let white_king_id = state.board.get(&Square::new(4, 0)).unwrap().clone();
+        let moves = state.get_legal_moves(&white_king_id);
+        
+        // e1에서 킹이 갈 수 있는 칸: d1, f1, d2, e2, f2 (5칸)
+        assert!(!moves.is_empty());
+        
+        // d2로 이동 가능한지 확인
+        assert!(moves.iter().any(|m| m.to == Square::new(3, 1)));
+        // e2로 이동 가능한지 확인
+        assert!(moves.iter().any(|m| m.to == Square::new(4, 1)));
+    }
+    
+    #[test]
+    fn test_rook_legal_moves() {
+        let mut state = GameState::new(0);
+        
+        // 룩 배치 (d4)
+        let rook = state.create_piece(PieceKind::Rook, 0);
+        let rook_id = rook.id.clone();
+        state.pieces.insert(rook_id.clone(), rook);
+        if let Some(p) = state.pieces.get_mut(&rook_id) {
+            p.pos = Some(Square::new(3, 3)); // d4
+            p.move_stack = 3;
+            p.stun = 0;
+        }
+        state.board.insert(Square::new(3, 3), rook_id.clone());
+        
+        // chessembly 직접 테스트
+        let script = "take-move(1, 0) repeat(1); take-move(-1, 0) repeat(1); take-move(0, 1) repeat(1); take-move(0, -1) repeat(1);";
+        
+        let mut board = state.to_chessembly_board(&rook_id, false).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_debug(state.debug_mode);
+        interpreter.parse(script);
+        let activations = interpreter.execute(&mut board).unwrap();
+        
+        println!("Script: {}", script);
+        println!("Piece at: ({}, {})", board.piece_x, board.piece_y);
+        println!("Activations count: {}", activations.len());
+        for a in &activations {
+            let target_x = board.piece_x + a.dx;
+            let target_y = board.piece_y + a.dy;
+            println!("  dx={}, dy={} -> ({}, {})", a.dx, a.dy, target_x, target_y);
+        }
+        
+        // 오른쪽으로 이동 가능
+        assert!(activations.iter().any(|a| a.dx == 1 && a.dy == 0), "오른쪽 이동 필요");
+        // 왼쪽으로 이동 가능
+        assert!(activations.iter().any(|a| a.dx == -1 && a.dy == 0), "왼쪽 이동 필요");
+        // 위로 이동 가능
+        assert!(activations.iter().any(|a| a.dx == 0 && a.dy == 1), "위 이동 필요");
+        // 아래로 이동 가능
+        assert!(activations.iter().any(|a| a.dx == 0 && a.dy == -1), "아래 이동 필요");
+    }
+    
+    #[test]
+    fn test_knight_legal_moves() {
+        let mut state = GameState::new(0);
+        
+        // 나이트 배치 (d4)
+        let knight = state.create_piece(PieceKind::Knight, 0);
+        let knight_id = knight.id.clone();
+        state.pieces.insert(knight_id.clone(), knight);
+        if let Some(p) = state.pieces.get_mut(&knight_id) {
+            p.pos = Some(Square::new(3, 3)); // d4
+            p.move_stack = 3;
+            p.stun = 0;
+        }
+        state.board.insert(Square::new(3, 3), knight_id.clone());
+        
+        let moves = state.get_legal_moves(&knight_id);
+        
+        // 나이트 L자 이동: b3, b5, c2, c6, e2, e6, f3, f5 (8칸)
+        assert_eq!(moves.len(), 8);
+        
+        // b5 (1,4)로 이동 가능
+        assert!(moves.iter().any(|m| m.to == Square::new(1, 4)));
+        // f5 (5,4)로 이동 가능
+        assert!(moves.iter().any(|m| m.to == Square::new(5, 4)));
+    }
+    
+    #[test]
+    fn test_continuation_moves_after_rook_move() {
+        let mut state = GameState::new(0);
+        
+        // 이동 전에는 연속 이동 없음
+        assert!(state.continuation_moves().is_empty());
+        
+        // 룩 배치 (a1, 이동 3)
+        let rook = state.create_piece(PieceKind::Rook, 0);
+        let rook_id = rook.id.clone();
+        state.pieces.insert(rook_id.clone(), rook);
+        if let Some(p) = state.pieces.get_mut(&rook_id) {
+            p.pos = Some(Square::new(0, 0));
+            p.move_stack = 3;
+        }
+        state.board.insert(Square::new(0, 0), rook_id.clone());
+        
+        // a1 -> a4 이동
+        let mv = state.get_legal_moves(&rook_id).into_iter()
+            .find(|m| m.to == Square::new(0, 3))
+            .unwrap();
+        state.move_piece_by_legal_moves(mv).unwrap();
+        
+        // 연속 이동은 a4에서 출발하는 룩의 이동과 같아야 함
+        let continuation = state.continuation_moves();
+        assert!(!continuation.is_empty());
+        assert!(continuation.iter().all(|m| m.from == Square::new(0, 3)));
+        assert_eq!(continuation.len(), state.get_legal_moves_at(Square::new(0, 3)).len());
+    }
+    
+    #[test]
+    fn test_piece_static_offsets() {
+        let knight = PieceKind::Knight.static_offsets(true).unwrap();
+        assert_eq!(knight.len(), 8);
+        assert!(knight.iter().all(|&(dx, dy, _)| dx.abs() + dy.abs() == 3));
+        
+        assert!(PieceKind::Cannon.static_offsets(true).is_none());
+    }
+    
+    #[test]
+    fn test_tempest_rook_empty_board() {
+        let mut state = GameState::new(0);
+        state.board.clear();
+        state.pieces.clear();
+        let id = put_piece(&mut state, PieceKind::TempestRook, 0, Square::new(3, 3)); // d4
+        
+        let moves = state.get_legal_moves(&id);
+        let mut expected = HashSet::new();
+        // 대각 1칸 후 그 칸에서 두 방향 직선 슬라이드
+        for (sx, sy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+            let step = Square::new(3 + sx, 3 + sy);
+            expected.insert(step);
+            for (dx, dy) in [(sx, 0), (0, sy)] {
+                let mut sq = Square::new(step.x + dx, step.y + dy);
+                while sq.is_valid() {
+                    expected.insert(sq);
+                    sq = Square::new(sq.x + dx, sq.y + dy);
+                }
+            }
+        }
+        
+        assert_eq!(moves.len(), 24);
+        assert_eq!(targets(&moves), expected);
+    }
+    
+    #[test]
+    fn test_tempest_rook_crowded_board() {
+        let mut state = GameState::new(0);
+        let id = put_piece(&mut state, PieceKind::TempestRook, 0, Square::new(3, 3)); // d4
+        put_piece(&mut state, PieceKind::Pawn, 0, Square::new(4, 4)); // e5 아군: 북동 대각 차단
+        put_piece(&mut state, PieceKind::Pawn, 1, Square::new(2, 4)); // c5 적: 북서 대각에서 잡고 종료
+        put_piece(&mut state, PieceKind::Pawn, 1, Square::new(6, 2)); // g3 적: 남동 슬라이드 중 잡기
+        put_piece(&mut state, PieceKind::Pawn, 0, Square::new(2, 1)); // c2 아군: 남서 하강 슬라이드 차단
+        
+        let moves = state.get_legal_moves(&id);
+        let expected: HashSet<Square> = [
+            // 북서: c5 잡기만
+            (2, 4),
+            // 남동: e3, 오른쪽 f3, g3(잡기), 아래 e2 (e1은 백 킹)
+            (4, 2), (5, 2), (6, 2), (4, 1),
+            // 남서: c3, 왼쪽 b3, a3 (아래는 c2 아군에 막힘)
+            (2, 2), (1, 2), (0, 2),
+        ].iter().map(|&(x, y)| Square::new(x, y)).collect();
+        
+        assert_eq!(targets(&moves), expected);
+        assert_eq!(moves.len(), expected.len());
+    }
+    
+    /// 백 킹 a1 (스턴으로 이동 불가), 흑 룩 a8의 파일 체크
+    fn rook_check_position() -> GameState {
+        let mut state = GameState::new(0);
+        state.board.clear();
+        state.pieces.clear();
+        let king = put_piece(&mut state, PieceKind::King, 0, Square::new(0, 0));
+        let king = state.pieces.get_mut(&king).unwrap();
+        king.is_royal = true;
+        king.stun = 3;
+        let black_king = put_piece(&mut state, PieceKind::King, 1, Square::new(7, 7));
+        state.pieces.get_mut(&black_king).unwrap().is_royal = true;
+        put_piece(&mut state, PieceKind::Rook, 1, Square::new(0, 7));
+        state
+    }
+    
+    #[test]
+    fn test_check_blocking_squares() {
+        let state = rook_check_position();
+        assert!(state.is_check(0));
+        assert!(!state.is_check(1));
+        
+        let blocking: HashSet<Square> = state.check_blocking_squares(0).into_iter().collect();
+        let expected: HashSet<Square> = (1..7).map(|y| Square::new(0, y)).collect();
+        assert_eq!(blocking, expected);
+        
+        // 나이트 체크는 가로막을 수 없음
+        let mut state = rook_check_position();
+        let rook_id = state.board.remove(&Square::new(0, 7)).unwrap();
+        state.pieces.remove(&rook_id);
+        put_piece(&mut state, PieceKind::Knight, 1, Square::new(1, 2));
+        assert!(state.is_check(0));
+        assert!(state.check_blocking_squares(0).is_empty());
+    }
+    
+    #[test]
+    fn test_blocking_drop_prevents_checkmate() {
+        // 포켓이 비어 있으면 체크메이트
+        let state = rook_check_position();
+        assert!(state.is_checkmate(0));
+        
+        // 차단할 기물이 포켓에 있으면 체크메이트가 아님
+        let mut state = rook_check_position();
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Knight)]);
+        assert!(!state.is_checkmate(0));
+        
+        let mut blocked = state.clone();
+        blocked.place_piece(0, PieceKind::Knight, Square::new(0, 3)).unwrap();
+        assert!(!blocked.is_check(0));
+    }
+    
+    #[test]
+    fn test_drop_in_check_policies() {
+        let mut state = rook_check_position();
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Knight)]);
+        let blocking = Square::new(0, 3);
+        let elsewhere = Square::new(4, 3);
+        
+        // 기본값: 차단 칸에만 착수 가능
+        assert_eq!(state.drop_in_check, DropInCheckPolicy::BlockingOnly);
+        assert!(state.can_place(0, &PieceKind::Knight, blocking).is_ok());
+        assert!(state.can_place(0, &PieceKind::Knight, elsewhere).is_err());
+        
+        state.drop_in_check = DropInCheckPolicy::Forbidden;
+        assert!(state.can_place(0, &PieceKind::Knight, blocking).is_err());
+        assert!(state.can_place(0, &PieceKind::Knight, elsewhere).is_err());
+        // 착수가 금지되면 차단 착수로 벗어날 수 없음
+        assert!(state.is_checkmate(0));
+        
+        state.drop_in_check = DropInCheckPolicy::Allowed;
+        assert!(state.can_place(0, &PieceKind::Knight, blocking).is_ok());
+        assert!(state.can_place(0, &PieceKind::Knight, elsewhere).is_ok());
+    }
+    
+    #[test]
+    fn test_royal_threat_via_two_step_path() {
+        let mut state = GameState::new(1);
+        // 흑 룩 a8: e1 킹과 정렬되지 않아 체크는 아님 (a8 -> a1 -> e1 두 번 이동으로 도달)
+        let rook_id = put_piece(&mut state, PieceKind::Rook, 1, Square::new(0, 7));
+        state.pieces.get_mut(&rook_id).unwrap().move_stack = 2;
+        assert!(!state.is_check(0));
+        
+        let threats = state.royal_threats(0);
+        assert_eq!(threats, vec![(rook_id.clone(), Square::new(4, 0))]);
+        assert!(state.reachable_within(&rook_id, 2).contains(&Square::new(4, 0)));
+        
+        // 이동 스택 1이면 위협 아님
+        state.pieces.get_mut(&rook_id).unwrap().move_stack = 1;
+        assert!(state.royal_threats(0).is_empty());
+    }
+    
+    #[test]
+    fn test_action_encode_roundtrip() {
+        let state = GameState::new(0);
+        let king_id = state.board[&Square::new(4, 0)].clone();
+        let actions = vec![
+            Action::Place { kind: PieceKind::Pawn, target: Square::new(0, 1) },
+            Action::Place { kind: PieceKind::Experiment, target: Square::new(7, 7) },
+            Action::Move { piece_id: king_id.clone(), from: Square::new(4, 0), to: Square::new(4, 1) },
+            Action::Disguise { piece_id: king_id.clone(), as_kind: PieceKind::Cannon },
+            Action::Crown { piece_id: king_id.clone() },
+            Action::Stun { piece_id: king_id.clone(), amount: 3 },
+        ];
+        
+        let castling = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(0, 0))
+            .build();
+        let castle = Action::Castle {
+            king_id: castling.board[&Square::new(4, 0)].clone(),
+            rook_id: castling.board[&Square::new(0, 0)].clone(),
+            side: CastleSide::QueenSide,
+        };
+        assert_eq!(Action::decode(castle.encode(), &castling), Some(castle));
+        
+        for action in actions {
+            let code = action.encode();
+            assert!(code < 1 << 23);
+            assert_eq!(Action::decode(code, &state), Some(action));
+        }
+        
+        // Custom 기물과 빈 칸 이동은 복원 불가
+        let custom = Action::Place { kind: PieceKind::Custom("x".into()), target: Square::new(0, 0) };
+        assert_eq!(Action::decode(custom.encode(), &state), None);
+        let empty = Action::Move { piece_id: king_id, from: Square::new(3, 3), to: Square::new(3, 4) };
+        assert_eq!(Action::decode(empty.encode(), &state), None);
+    }
+    
+    #[test]
+    fn test_action_encode_large_board() {
+        let mut state = GameState::with_size(10, 10, 0);
+        let squares: Vec<Square> = (0..10).flat_map(|y| (0..10).map(move |x| Square::new(x, y))).collect();
+        
+        // 10x10의 모든 칸이 서로 다른 코드로 압축되고 그대로 복원됨
+        let codes: HashSet<u32> = squares.iter().map(|sq| {
+            let place = Action::Place { kind: PieceKind::Pawn, target: *sq };
+            let code = place.encode();
+            assert_eq!(Action::decode(code, &state), Some(place));
+            code
+        }).collect();
+        assert_eq!(codes.len(), squares.len());
+        
+        let rook_id = put_piece(&mut state, PieceKind::Rook, 0, Square::new(9, 8));
+        let mv = Action::Move { piece_id: rook_id, from: Square::new(9, 8), to: Square::new(1, 8) };
+        assert_eq!(Action::decode(mv.encode(), &state), Some(mv));
+    }
+    
+    #[test]
+    fn test_attack_cache_matches_recompute() {
+        let mut state = GameState::new(0);
+        let assert_cache_fresh = |state: &GameState| {
+            for player in 0..2 {
+                assert_eq!(*state.attacked_squares(player), state.compute_attacked_squares(player));
+            }
+        };
+        
+        let rook_id = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 3));
+        put_piece(&mut state, PieceKind::Knight, 1, Square::new(5, 5));
+        assert_cache_fresh(&state);
+        
+        state.move_piece(0, &rook_id, Square::new(0, 3), Square::new(5, 3), MoveType::TakeMove).unwrap();
+        assert_cache_fresh(&state);
+        state.move_piece(0, &rook_id, Square::new(5, 3), Square::new(5, 5), MoveType::TakeMove).unwrap();
+        assert_cache_fresh(&state);
+        state.end_turn().unwrap();
+        assert_cache_fresh(&state);
+        
+        let king_id = state.board[&Square::new(4, 7)].clone();
+        state.move_piece(1, &king_id, Square::new(4, 7), Square::new(3, 6), MoveType::TakeMove).unwrap();
+        assert_cache_fresh(&state);
+        assert!(state.attacked_squares(0).contains(&Square::new(5, 7)));
+    }
+    
+    #[test]
+    fn test_move_counts_starting_position() {
+        let mut state = GameState::new(0);
+        let white_king = state.board[&Square::new(4, 0)].clone();
+        let black_king = state.board[&Square::new(4, 7)].clone();
+        
+        assert_eq!(state.move_counts(0), HashMap::from([(white_king.clone(), 5)]));
+        assert_eq!(state.move_counts(1), HashMap::from([(black_king, 5)]));
+        
+        // 스턴 상태면 0
+        state.pieces.get_mut(&white_king).unwrap().stun = 1;
+        assert_eq!(state.move_counts(0), HashMap::from([(white_king, 0)]));
+    }
+    
+    #[test]
+    fn test_drop_check_and_self_check() {
+        // 착수로 상대에게 체크
+        let mut state = GameState::new(0);
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Rook)]);
+        assert!(!state.is_check(1));
+        state.place_piece(0, PieceKind::Rook, Square::new(4, 4)).unwrap();
+        assert!(state.is_check(1));
+        
+        // 메뚜기의 발판이 되는 착수는 자신을 체크에 빠뜨리므로 불가
+        let mut state = GameState::new(0);
+        put_piece(&mut state, PieceKind::Grasshopper, 1, Square::new(0, 4));
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Knight)]);
+        assert!(!state.is_check(0));
+        assert!(state.can_place(0, &PieceKind::Knight, Square::new(3, 1)).is_err());
+        assert!(state.can_place(0, &PieceKind::Knight, Square::new(2, 2)).is_ok());
+        
+        state.drop_in_check = DropInCheckPolicy::Allowed;
+        assert!(state.can_place(0, &PieceKind::Knight, Square::new(3, 1)).is_ok());
+    }
+    
+    #[test]
+    fn test_piece_count_metrics() {
+        let mut state = GameState::new(0);
+        assert_eq!(state.piece_count(0), 1);
+        assert_eq!(state.total_piece_count(), 2);
+        
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Rook)]);
+        state.setup_pocket_unchecked(1, vec![PieceSpec::new(PieceKind::Knight)]);
+        let rook_id = state.place_piece(0, PieceKind::Rook, Square::new(0, 3)).unwrap();
+        state.end_turn().unwrap();
+        let knight_id = state.place_piece(1, PieceKind::Knight, Square::new(0, 6)).unwrap();
+        assert_eq!(state.piece_count(0), 2);
+        assert_eq!(state.piece_count(1), 2);
+        assert_eq!(state.total_piece_count(), 4);
+        assert_eq!(state.board_fill_ratio(), 4.0 / 64.0);
+        
+        state.capture(&rook_id, &knight_id).unwrap();
+        assert_eq!(state.piece_count(1), 1);
+        assert_eq!(state.total_piece_count(), 3);
+    }
+    
+    #[test]
+    fn test_count_pawns_after_placement() {
+        let mut state = GameState::new(0);
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Pawn); 3]);
+        
+        let mut placed = Vec::new();
+        for x in 0..3 {
+            state.action_taken = false;
+            placed.push(state.place_piece(0, PieceKind::Pawn, Square::new(x, 1)).unwrap());
+        }
+        placed.sort();
+        
+        assert_eq!(state.count_kind(0, &PieceKind::Pawn), 3);
+        assert_eq!(state.pieces_of_kind(0, &PieceKind::Pawn), placed);
+        assert_eq!(state.count_kind(1, &PieceKind::Pawn), 0);
+        assert_eq!(state.count_kind(0, &PieceKind::King), 1);
+    }
+    
+    #[test]
+    fn test_legal_moves_hypothetical_knight() {
+        let state = GameState::new(0);
+        let moves = state.legal_moves_hypothetical(PieceKind::Knight, Square::new(3, 3), 0);
+        assert_eq!(moves.len(), 8);
+        assert!(targets(&moves).contains(&Square::new(4, 5)));
+        
+        // 상태는 그대로
+        assert_eq!(state.total_piece_count(), 2);
+        assert!(state.get_piece_at(Square::new(3, 3)).is_none());
+        
+        // 이미 기물이 있는 칸은 미리보기 불가
+        assert!(state.legal_moves_hypothetical(PieceKind::Knight, Square::new(4, 0), 0).is_empty());
+    }
+    
+    #[test]
+    fn test_captured_value_tally() {
+        let mut state = GameState::new(0);
+        let rook_id = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        let knight_id = put_piece(&mut state, PieceKind::Knight, 1, Square::new(0, 5));
+        let pawn_id = put_piece(&mut state, PieceKind::Pawn, 1, Square::new(3, 5));
+        let bishop_id = put_piece(&mut state, PieceKind::Bishop, 1, Square::new(5, 5));
+        assert_eq!(state.captured_value(0), 0);
+        
+        state.capture(&rook_id, &knight_id).unwrap();
+        state.capture(&rook_id, &pawn_id).unwrap();
+        assert_eq!(state.captured_value(0), 4);
+        assert_eq!(state.captured_value(1), 0);
+        
+        state.capture(&bishop_id, &rook_id).unwrap();
+        assert_eq!(state.captured_value(1), 5);
+    }
+    
+    #[test]
+    fn test_stack_refill_proportional_to_stun() {
+        let mut state = GameState::new(0);
+        let pawn_id = put_piece(&mut state, PieceKind::Pawn, 1, Square::new(0, 6));
+        state.pieces.get_mut(&pawn_id).unwrap().stun = 1;
+        
+        // 기본: 스턴과 무관하게 가득 채움
+        let mut full = state.clone();
+        full.end_turn().unwrap();
+        assert_eq!(full.pieces[&pawn_id].move_stack, 5);
+        
+        // 비례: 5 / (1 + 1) 올림 = 3
+        state.stack_refill = StackRefillRule::ProportionalToStun;
+        state.end_turn().unwrap();
+        assert_eq!(state.pieces[&pawn_id].move_stack, 3);
+        
+        // 스턴이 풀린 뒤에는 다시 가득 채움
+        state.end_turn().unwrap();
+        state.end_turn().unwrap();
+        assert_eq!(state.pieces[&pawn_id].stun, 0);
+        assert_eq!(state.pieces[&pawn_id].move_stack, 5);
+    }
+    
+    #[test]
+    fn test_stack_refill_top_up_keeps_captured_stack() {
+        let mut state = GameState::new(1);
+        let rook_id = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 3));
+        let knight_id = put_piece(&mut state, PieceKind::Knight, 0, Square::new(5, 3));
+        // 잡기로 초기값(3)보다 많이 쌓인 룩, 막 착수해 초기값보다 적은 나이트
+        state.pieces.get_mut(&rook_id).unwrap().move_stack = 7;
+        state.pieces.get_mut(&knight_id).unwrap().move_stack = 1;
+        
+        // 기본: stack.md대로 턴 시작마다 새로 부여
+        let mut full = state.clone();
+        full.end_turn().unwrap();
+        assert_eq!(full.pieces[&rook_id].move_stack, 3);
+        assert_eq!(full.pieces[&knight_id].move_stack, 3);
+        
+        // TopUp: 모자란 스택만 채우고 쌓인 스택은 유지
+        state.stack_refill = StackRefillRule::TopUp;
+        state.end_turn().unwrap();
+        assert_eq!(state.pieces[&rook_id].move_stack, 7);
+        assert_eq!(state.pieces[&knight_id].move_stack, 3);
+    }
+    
+    #[test]
+    fn test_position_string_roundtrip_and_version() {
+        let mut state = GameState::new_default();
+        state.setup_initial_position();
+        state.end_turn().unwrap();
+        let text = state.to_position_string();
+        assert!(text.starts_with(&format!("{};8x8;b;", FORMAT_VERSION)));
+        
+        let restored = GameState::from_position_string(&text).unwrap();
+        assert!(restored.same_position(&state));
+        assert_eq!(restored.turn, 1);
+        assert_eq!(restored.to_position_string(), text);
+        
+        // 버전이 올라간 문자열은 거부
+        let bumped = text.replacen(&FORMAT_VERSION.to_string(), &(FORMAT_VERSION + 1).to_string(), 1);
+        let err = GameState::from_position_string(&bumped).unwrap_err();
+        assert!(matches!(&err, GameError::Parse(message) if message.contains("지원하지 않는 형식 버전")));
+    }
+    
+    #[test]
+    fn test_position_formats_on_large_board() {
+        let mut state = GameState::with_size(10, 12, 0);
+        put_piece(&mut state, PieceKind::Rook, 1, Square::new(9, 10));
+        put_piece(&mut state, PieceKind::Knight, 0, Square::new(0, 11));
+        let king = state.board[&Square::new(5, 11)].clone();
+        assert_eq!(state.pieces[&king].owner, 1);
+        
+        let text = state.to_position_string();
+        assert!(text.starts_with(&format!("{};10x12;w;", FORMAT_VERSION)));
+        let restored = GameState::from_position_string(&text).unwrap();
+        assert_eq!((restored.board_width, restored.board_height), (10, 12));
+        assert!(restored.same_position(&state));
+        assert_eq!(restored.to_position_string(), text);
+        
+        // 킹이 있는 f12 랭크와 빈 칸이 10칸인 랭크도 그대로 복원
+        let notation = state.to_notation();
+        assert!(notation.starts_with("N{0,"));
+        assert!(notation.contains("/10/"));
+        let restored = GameState::from_notation(&notation).unwrap();
+        assert_eq!((restored.board_width, restored.board_height), (10, 12));
+        assert!(restored.same_position(&state));
+        assert_eq!(restored.to_notation(), notation);
+        
+        assert!(GameState::from_position_string(&text.replace(";10x12;", ";8x8;")).is_err());
+        assert!(GameState::from_position_string(&text.replace(";10x12;", ";17x17;")).is_err());
+    }
+    
+    #[test]
+    fn test_position_string_keeps_moved_and_en_passant() {
+        // 킹이 움직인 뒤에는 캐슬링 권리(이동 여부)까지 같아야 같은 국면
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(7, 0))
+            .piece(PieceKind::King, 1, Square::new(4, 7)).royal()
+            .build();
+        let king = state.board[&Square::new(4, 0)].clone();
+        state.move_piece(0, &king, Square::new(4, 0), Square::new(4, 1), MoveType::TakeMove).unwrap();
+        state.end_turn().unwrap();
+        let text = state.to_position_string();
+        assert!(text.contains("e2:w:1:") && text.ends_with(";-"));
+        let restored = GameState::from_position_string(&text).unwrap();
+        assert!(restored.same_position(&state));
+        assert!(restored.pieces[&restored.board[&Square::new(4, 1)]].has_moved);
+        assert!(!restored.pieces[&restored.board[&Square::new(7, 0)]].has_moved);
+        assert_eq!(restored.zobrist_key(), state.zobrist_key());
+        
+        // 폰 두 칸 전진 직후의 앙파상 칸도 복원
+        let (state, _, _) = double_stepped_pawns();
+        let text = state.to_position_string();
+        assert!(text.ends_with(";e3"));
+        let restored = GameState::from_position_string(&text).unwrap();
+        assert_eq!(restored.en_passant_target, state.en_passant_target);
+        assert!(restored.same_position(&state));
+        assert_eq!(restored.zobrist_key(), state.zobrist_key());
+        
+        assert!(GameState::from_position_string(&text.replace(";e3", ";z9")).is_err());
+    }
+    
+    #[test]
+    fn test_same_position() {
+        let mut state = GameState::new(0);
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Rook), PieceSpec::new(PieceKind::Pawn)]);
+        let rook_id = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        
+        let clone = state.clone();
+        assert!(state.same_position(&clone));
+        
+        // ID가 달라도 같은 국면
+        let mut rebuilt = GameState::new(0);
+        rebuilt.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Pawn), PieceSpec::new(PieceKind::Rook)]);
+        rebuilt.next_piece_id = 100;
+        put_piece(&mut rebuilt, PieceKind::Rook, 0, Square::new(0, 0));
+        assert!(state.same_position(&rebuilt));
+        
+        state.move_piece(0, &rook_id, Square::new(0, 0), Square::new(0, 3), MoveType::TakeMove).unwrap();
+        assert!(!state.same_position(&clone));
+    }
+    
+    #[test]
+    fn test_pawn_attacks_both_diagonals() {
+        let mut state = GameState::new(0);
+        let pawn_id = put_piece(&mut state, PieceKind::Pawn, 0, Square::new(3, 3));
+        put_piece(&mut state, PieceKind::Knight, 0, Square::new(4, 4));
+        
+        // 빈 칸(c5)과 아군 칸(e5) 모두 위협, 전진 칸(d5)은 제외
+        assert_eq!(state.attacks_of(&pawn_id), vec![Square::new(2, 4), Square::new(4, 4)]);
+        assert!(state.get_legal_moves(&pawn_id).iter().all(|m| m.move_type == MoveType::Move));
+    }
+    
+    #[test]
+    fn test_disguised_royal_refills_as_disguise() {
+        let mut state = GameState::new(0);
+        let king_id = state.board[&Square::new(4, 0)].clone();
+        state.pieces.get_mut(&king_id).unwrap().is_royal = true;
+        
+        state.disguise_piece(0, &king_id, PieceKind::Pawn).unwrap();
+        assert_eq!(state.pieces[&king_id].move_stack, 5);
+        
+        state.end_turn().unwrap();
+        state.end_turn().unwrap();
+        assert_eq!(state.pieces[&king_id].move_stack, 5);
+        
+        // 점수는 실제 종류 기준 유지
+        assert_eq!(state.pieces[&king_id].score(), 4);
+    }
+    
+    #[test]
+    fn test_promotion_via_legal_move() {
+        let mut state = GameState::new(0);
+        let pawn_id = put_piece(&mut state, PieceKind::Pawn, 0, Square::new(6, 6));
+        assert_eq!(state.pending_promotion, None);
+        
+        let mv = state.get_legal_moves(&pawn_id).into_iter()
+            .find(|m| m.to == Square::new(6, 7))
+            .unwrap();
+        state.move_piece_by_legal_moves(mv).unwrap();
+        assert_eq!(state.pending_promotion(), Some(pawn_id.clone()));
+        
+        // 프로모션 전에는 턴을 끝낼 수 없음
+        assert!(state.end_turn().is_err());
+        assert_eq!(state.turn, 0);
+        
+        let (stun, move_stack) = (state.pieces[&pawn_id].stun, state.pieces[&pawn_id].move_stack);
+        state.promote(&pawn_id, PieceKind::Queen).unwrap();
+        let piece = &state.pieces[&pawn_id];
+        assert_eq!(piece.kind, PieceKind::Queen);
+        assert_eq!((piece.stun, piece.move_stack), (stun, move_stack));
+        assert_eq!(state.pending_promotion, None);
+        state.end_turn().unwrap();
+    }
+    
+    #[test]
+    fn test_clone_bare_keeps_position() {
+        let state = rook_check_position();
+        assert!(state.is_check(0));
+        assert!(state.attacked_by.get().is_some());
+        
+        let bare = state.clone_bare();
+        assert!(bare.attacked_by.get().is_none());
+        assert!(bare.same_position(&state));
+        assert!(bare.is_check(0));
+    }
+    
+    #[test]
+    fn test_move_and_action_policy() {
+        let setup = |policy: TurnActionPolicy| {
+            let mut state = GameState::new(0);
+            state.action_policy = policy;
+            let rook_id = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+            let knight_id = put_piece(&mut state, PieceKind::Knight, 1, Square::new(5, 5));
+            (state, rook_id, knight_id)
+        };
+        
+        // 기본: 이동 후 스턴 불가, 스턴 후 이동 불가
+        let (mut state, rook_id, knight_id) = setup(TurnActionPolicy::Exclusive);
+        state.move_piece(0, &rook_id, Square::new(0, 0), Square::new(0, 3), MoveType::TakeMove).unwrap();
+        assert!(state.apply_stun(0, &knight_id, 1).is_err());
+        let (mut state, rook_id, knight_id) = setup(TurnActionPolicy::Exclusive);
+        state.apply_stun(0, &knight_id, 1).unwrap();
+        assert!(state.move_piece(0, &rook_id, Square::new(0, 0), Square::new(0, 3), MoveType::TakeMove).is_err());
+        // `stun_piece`도 같은 정책을 따름: 착수나 이동 뒤에는 스턴 불가
+        let (mut state, _, knight_id) = setup(TurnActionPolicy::Exclusive);
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Pawn)]);
+        state.place_piece(0, PieceKind::Pawn, Square::new(2, 2)).unwrap();
+        assert_eq!(state.stun_piece(&knight_id, 1), Err(GameError::AlreadyActed));
+        let (mut state, rook_id, knight_id) = setup(TurnActionPolicy::Exclusive);
+        state.move_piece(0, &rook_id, Square::new(0, 0), Square::new(0, 3), MoveType::TakeMove).unwrap();
+        assert_eq!(state.stun_piece(&knight_id, 1), Err(GameError::AlreadyActed));
+        assert_eq!(state.pieces[&knight_id].stun, 0);
+        
+        // 병행 허용: 이동 -> 스턴
+        let (mut state, rook_id, knight_id) = setup(TurnActionPolicy::MoveAndAction);
+        state.move_piece(0, &rook_id, Square::new(0, 0), Square::new(0, 3), MoveType::TakeMove).unwrap();
+        state.apply_stun(0, &knight_id, 1).unwrap();
+        state.move_piece(0, &rook_id, Square::new(0, 3), Square::new(1, 3), MoveType::TakeMove).unwrap();
+        // 행동은 한 번만
+        let king_id = state.board[&Square::new(4, 0)].clone();
+        assert!(state.crown_piece(0, &king_id).is_err());
+        
+        // 병행 허용: 스턴 -> 이동
+        let (mut state, rook_id, knight_id) = setup(TurnActionPolicy::MoveAndAction);
+        state.apply_stun(0, &knight_id, 1).unwrap();
+        state.move_piece(0, &rook_id, Square::new(0, 0), Square::new(0, 3), MoveType::TakeMove).unwrap();
+        assert_eq!(state.pieces[&knight_id].stun, 1);
+    }
+    
+    #[test]
+    fn test_royal_squares() {
+        let mut state = GameState::new(0);
+        assert_eq!(state.royal_squares(0), vec![Square::new(4, 0)]);
+        assert_eq!(state.royal_squares(1), vec![Square::new(4, 7)]);
+        
+        let rook_id = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        state.crown_piece(0, &rook_id).unwrap();
+        assert_eq!(state.royal_squares(0), vec![Square::new(0, 0), Square::new(4, 0)]);
+    }
+    
+    #[test]
+    fn test_catch_advance_moves_attacker() {
+        let mut state = GameState::new(0);
+        let rook_id = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        let knight_id = put_piece(&mut state, PieceKind::Knight, 1, Square::new(0, 2));
+        
+        // catch-advance(0, 2, 0, 1): 두 칸 앞의 적을 잡고 한 칸 전진
+        let mv = LegalMove {
+            from: Square::new(0, 0),
+            to: Square::new(0, 2),
+            move_type: MoveType::Catch,
+            is_capture: true,
+            tags: Vec::new(),
+            catch_to: None,
+            advance_to: Some(Square::new(0, 1)),
+        };
+        let captured = state.move_piece_by_legal_moves(mv).unwrap();
+        
+        assert_eq!(captured, Some(knight_id));
+        assert_eq!(state.pieces[&rook_id].pos, Some(Square::new(0, 1)));
+        assert_eq!(state.board.get(&Square::new(0, 1)), Some(&rook_id));
+        assert!(state.get_piece_at(Square::new(0, 0)).is_none());
+        assert!(state.get_piece_at(Square::new(0, 2)).is_none());
+    }
+    
+    /// 계산 횟수는 디버그 빌드에서만 셈
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_is_check_shares_attack_computation() {
+        let state = rook_check_position();
+        assert_eq!(state.attack_computations.get(), 0);
+        
+        // 수동 스캔과 일치
+        let manual = state.pieces.values()
+            .filter(|p| p.owner == 1 && p.pos.is_some())
+            .flat_map(|p| state.capture_targets(&p.id))
+            .any(|sq| sq == Square::new(0, 0));
+        assert_eq!(state.is_check(0), manual);
+        
+        // is_check, attacked_squares, 스크립트용 위협 정보가 한 번의 계산을 공유
+        let king_id = state.board[&Square::new(0, 0)].clone();
+        let board = state.to_chessembly_board(&king_id, true).unwrap();
+        assert!(board.in_check);
+        assert!(state.attacked_squares(1).contains(&Square::new(0, 0)));
+        assert!(!state.is_check(1));
+        assert_eq!(state.attack_computations.get(), 1);
+    }
+    
+    #[test]
+    fn test_best_reply_captures_hanging_piece() {
+        // d4의 백 룩은 아무도 지키지 않음
+        let state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(7, 7)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(3, 3))
+            .piece(PieceKind::Rook, 1, Square::new(3, 6))
+            .turn(1)
+            .build();
+        let black_rook = state.board[&Square::new(3, 6)].clone();
+        
+        let reply = state.best_reply(1).unwrap();
+        assert_eq!(reply, Action::Move { piece_id: black_rook, from: Square::new(3, 6), to: Square::new(3, 3) });
+    }
+    
+    #[test]
+    fn test_quiescence_sees_recapture() {
+        // d6의 흑 폰은 d8의 흑 룩이 지킴
+        let state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(7, 7)).royal()
+            .piece(PieceKind::Queen, 0, Square::new(3, 0))
+            .piece(PieceKind::Pawn, 1, Square::new(3, 5))
+            .piece(PieceKind::Rook, 1, Square::new(3, 7))
+            .build();
+        let queen = state.board[&Square::new(3, 0)].clone();
+        let grab = Action::Move { piece_id: queen.clone(), from: Square::new(3, 0), to: Square::new(3, 5) };
+        
+        // 폰을 잡은 직후만 보면 백이 이득이지만, 룩이 되잡으면 퀸을 잃음
+        let mv = state.get_legal_moves(&queen).into_iter().find(|m| m.to == Square::new(3, 5)).unwrap();
+        let after = state.after_move(mv).unwrap();
+        assert!(after.evaluate(0) > state.evaluate(0));
+        assert!(-after.quiescence(QUIESCENCE_MAX_DEPTH) < state.evaluate(0));
+        assert_ne!(state.best_reply(1), Some(grab));
+    }
+    
+    #[test]
+    fn test_positional_score_prefers_center() {
+        let mut state = GameState::new(0);
+        state.board.clear();
+        state.pieces.clear();
+        let knight = put_piece(&mut state, PieceKind::Knight, 0, Square::new(0, 0));
+        let cornered = state.positional_score(0);
+        
+        state.board.remove(&Square::new(0, 0));
+        state.board.insert(Square::new(3, 3), knight.clone());
+        state.pieces.get_mut(&knight).unwrap().pos = Some(Square::new(3, 3));
+        assert!(state.positional_score(0) > cornered);
+        assert_eq!(state.positional_score(1), -state.positional_score(0));
+        
+        // 종류별 테이블 지정: 자기 진영 기준 랭크로 조회
+        let mut table = [0; 64];
+        table[3 * 8 + 3] = 40;
+        state.piece_square_tables.insert(PieceKind::Knight, table);
+        assert_eq!(state.positional_score(0), 40);
+    }
+    
+    #[test]
+    fn test_legal_moves_grouped_pawn() {
+        let mut state = GameState::new(0);
+        let pawn = put_piece(&mut state, PieceKind::Pawn, 0, Square::new(4, 1));
+        put_piece(&mut state, PieceKind::Knight, 1, Square::new(3, 2));
+        
+        let grouped = state.legal_moves_grouped(&pawn);
+        assert!(grouped[&MoveType::Take].contains(&Square::new(3, 2)));
+        assert!(grouped.values().flatten().any(|sq| *sq == Square::new(4, 2)));
+        let total: usize = grouped.values().map(Vec::len).sum();
+        assert_eq!(total, state.get_legal_moves(&pawn).len());
+        assert!(grouped.len() >= 2);
+    }
+    
+    #[test]
+    fn test_first_legal_action_on_start_is_drop() {
+        let mut state = GameState::new_default();
+        state.setup_initial_position();
+        
+        let action = state.first_legal_action().unwrap();
+        assert_eq!(state.first_legal_action(), Some(action.clone()));
+        let Action::Place { kind, target } = action else {
+            panic!("착수가 아닙니다: {:?}", action);
+        };
+        assert!(state.can_place(0, &kind, target).is_ok());
+        
+        // 포켓이 없으면 킹 이동
+        let bare = GameState::new(0);
+        assert!(matches!(bare.first_legal_action(), Some(Action::Move { .. })));
+    }
+    
+    #[test]
+    fn test_checked_royals_after_checking_move() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(7, 7)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(3, 3))
+            .build();
+        let rook = state.board[&Square::new(3, 3)].clone();
+        assert!(state.checked_royals(1).is_empty());
+        
+        let mv = state.get_legal_moves(&rook).into_iter().find(|m| m.to == Square::new(3, 7)).unwrap();
+        state.move_piece_by_legal_moves(mv).unwrap();
+        assert_eq!(state.checked_royals(1), vec![Square::new(7, 7)]);
+        assert!(state.checked_royals(0).is_empty());
+    }
+    
+    #[test]
+    fn test_promotion_stun_policy() {
+        let mut state = GameState::new(0);
+        state.promotion_stun_fn = Some(|kind| kind.score() / 3);
+        let pawn = put_piece(&mut state, PieceKind::Pawn, 0, Square::new(0, 7));
+        state.promote(&pawn, PieceKind::Queen).unwrap();
+        assert_eq!(state.pieces[&pawn].stun, 3);
+        
+        // 기본값: 스턴 변화 없음
+        let mut state = GameState::new(0);
+        let pawn = put_piece(&mut state, PieceKind::Pawn, 0, Square::new(0, 7));
+        state.promote(&pawn, PieceKind::Queen).unwrap();
+        assert_eq!(state.pieces[&pawn].stun, 0);
+        
+        // promote_with_stun: 폰의 최대 프로모션 스턴까지 채움
+        let mut state = GameState::new(0);
+        let pawn = put_piece(&mut state, PieceKind::Pawn, 0, Square::new(0, 7));
+        state.pieces.get_mut(&pawn).unwrap().stun = 3;
+        state.promote_with_stun(&pawn, PieceKind::Queen).unwrap();
+        assert_eq!(state.pieces[&pawn].stun, PieceKind::Pawn.max_promotion_stun());
+        assert!(state.promote_with_stun(&pawn, PieceKind::Rook).is_err());
+    }
+    
+    #[test]
+    fn test_shift_keeps_board_and_positions_in_sync() {
+        let mut state = GameState::new(0);
+        let knight = put_piece(&mut state, PieceKind::Knight, 0, Square::new(3, 3));
+        let rook = put_piece(&mut state, PieceKind::Rook, 0, Square::new(4, 5));
+        let mv = LegalMove {
+            from: Square::new(3, 3),
+            to: Square::new(4, 5),
+            move_type: MoveType::Shift,
+            is_capture: false,
+            tags: Vec::new(),
+            catch_to: None,
+            advance_to: None,
+        };
+        state.move_piece_by_legal_moves(mv).unwrap();
+        
+        for id in [&knight, &rook] {
+            let pos = state.pieces[id].pos.unwrap();
+            assert_eq!(&state.board[&pos], id);
+        }
+        assert_eq!(state.pieces[&knight].pos, Some(Square::new(4, 5)));
+        assert_eq!(state.pieces[&rook].pos, Some(Square::new(3, 3)));
+    }
+    
+    #[test]
+    fn test_builder_two_piece_position() {
+        let state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 1, Square::new(4, 7)).stun(2).move_stack(1)
+            .turn(1)
+            .pocket(0, vec![PieceKind::Pawn])
+            .build();
+        assert!(state.validate_invariants().is_ok());
+        assert_eq!(state.turn, 1);
+        assert_eq!(state.piece_count(0) + state.piece_count(1), 2);
+        
+        let rook = state.get_piece_at(Square::new(4, 7)).unwrap();
+        assert_eq!((rook.stun, rook.move_stack, rook.is_royal), (2, 1, false));
+        assert!(state.get_piece_at(Square::new(4, 0)).unwrap().is_royal);
+        assert_eq!(state.get_pocket(0), vec![PieceKind::Pawn]);
+        
+        // 보드와 기물 위치가 어긋나면 실패
+        let mut broken = state.clone();
+        broken.board.insert(Square::new(0, 0), rook.id.clone());
+        assert!(broken.validate_invariants().is_err());
+    }
+    
+    #[test]
+    fn test_danger_token_sees_enemy_attacks() {
+        let state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 1, Square::new(3, 7))
+            .build();
+        let king = state.board[&Square::new(4, 0)].clone();
+        
+        let mut board = state.to_chessembly_board(&king, true).unwrap();
+        assert!(board.danger_squares.contains(&(3, 0)));
+        assert!(!board.danger_squares.contains(&(5, 0)));
+        
+        // d1이 위험할 때만 f1로 이동하는 스크립트
+        let mut interpreter = Interpreter::new();
+        interpreter.parse("danger(-1, 0) take-move(1, 0);");
+        let activations = interpreter.execute(&mut board).unwrap();
+        assert!(activations.iter().any(|a| (a.dx, a.dy) == (1, 0)));
+        
+        // 위협 정보 없이 만든 보드에서는 발동하지 않음
+        let mut quiet = state.to_chessembly_board(&king, false).unwrap();
+        assert!(interpreter.execute(&mut quiet).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_move_gives_check() {
+        let state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(7, 7)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(3, 3))
+            .build();
+        let rook = state.board[&Square::new(3, 3)].clone();
+        let moves = state.get_legal_moves(&rook);
+        
+        let checking = moves.iter().find(|m| m.to == Square::new(3, 7)).unwrap();
+        assert!(state.move_gives_check(checking));
+        let quiet = moves.iter().find(|m| m.to == Square::new(3, 4)).unwrap();
+        assert!(!state.move_gives_check(quiet));
+        // 원래 국면은 그대로
+        assert!(!state.is_check(1));
+    }
+    
+    #[test]
+    fn test_check_token_with_multiple_and_disguised_royals() {
+        // 위장한 로얄(a1)은 안전하고, 계승한 두 번째 로얄(h1)만 공격받음
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::Knight, 0, Square::new(7, 0)).royal()
+            .piece(PieceKind::Rook, 1, Square::new(7, 7))
+            .piece(PieceKind::King, 1, Square::new(3, 7)).royal()
+            .build();
+        let king = state.board[&Square::new(0, 0)].clone();
+        state.pieces.get_mut(&king).unwrap().disguise = Some(PieceKind::Bishop);
+        
+        assert_eq!(state.checked_royals(0), vec![Square::new(7, 0)]);
+        let board = state.to_chessembly_board(&king, true).unwrap();
+        assert!(board.in_check);
+        let board = state.to_chessembly_board(&state.board[&Square::new(3, 7)], true).unwrap();
+        assert!(!board.in_check);
+        
+        // 공격받던 로얄이 비켜나면 체크 해제
+        let knight = state.board[&Square::new(7, 0)].clone();
+        let mv = state.get_legal_moves(&knight).into_iter().find(|m| m.to == Square::new(5, 1)).unwrap();
+        state.move_piece_by_legal_moves(mv).unwrap();
+        assert!(!state.to_chessembly_board(&king, true).unwrap().in_check);
+    }
+    
+    #[test]
+    fn test_max_turns_adjudicates_by_material() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(7, 7)).royal()
+            .piece(PieceKind::Rook, 1, Square::new(7, 6))
+            .build();
+        state.max_turns = Some(2);
+        
+        state.end_turn().unwrap();
+        assert_eq!(state.game_status(), GameResult::Ongoing);
+        state.end_turn().unwrap();
+        assert_eq!(state.turns_played, 2);
+        assert_eq!(state.game_status(), GameResult::BlackWins);
+        
+        // 점수가 같으면 무승부
+        state.pockets.insert(0, vec![PieceSpec::new(PieceKind::Rook)]);
+        assert_eq!(state.game_status(), GameResult::Draw);
+        assert_eq!(state.check_victory(), GameResult::Ongoing);
+    }
+    
+    #[test]
+    fn test_legal_targets_at_matches_moves() {
+        let mut state = GameState::new(0);
+        put_piece(&mut state, PieceKind::Queen, 0, Square::new(3, 3));
+        put_piece(&mut state, PieceKind::Knight, 1, Square::new(3, 6));
+        
+        let dots = state.legal_targets_at(Square::new(3, 3));
+        let expected = targets(&state.get_legal_moves_at(Square::new(3, 3)));
+        assert_eq!(dots.len(), expected.len());
+        assert_eq!(dots.iter().copied().collect::<HashSet<_>>(), expected);
+        assert!(state.legal_targets_at(Square::new(0, 5)).is_empty());
+    }
+    
+    #[test]
+    fn test_check_victory_full_mate_and_stalemate() {
+        // 백 킹 a1: 흑 룩 두 개가 a, b 파일을 장악 -> 체크메이트
+        let mate = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::Rook, 1, Square::new(0, 7))
+            .piece(PieceKind::Rook, 1, Square::new(1, 6))
+            .piece(PieceKind::King, 1, Square::new(7, 7)).royal()
+            .build();
+        assert!(!mate.has_any_legal_move(0));
+        assert_eq!(mate.check_victory_full(), GameResult::BlackWins);
+        assert_eq!(mate.check_victory(), GameResult::Ongoing);
+        assert_eq!(mate.game_status(), GameResult::BlackWins);
+        
+        // 체크는 아니지만 둘 곳이 없음 -> 스테일메이트
+        let stalemate = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::Queen, 1, Square::new(2, 1))
+            .piece(PieceKind::King, 1, Square::new(7, 7)).royal()
+            .build();
+        assert!(!stalemate.is_check(0));
+        assert_eq!(stalemate.check_victory_full(), GameResult::Draw);
+        assert_eq!(stalemate.game_status(), GameResult::Draw);
+        
+        // 포켓 착수가 가능하면 스테일메이트가 아님
+        let mut with_pocket = stalemate.clone();
+        with_pocket.pockets.insert(0, vec![PieceSpec::new(PieceKind::Pawn)]);
+        assert_eq!(with_pocket.check_victory_full(), GameResult::Ongoing);
+    }
+    
+    #[test]
+    fn test_unknown_transition_name() {
+        let mut state = GameState::new(0);
+        let rook = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        let mv = LegalMove {
+            from: Square::new(0, 0),
+            to: Square::new(0, 1),
+            move_type: MoveType::Move,
+            is_capture: false,
+            tags: vec![chessembly::ActionTag {
+                tag_type: chessembly::ActionTagType::Transition,
+                key: String::new(),
+                value: 0,
+                piece_name: Some("qeen".to_string()),
+            }],
+            catch_to: None,
+            advance_to: None,
+        };
+        
+        // 엄격 모드: 이동 전에 거부
+        state.strict_transitions = true;
+        let err = state.clone().move_piece_by_legal_moves(mv.clone()).unwrap_err();
+        assert_eq!(err, GameError::UnknownTransition("qeen".to_string()));
+        
+        // 기본: 변환만 건너뛰고 이동은 적용
+        state.strict_transitions = false;
+        state.move_piece_by_legal_moves(mv).unwrap();
+        assert_eq!(state.pieces[&rook].kind, PieceKind::Rook);
+        assert_eq!(state.pieces[&rook].pos, Some(Square::new(0, 1)));
+    }
+    
+    #[test]
+    fn test_edge_gated_transition_promotes() {
+        let script = "move(0, 1); edge-top(0, 2) transition(queen) move(0, 1);";
+        let setup = |square: Square| {
+            let mut state = GameState::new(0);
+            state.register_custom_piece("climber".to_string(), script.to_string(), 1);
+            let climber = put_piece(&mut state, PieceKind::Custom("climber".into()), 0, square);
+            (state, climber)
+        };
+        
+        // 태그 없는 전진과 승급 전진이 같은 칸이면 승급 전진 하나만 남음
+        let (mut state, climber) = setup(Square::new(0, 6));
+        let moves = state.get_legal_moves(&climber);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].tags.len(), 1);
+        state.apply_action(Action::Move { piece_id: climber.clone(), from: Square::new(0, 6), to: Square::new(0, 7) }).unwrap();
+        assert_eq!(state.pieces[&climber].kind, PieceKind::Queen);
+        
+        let (mut state, climber) = setup(Square::new(0, 6));
+        state.move_piece(0, &climber, Square::new(0, 6), Square::new(0, 7), MoveType::Move).unwrap();
+        assert_eq!(state.pieces[&climber].kind, PieceKind::Queen);
+        
+        // 그 전 랭크에서는 승급 없이 전진
+        let (mut state, climber) = setup(Square::new(0, 5));
+        state.move_piece(0, &climber, Square::new(0, 5), Square::new(0, 6), MoveType::Move).unwrap();
+        assert_eq!(state.pieces[&climber].kind, PieceKind::Custom("climber".into()));
+    }
+    
+    #[test]
+    fn test_mirrored_pawn_script_matches_black() {
+        let white = PieceKind::Pawn.chessembly_script(true);
+        assert_eq!(chessembly::mirror_script(white), PieceKind::Pawn.chessembly_script(false));
+    }
+    
+    #[test]
+    fn test_grasshopper_jumps_over_one_piece() {
+        let mut state = GameState::new(0);
+        let hopper = put_piece(&mut state, PieceKind::Grasshopper, 0, Square::new(3, 3));
+        put_piece(&mut state, PieceKind::Pawn, 1, Square::new(3, 5));
+        put_piece(&mut state, PieceKind::Pawn, 0, Square::new(6, 3));
+        put_piece(&mut state, PieceKind::Pawn, 1, Square::new(7, 7));
+        
+        // 위: (3,5)를 넘어 (3,6) / 오른쪽: 아군 (6,3)을 넘어 (7,3) / 대각: (7,7) 너머는 보드 밖
+        let expected: HashSet<Square> = [(3, 6), (7, 3)].into_iter().map(|(x, y)| Square::new(x, y)).collect();
+        assert_eq!(targets(&state.get_legal_moves(&hopper)), expected);
+    }
+    
+    #[test]
+    fn test_grasshopper_lands_only_behind_hurdle() {
+        let mut state = GameState::new(0);
+        let hopper = put_piece(&mut state, PieceKind::Grasshopper, 0, Square::from_notation("d4").unwrap());
+        put_piece(&mut state, PieceKind::Pawn, 1, Square::from_notation("f4").unwrap());
+        
+        // e4를 지나 f4 바로 뒤의 g4에만 착지 (허들이 없는 방향으로는 미끄러지지 않음)
+        assert_eq!(targets(&state.get_legal_moves(&hopper)), HashSet::from([Square::from_notation("g4").unwrap()]));
+    }
+    
+    #[test]
+    fn test_kingside_castle() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(7, 0))
+            .piece(PieceKind::Rook, 0, Square::new(0, 0))
+            .piece(PieceKind::Knight, 0, Square::new(1, 0))
+            .piece(PieceKind::King, 1, Square::new(4, 7)).royal()
+            .build();
+        let king = state.board[&Square::new(4, 0)].clone();
+        let rook = state.board[&Square::new(7, 0)].clone();
+        let castle = Action::Castle { king_id: king.clone(), rook_id: rook.clone(), side: CastleSide::KingSide };
+        
+        // 퀸사이드는 b1의 나이트에 막힘
+        assert_eq!(state.legal_castles(), vec![castle.clone()]);
+        let queen_rook = state.board[&Square::new(0, 0)].clone();
+        assert_eq!(state.castle(0, &king, &queen_rook, CastleSide::QueenSide), Err(GameError::CastlingBlocked));
+        
+        state.apply_action(castle).unwrap();
+        assert_eq!(state.board.get(&Square::new(6, 0)), Some(&king));
+        assert_eq!(state.board.get(&Square::new(5, 0)), Some(&rook));
+        assert!(state.pieces[&king].has_moved && state.pieces[&rook].has_moved);
+        assert_eq!(state.zobrist_key(), state.zobrist_hash());
+        
+        // 한 번 움직인 킹은 다시 캐슬링할 수 없음
+        state.end_turn().unwrap();
+        state.end_turn().unwrap();
+        assert_eq!(state.castle(0, &king, &queen_rook, CastleSide::QueenSide), Err(GameError::CastlingPieceMoved));
+    }
+    
+    #[test]
+    fn test_is_square_attacked_respects_blockers() {
+        let state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(7, 7)).royal()
+            .piece(PieceKind::King, 1, Square::new(7, 5)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(0, 0))
+            .piece(PieceKind::Pawn, 0, Square::new(0, 3))
+            .piece(PieceKind::Knight, 1, Square::new(0, 5))
+            .build();
+        
+        // 빈 파일/랭크를 따라서는 공격
+        assert!(state.is_square_attacked(Square::new(0, 2), 0));
+        assert!(state.is_square_attacked(Square::new(6, 0), 0));
+        // 아군 폰이 있는 칸과 그 뒤는 공격하지 않음
+        assert!(!state.is_square_attacked(Square::new(0, 3), 0));
+        assert!(!state.is_square_attacked(Square::new(0, 4), 0));
+        assert!(!state.is_square_attacked(Square::new(0, 5), 0));
+        // 흑 기준으로는 따로 계산
+        assert!(!state.is_square_attacked(Square::new(0, 2), 1));
+    }
+    
+    #[test]
+    fn test_castle_rejected_through_attacked_square() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(7, 0))
+            .piece(PieceKind::King, 1, Square::new(4, 7)).royal()
+            .piece(PieceKind::Rook, 1, Square::new(5, 5))
+            .build();
+        let king = state.board[&Square::new(4, 0)].clone();
+        let rook = state.board[&Square::new(7, 0)].clone();
+        
+        // f1이 흑 룩에게 공격받음
+        assert!(state.legal_castles().is_empty());
+        assert_eq!(state.castle(0, &king, &rook, CastleSide::KingSide), Err(GameError::CastlingThroughAttack));
+        assert_eq!(state.castle(0, &king, &rook, CastleSide::QueenSide), Err(GameError::InvalidCastle));
+        assert_eq!(state.board.get(&Square::new(4, 0)), Some(&king));
+    }
+    
+    /// 백 폰이 e2에서 e4로 두 칸 전진하고 턴을 넘긴 국면 (흑 폰은 d4)
+    fn double_stepped_pawns() -> (GameState, PieceId, PieceId) {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(0, 7)).royal()
+            .piece(PieceKind::Pawn, 0, Square::from_notation("e2").unwrap())
+            .piece(PieceKind::Pawn, 1, Square::from_notation("d4").unwrap())
+            .build();
+        let white = state.board[&Square::from_notation("e2").unwrap()].clone();
+        let black = state.board[&Square::from_notation("d4").unwrap()].clone();
+        state.move_piece(0, &white, Square::from_notation("e2").unwrap(), Square::from_notation("e3").unwrap(), MoveType::Move).unwrap();
+        assert_eq!(state.en_passant_pending, None);
+        state.move_piece(0, &white, Square::from_notation("e3").unwrap(), Square::from_notation("e4").unwrap(), MoveType::Move).unwrap();
+        state.end_turn().unwrap();
+        (state, white, black)
+    }
+    
+    #[test]
+    fn test_en_passant_capture() {
+        let (mut state, white, black) = double_stepped_pawns();
+        let e3 = Square::from_notation("e3").unwrap();
+        assert_eq!(state.en_passant_target, Some(e3));
+        assert_eq!(state.zobrist_key(), state.zobrist_hash());
+        
+        let en_passant = state.get_legal_moves(&black).into_iter()
+            .find(|mv| mv.to == e3)
+            .expect("d4의 흑 폰은 e3으로 앙파상할 수 있어야 합니다");
+        assert_eq!(en_passant.catch_to, Some(Square::from_notation("e4").unwrap()));
+        
+        assert_eq!(state.move_piece_by_legal_moves(en_passant).unwrap(), Some(white.clone()));
+        assert_eq!(state.board.get(&e3), Some(&black));
+        assert!(!state.board.contains_key(&Square::from_notation("e4").unwrap()));
+        
+        // 흑의 턴이 끝나면 앙파상 칸도 사라짐
+        state.end_turn().unwrap();
+        assert_eq!(state.en_passant_target, None);
+        assert_eq!(state.zobrist_key(), state.zobrist_hash());
+    }
+    
+    #[test]
+    fn test_en_passant_expires_after_one_turn() {
+        let (mut state, _, black) = double_stepped_pawns();
+        
+        // 흑이 바로 잡지 않고 턴을 넘기면 다음 흑 턴에는 잡을 수 없음
+        state.end_turn().unwrap();
+        state.end_turn().unwrap();
+        assert_eq!(state.en_passant_target, None);
+        assert!(state.get_legal_moves(&black).iter().all(|mv| mv.to != Square::from_notation("e3").unwrap()));
+    }
+    
+    #[test]
+    fn test_turn_exhausted_when_move_stack_runs_out() {
+        let build = |auto_end_turn| {
+            let mut state = GameState::builder()
+                .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+                .piece(PieceKind::King, 1, Square::new(0, 7)).royal()
+                .piece(PieceKind::Pawn, 0, Square::new(4, 1))
+                .build();
+            state.auto_end_turn = auto_end_turn;
+            let pawn = state.board[&Square::new(4, 1)].clone();
+            (state, pawn)
+        };
+        
+        let (mut state, pawn) = build(false);
+        let stack = state.pieces[&pawn].move_stack;
+        assert!(!state.is_turn_exhausted());
+        for y in 1..stack {
+            state.move_piece(0, &pawn, Square::new(4, y), Square::new(4, y + 1), MoveType::Move).unwrap();
+            assert!(!state.is_turn_exhausted());
+        }
+        state.move_piece(0, &pawn, Square::new(4, stack), Square::new(4, stack + 1), MoveType::Move).unwrap();
+        assert_eq!(state.pieces[&pawn].move_stack, 0);
+        assert!(state.is_turn_exhausted());
+        assert_eq!(state.turn, 0);
+        state.end_turn().unwrap();
+        assert!(!state.is_turn_exhausted());
+        
+        // auto_end_turn이면 마지막 이동 직후 흑 턴으로 넘어감
+        let (mut state, pawn) = build(true);
+        for y in 1..=stack {
+            assert_eq!(state.turn, 0);
+            state.move_piece(0, &pawn, Square::new(4, y), Square::new(4, y + 1), MoveType::Move).unwrap();
+        }
+        assert_eq!(state.turn, 1);
+        assert!(!state.is_turn_exhausted());
+        state.undo().unwrap();
+        assert_eq!((state.turn, state.active_piece.clone()), (0, Some(pawn)));
+    }
+    
+    #[test]
+    fn test_builtin_scripts_validate() {
+        let mut interpreter = Interpreter::new();
+        for kind in PieceKind::all() {
+            for is_white in [true, false] {
+                interpreter.parse(kind.chessembly_script(is_white));
+                assert_eq!(interpreter.validate(), Ok(()), "{:?}", kind);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_cannot_move_after_drop() {
+        let mut state = GameState::new(0);
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Pawn)]);
+        let pawn = state.place_piece(0, PieceKind::Pawn, Square::new(1, 0)).unwrap();
+        assert_eq!(state.pieces[&pawn].stun, 0);
+        
+        let mv = state.get_legal_moves(&pawn).into_iter().find(|m| m.to == Square::new(1, 1)).unwrap();
+        let err = state.clone().move_piece_by_legal_moves(mv.clone()).unwrap_err();
+        assert_eq!(err, GameError::OtherActionTaken);
+        assert_eq!(err.to_string(), "이번 턴에 이미 다른 행동을 했습니다");
+        
+        state.action_policy = TurnActionPolicy::MoveAndAction;
+        assert!(state.move_piece_by_legal_moves(mv).is_ok());
+    }
+    
+    #[test]
+    fn test_jump_capture_on_a1() {
+        let jump = |catch_to| LegalMove {
+            from: Square::new(2, 2),
+            to: Square::new(1, 1),
+            move_type: MoveType::Jump,
+            is_capture: false,
+            tags: Vec::new(),
+            catch_to,
+            advance_to: None,
+        };
+        
+        // a1의 적을 잡는 점프
+        let mut state = GameState::new(0);
+        put_piece(&mut state, PieceKind::Cannon, 0, Square::new(2, 2));
+        let victim = put_piece(&mut state, PieceKind::Knight, 1, Square::new(0, 0));
+        assert_eq!(state.move_piece_by_legal_moves(jump(Some(Square::new(0, 0)))).unwrap(), Some(victim));
+        assert!(state.get_piece_at(Square::new(0, 0)).is_none());
+        
+        // 잡을 대상이 없는 점프는 a1을 건드리지 않음
+        let mut state = GameState::new(0);
+        put_piece(&mut state, PieceKind::Cannon, 0, Square::new(2, 2));
+        let bystander = put_piece(&mut state, PieceKind::Knight, 1, Square::new(0, 0));
+        assert_eq!(state.move_piece_by_legal_moves(jump(None)).unwrap(), None);
+        assert_eq!(state.board.get(&Square::new(0, 0)), Some(&bystander));
+    }
+    
+    #[test]
+    fn test_capture_with_zero_stack_does_not_go_negative() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::Knight, 0, Square::new(0, 0)).move_stack(0)
+            .piece(PieceKind::Rook, 1, Square::new(2, 1)).move_stack(0)
+            .piece(PieceKind::Bishop, 1, Square::new(5, 5)).move_stack(0)
+            .build();
+        let attacker = state.get_piece_at(Square::new(0, 0)).unwrap().id.clone();
+        let rook = state.get_piece_at(Square::new(2, 1)).unwrap().id.clone();
+        state.capture(&attacker, &rook).unwrap();
+        assert_eq!(state.pieces[&attacker].move_stack, 0);
+        
+        state.capture_rule = CaptureRule::Remove;
+        let bishop = state.get_piece_at(Square::new(5, 5)).unwrap().id.clone();
+        state.capture(&attacker, &bishop).unwrap();
+        assert_eq!(state.pieces[&attacker].move_stack, 0);
+        assert!(state.validate_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_royal_safety_blocks_attacked_squares() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 1, Square::new(3, 7))
+            .build();
+        let king = state.board[&Square::new(4, 0)].clone();
+        assert!(targets(&state.get_legal_moves(&king)).contains(&Square::new(3, 0)));
+        
+        state.royal_safety = true;
+        let moves = targets(&state.get_legal_moves(&king));
+        assert!(!moves.contains(&Square::new(3, 0)));
+        assert!(!moves.contains(&Square::new(3, 1)));
+        assert!(moves.contains(&Square::new(5, 0)));
+    }
+    
+    #[test]
+    fn test_move_piece_rejects_moves_not_generated() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(0, 0))
+            .piece(PieceKind::Rook, 1, Square::new(3, 7))
+            .build();
+        let king = state.board[&Square::new(4, 0)].clone();
+        let rook = state.board[&Square::new(0, 0)].clone();
+        
+        // 룩의 대각선, 스크립트에 없는 이동 종류
+        assert_eq!(state.move_piece(0, &rook, Square::new(0, 0), Square::new(2, 2), MoveType::TakeMove), Err(GameError::IllegalMove));
+        assert_eq!(state.move_piece(0, &rook, Square::new(0, 0), Square::new(0, 3), MoveType::Jump), Err(GameError::IllegalMove));
+        
+        // royal_safety가 걸러낸 이동
+        state.royal_safety = true;
+        assert_eq!(state.move_piece(0, &king, Square::new(4, 0), Square::new(3, 0), MoveType::TakeMove), Err(GameError::IllegalMove));
+        assert_eq!(state.board.get(&Square::new(4, 0)), Some(&king));
+        assert_eq!(state.undo_depth(), 0);
+    }
+    
+    #[test]
+    fn test_royal_safety_blocks_retreat_along_check_line() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 1)).royal()
+            .piece(PieceKind::Rook, 1, Square::new(4, 7))
+            .build();
+        state.royal_safety = true;
+        let king = state.board[&Square::new(4, 1)].clone();
+        
+        // e1은 지금 킹에 가려 있지만 킹이 물러나면 룩이 그대로 공격
+        let moves = targets(&state.get_legal_moves(&king));
+        assert!(!moves.contains(&Square::new(4, 0)));
+        assert!(!moves.contains(&Square::new(4, 2)));
+        assert!(moves.contains(&Square::new(3, 0)));
+    }
+    
+    #[test]
+    fn test_royal_safety_blocks_capturing_defended_piece() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Knight, 1, Square::new(4, 1))
+            .piece(PieceKind::Rook, 1, Square::new(4, 7))
+            .build();
+        state.royal_safety = true;
+        let king = state.board[&Square::new(4, 0)].clone();
+        
+        // e2의 나이트는 룩이 지키므로 잡을 수 없음
+        let moves = targets(&state.get_legal_moves(&king));
+        assert!(!moves.contains(&Square::new(4, 1)));
+        assert!(moves.contains(&Square::new(3, 1)));
+    }
+    
+    #[test]
+    fn test_move_piece_matches_legal_move_path() {
+        // move_piece도 행마 목록의 LegalMove를 거치므로 Jump의 잡기가 적용됨
+        let mut state = GameState::new(0);
+        let cannon = put_piece(&mut state, PieceKind::Cannon, 0, Square::new(0, 3));
+        let victim = put_piece(&mut state, PieceKind::Knight, 1, Square::new(3, 3));
+        let jump = state.get_legal_moves(&cannon).into_iter()
+            .find(|mv| mv.move_type == MoveType::Jump && mv.catch_to == Some(Square::new(3, 3)))
+            .unwrap();
         
-        let black_king = state.get_piece_at(Square::new(4, 7));
-        assert!(black_king.is_some());
-        assert_eq!(black_king.unwrap().kind, PieceKind::King);
+        let mut by_legal = state.clone();
+        let mut direct = state.clone();
+        let expected = by_legal.move_piece_by_legal_moves(jump.clone()).unwrap();
+        assert_eq!(expected, Some(victim.clone()));
+        assert_eq!(direct.move_piece(0, &cannon, jump.from, jump.to, MoveType::Jump).unwrap(), expected);
+        assert!(direct.same_position(&by_legal));
+        
+        // 출발 칸이 기물 위치와 다르면 거부
+        assert!(state.move_piece(0, &cannon, Square::new(1, 3), jump.to, MoveType::Jump).is_err());
     }
     
     #[test]
-    fn test_move_stack_calculation() {
-        assert_eq!(GameState::initial_move_stack(1), 5);  // 폰
-        assert_eq!(GameState::initial_move_stack(2), 5);  // 다바바, 알필
-        assert_eq!(GameState::initial_move_stack(3), 3);  // 나이트, 비숍
-        assert_eq!(GameState::initial_move_stack(5), 3);  // 룩
-        assert_eq!(GameState::initial_move_stack(7), 2);  // 나이트라이더
-        assert_eq!(GameState::initial_move_stack(9), 1);  // 퀸
-        assert_eq!(GameState::initial_move_stack(13), 1); // 아마존
+    fn test_checking_drops_rook_on_open_file() {
+        let state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(0, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(4, 7)).royal()
+            .pocket(0, vec![PieceKind::Rook])
+            .build();
+        let drops = state.checking_drops(0);
+        assert!(drops.contains(&(PieceKind::Rook, Square::new(4, 3))));
+        assert!(drops.contains(&(PieceKind::Rook, Square::new(1, 7))));
+        assert!(!drops.contains(&(PieceKind::Rook, Square::new(3, 3))));
+        
+        // 흑은 포켓이 비어 있음
+        assert!(state.checking_drops(1).is_empty());
     }
     
     #[test]
-    fn test_piece_scores() {
-        assert_eq!(PieceKind::Pawn.score(), 1);
-        assert_eq!(PieceKind::King.score(), 4);
-        assert_eq!(PieceKind::Queen.score(), 9);
-        assert_eq!(PieceKind::Rook.score(), 5);
-        assert_eq!(PieceKind::Knight.score(), 3);
-        assert_eq!(PieceKind::Bishop.score(), 3);
-        assert_eq!(PieceKind::Amazon.score(), 13);
+    fn test_apply_action_place_and_errors() {
+        let mut state = GameState::new(0);
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Knight)]);
+        
+        let place = Action::Place { kind: PieceKind::Knight, target: Square::new(2, 2) };
+        state.apply_action(place.clone()).unwrap();
+        assert_eq!(state.get_piece_at(Square::new(2, 2)).unwrap().kind, PieceKind::Knight);
+        assert!(state.get_pocket(0).is_empty());
+        
+        // 포켓이 비었으므로 같은 착수는 실패
+        assert!(state.apply_action(place).is_err());
+        let king_id = state.board[&Square::new(4, 0)].clone();
+        let off_path = Action::Move { piece_id: king_id, from: Square::new(4, 0), to: Square::new(4, 4) };
+        assert!(state.apply_action(off_path).is_err());
     }
     
     #[test]
-    fn test_pocket_score_limit() {
+    fn test_apply_action_checks_crown_disguise_and_stun() {
         let mut state = GameState::new(0);
+        state.setup_pocket_unchecked(0, vec![PieceSpec::new(PieceKind::Knight)]);
+        let white_king = state.board[&Square::new(4, 0)].clone();
+        let black_king = state.board[&Square::new(4, 7)].clone();
         
-        // 39점 이하는 OK
-        let valid_pocket = vec![
-            PieceSpec { kind: PieceKind::Queen },  // 9
-            PieceSpec { kind: PieceKind::Rook },   // 5
-            PieceSpec { kind: PieceKind::Rook },   // 5
-            PieceSpec { kind: PieceKind::Bishop }, // 3
-            PieceSpec { kind: PieceKind::Bishop }, // 3
-            PieceSpec { kind: PieceKind::Knight }, // 3
-            PieceSpec { kind: PieceKind::Knight }, // 3
-            PieceSpec { kind: PieceKind::Pawn },   // 1
-            PieceSpec { kind: PieceKind::Pawn },   // 1
-            PieceSpec { kind: PieceKind::Pawn },   // 1
-            PieceSpec { kind: PieceKind::Pawn },   // 1
-            PieceSpec { kind: PieceKind::Pawn },   // 1
-            PieceSpec { kind: PieceKind::Pawn },   // 1
-            PieceSpec { kind: PieceKind::Pawn },   // 1
-            PieceSpec { kind: PieceKind::Pawn },   // 1
-        ]; // 총 39점
-        assert!(state.setup_pocket(0, valid_pocket).is_ok());
+        // 상대 킹은 위장할 수 없음
+        let disguise = Action::Disguise { piece_id: black_king.clone(), as_kind: PieceKind::Queen };
+        assert_eq!(state.apply_action(disguise), Err(GameError::NotYourPiece));
+        assert_eq!(state.pieces[&black_king].disguise, None);
         
-        // 39점 초과는 에러
-        let invalid_pocket = vec![
-            PieceSpec { kind: PieceKind::Amazon }, // 13
-            PieceSpec { kind: PieceKind::Queen },  // 9
-            PieceSpec { kind: PieceKind::Queen },  // 9
-            PieceSpec { kind: PieceKind::Queen },  // 9
-        ]; // 총 40점
-        assert!(state.setup_pocket(1, invalid_pocket).is_err());
+        // 착수한 턴에는 계승과 스턴도 할 수 없음
+        state.apply_action(Action::Place { kind: PieceKind::Knight, target: Square::new(2, 2) }).unwrap();
+        let knight = state.board[&Square::new(2, 2)].clone();
+        let before = state.clone();
+        assert_eq!(state.apply_action(Action::Crown { piece_id: knight.clone() }), Err(GameError::AlreadyActed));
+        assert_eq!(state.apply_action(Action::Stun { piece_id: black_king, amount: 1 }), Err(GameError::AlreadyActed));
+        assert_eq!(
+            state.apply_action(Action::Disguise { piece_id: white_king, as_kind: PieceKind::Rook }),
+            Err(GameError::AlreadyActed),
+        );
+        assert!(state.same_position(&before));
+        assert!(!state.pieces[&knight].is_royal);
     }
     
     #[test]
-    fn test_capture_stack_transfer() {
+    fn test_phase_opening_and_endgame() {
         let mut state = GameState::new(0);
+        assert_eq!(state.phase(), GamePhase::Endgame);
         
-        // 공격자 배치 (나이트: 3점, 이동3)
-        let attacker = state.create_piece(PieceKind::Knight, 0);
-        let attacker_id = attacker.id.clone();
-        state.pieces.insert(attacker_id.clone(), attacker);
-        if let Some(p) = state.pieces.get_mut(&attacker_id) {
-            p.pos = Some(Square::new(0, 0));
-            p.move_stack = 3;
-            p.stun = 0;
+        let full = vec![
+            PieceSpec::new(PieceKind::Queen), PieceSpec::new(PieceKind::Rook), PieceSpec::new(PieceKind::Rook),
+            PieceSpec::new(PieceKind::Bishop), PieceSpec::new(PieceKind::Bishop),
+            PieceSpec::new(PieceKind::Knight), PieceSpec::new(PieceKind::Knight),
+        ];
+        for player in 0..2 {
+            state.setup_pocket(player, full.clone()).unwrap();
         }
-        state.board.insert(Square::new(0, 0), attacker_id.clone());
+        assert_eq!(state.phase(), GamePhase::Opening);
         
-        // 피해자 배치 (룩: 5점, 이동3, 스턴2)
-        let victim = state.create_piece(PieceKind::Rook, 1);
-        let victim_id = victim.id.clone();
-        state.pieces.insert(victim_id.clone(), victim);
-        if let Some(p) = state.pieces.get_mut(&victim_id) {
-            p.pos = Some(Square::new(2, 1));
-            p.move_stack = 3;
-            p.stun = 2;
+        // 보드에 기물이 많아지면 미들게임
+        for x in 0..5 {
+            put_piece(&mut state, PieceKind::Pawn, 0, Square::new(x, 1));
         }
-        state.board.insert(Square::new(2, 1), victim_id.clone());
+        assert_eq!(state.phase(), GamePhase::Middlegame);
         
-        // 캡처 실행
-        state.capture(&attacker_id, &victim_id).unwrap();
+        // 기준 변경
+        state.phase_thresholds.opening_max_board_pieces = 8;
+        assert_eq!(state.phase(), GamePhase::Opening);
+    }
+    
+    #[test]
+    fn test_affordable_additions_within_budget() {
+        let mut state = GameState::new(0);
+        let pocket = vec![
+            PieceSpec::new(PieceKind::Queen), PieceSpec::new(PieceKind::Queen), PieceSpec::new(PieceKind::Queen),
+            PieceSpec::new(PieceKind::Rook), PieceSpec::new(PieceKind::Knight), PieceSpec::new(PieceKind::Pawn),
+        ];
+        state.setup_pocket(0, pocket).unwrap(); // 36점
         
-        // 결과 확인
-        let attacker = state.pieces.get(&attacker_id).unwrap();
-        // 이동 스택: 3 - 1 + 3 = 5
-        assert_eq!(attacker.move_stack, 5);
-        // 스턴 스택: 0 + 2 = 2
-        assert_eq!(attacker.stun, 2);
+        let kinds = state.affordable_additions(0);
+        assert!(kinds.iter().all(|k| k.score() <= 3));
+        assert!(kinds.contains(&PieceKind::Knight));
+        assert!(!kinds.contains(&PieceKind::Grasshopper));
+        assert_eq!(state.affordable_additions(1).len(), PieceKind::all().len() - 1);
+    }
+    
+    #[test]
+    fn test_step_forward_with_events_capture() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(4, 7)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(0, 0))
+            .piece(PieceKind::Knight, 1, Square::new(0, 5))
+            .pocket(1, vec![PieceKind::Pawn])
+            .build();
+        let rook = state.board[&Square::new(0, 0)].clone();
+        let knight = state.board[&Square::new(0, 5)].clone();
+        let transcript = vec![
+            vec![Action::Move { piece_id: rook.clone(), from: Square::new(0, 0), to: Square::new(0, 5) }],
+            vec![Action::Place { kind: PieceKind::Pawn, target: Square::new(3, 5) }],
+        ];
         
-        // 피해자 제거됨
-        assert!(state.pieces.get(&victim_id).is_none());
+        let events = state.step_forward_with_events(&transcript, 0).unwrap();
+        assert_eq!(events, vec![
+            GameEvent::Captured { piece_id: knight, kind: PieceKind::Knight, at: Square::new(0, 5), by: 0 },
+            GameEvent::Moved { piece_id: rook, from: Square::new(0, 0), to: Square::new(0, 5) },
+            GameEvent::TurnEnded { next: 1 },
+        ]);
+        
+        let events = state.step_forward_with_events(&transcript, 1).unwrap();
+        assert!(matches!(events[0], GameEvent::Placed { kind: PieceKind::Pawn, at, .. } if at == Square::new(3, 5)));
+        assert!(state.step_forward_with_events(&transcript, 2).is_err());
+        assert!(state.clone_bare().events.is_empty());
     }
     
     #[test]
-    fn test_victory_condition() {
+    fn test_disguise_rejects_king_and_nonstandard() {
         let mut state = GameState::new(0);
-        assert_eq!(state.check_victory(), GameResult::Ongoing);
-        
-        // 흑 킹 제거
-        let black_king_id = state.board.get(&Square::new(4, 7)).cloned();
-        if let Some(id) = black_king_id {
-            state.board.remove(&Square::new(4, 7));
-            state.pieces.remove(&id);
+        let king_id = state.board[&Square::new(4, 0)].clone();
+        for kind in [PieceKind::King, PieceKind::Experiment, PieceKind::Custom("x".into())] {
+            assert!(state.disguise_piece(0, &king_id, kind).is_err());
         }
+        assert_eq!(state.pieces[&king_id].disguise, None);
         
-        assert_eq!(state.check_victory(), GameResult::WhiteWins);
+        // 액션 디스패처로 들어와도 같은 검사
+        let as_king = Action::Disguise { piece_id: king_id.clone(), as_kind: PieceKind::King };
+        assert_eq!(state.apply_action(as_king), Err(GameError::DisguiseAsKing));
+        assert_eq!(state.pieces[&king_id].disguise, None);
+        
+        state.disguise_piece(0, &king_id, PieceKind::Knight).unwrap();
+        let king = &state.pieces[&king_id];
+        assert_eq!((king.score(), king.effective_score()), (4, 3));
     }
     
     #[test]
-    fn test_square_notation() {
-        let e4 = Square::from_notation("e4").unwrap();
-        assert_eq!(e4.x, 4);
-        assert_eq!(e4.y, 3);
-        assert_eq!(e4.to_notation(), "e4");
+    fn test_set_global_gates_if_state_moves() {
+        let mut state = GameState::new(0);
+        let rook = put_piece(&mut state, PieceKind::Rook, 0, Square::new(3, 3));
+        let mut interpreter = Interpreter::new();
+        interpreter.parse("if-state(mode, 1) move(1, 0);");
+        let moves = |state: &GameState| {
+            let mut board = state.to_chessembly_board(&rook, false).unwrap();
+            interpreter.execute(&mut board).unwrap().len()
+        };
         
-        let a1 = Square::from_notation("a1").unwrap();
-        assert_eq!(a1.x, 0);
-        assert_eq!(a1.y, 0);
+        assert_eq!(state.get_global("mode"), 0);
+        assert_eq!(moves(&state), 0);
+        state.set_global("mode", 1);
+        assert_eq!(moves(&state), 1);
+        state.set_global("alpha", 2);
+        assert_eq!(state.global_keys(), vec!["alpha".to_string(), "mode".to_string()]);
+    }
+    
+    #[test]
+    fn test_add_state_counts_moves() {
+        let mut state = GameState::new(0);
+        state.register_custom_piece("counter".into(), "add-state(moves, 1) move(0, 1); sub-state(moves, 1) move(0, -1);".into(), 2);
+        let id = put_piece(&mut state, PieceKind::Custom("counter".into()), 0, Square::new(3, 3));
+        // 목록을 만드는 것만으로는 상태가 바뀌지 않음
+        let forward = state.get_legal_moves(&id).into_iter().find(|mv| mv.to == Square::new(3, 4)).unwrap();
+        assert_eq!(state.get_global("moves"), 0);
         
-        let h8 = Square::from_notation("h8").unwrap();
-        assert_eq!(h8.x, 7);
-        assert_eq!(h8.y, 7);
+        state.move_piece_by_legal_moves(forward).unwrap();
+        assert_eq!(state.get_global("moves"), 1);
+        
+        let back = state.get_legal_moves(&id).into_iter().find(|mv| mv.to == Square::new(3, 3)).unwrap();
+        state.move_piece_by_legal_moves(back).unwrap();
+        assert_eq!(state.get_global("moves"), 0);
     }
     
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_pawn_promotion_stun() {
-        let state = GameState::new(0);
+    fn test_serde_json_roundtrip() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(4, 7)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(0, 0)).stun(2)
+            .piece(PieceKind::Knight, 1, Square::new(2, 5)).move_stack(1)
+            .pocket(0, vec![PieceKind::Pawn, PieceKind::Bishop])
+            .build();
+        state.set_global("mode", 3);
         
-        // 폰 생성
-        let pawn = Piece::new("pawn1".to_string(), PieceKind::Pawn, 0);
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: GameState = serde_json::from_str(&json).unwrap();
+        assert!(restored.same_position(&state));
+        assert_eq!(restored.get_pocket(0), state.get_pocket(0));
+        assert_eq!(restored.get_global("mode"), 3);
+        for (id, piece) in &state.pieces {
+            let other = &restored.pieces[id];
+            assert_eq!((other.pos, other.stun, other.move_stack), (piece.pos, piece.stun, piece.move_stack));
+        }
+        assert!(restored.validate_invariants().is_ok());
+        assert_ne!(restored.zobrist_key(), 0);
+        assert_eq!(restored.zobrist_key(), state.zobrist_key());
         
-        // 1랭크(y=0) 착수: 스턴 0
-        let stun_at_1 = state.calculate_placement_stun(&pawn, Square::new(0, 0));
-        assert_eq!(stun_at_1, 0);
+        assert!(serde_json::from_str::<Square>("\"z9\"").is_err());
+    }
+    
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_rejects_other_versions() {
+        let json = serde_json::to_string(&GameState::new(0)).unwrap();
+        let version = format!("\"version\":{}", SERDE_VERSION);
+        assert!(json.starts_with(&format!("{{{}", version)));
         
-        // 7랭크(y=6) 착수: 스턴 ~7 (거리 1)
-        let stun_at_7 = state.calculate_placement_stun(&pawn, Square::new(0, 6));
-        assert!(stun_at_7 > 0);
+        let bumped = json.replacen(&version, &format!("\"version\":{}", SERDE_VERSION + 1), 1);
+        let err = serde_json::from_str::<GameState>(&bumped).unwrap_err();
+        assert!(err.to_string().contains("지원하지 않는 형식 버전"));
+        // 버전이 없는 이전 형식도 거부
+        assert!(serde_json::from_str::<GameState>(&json.replacen(&format!("{},", version), "", 1)).is_err());
     }
     
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_crown_piece() {
+    fn test_serde_json_keeps_repetitions() {
         let mut state = GameState::new(0);
+        let shuffle = |state: &mut GameState, player: PlayerId, from: Square, to: Square| {
+            let king = state.board[&from].clone();
+            state.move_piece(player, &king, from, to, MoveType::TakeMove).unwrap();
+            state.end_turn().unwrap();
+        };
+        let round = |state: &mut GameState| {
+            let (e1, e2, e7, e8) = (Square::new(4, 0), Square::new(4, 1), Square::new(4, 6), Square::new(4, 7));
+            shuffle(state, 0, e1, e2);
+            shuffle(state, 1, e8, e7);
+            shuffle(state, 0, e2, e1);
+            shuffle(state, 1, e7, e8);
+        };
+        round(&mut state);
+        round(&mut state);
+        assert_eq!(state.repetition_count(), 2);
         
-        // 폰 배치
-        state.pockets.insert(0, vec![PieceSpec { kind: PieceKind::Pawn }]);
-        let pawn_id = state.place_piece(0, PieceKind::Pawn, Square::new(0, 1)).unwrap();
+        // 읽은 국면에서 한 바퀴 더 돌면 세 번째 반복
+        let json = serde_json::to_string(&state).unwrap();
+        let mut restored: GameState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.repetition_count(), 2);
+        round(&mut restored);
+        assert!(restored.is_threefold_repetition());
+    }
+    
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_roundtrip_large_board() {
+        let mut state = GameState::with_size(10, 10, 0);
+        put_piece(&mut state, PieceKind::Rook, 0, Square::new(9, 8));
         
-        // 턴 종료 후 계승
-        state.end_turn();
-        state.end_turn();
-        state.action_taken = false;
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(json.contains("\"j9\""));
+        let restored: GameState = serde_json::from_str(&json).unwrap();
+        assert!(restored.same_position(&state));
+        assert_eq!((restored.board_width, restored.board_height), (10, 10));
+        assert!(restored.validate_invariants().is_ok());
         
-        assert!(state.crown_piece(0, &pawn_id).is_ok());
-        assert!(state.pieces.get(&pawn_id).unwrap().is_royal);
+        // 칸 표기는 읽히지만 8x8 국면에서는 보드 밖
+        let narrowed = json.replace("\"board_width\":10", "\"board_width\":8");
+        let restored: GameState = serde_json::from_str(&narrowed).unwrap();
+        assert!(matches!(restored.validate_invariants(), Err(GameError::InvariantViolation(_))));
+        assert!(serde_json::from_str::<Square>("\"q1\"").is_err());
     }
     
     #[test]
-    fn test_pawn_cannot_place_on_promotion_rank() {
+    fn test_debug_dump_reproduces_legal_moves() {
         let mut state = GameState::new(0);
-        state.pockets.insert(0, vec![PieceSpec { kind: PieceKind::Pawn }]);
+        let rook = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        put_piece(&mut state, PieceKind::Knight, 1, Square::new(0, 5));
+        state.set_global("mode", 2);
+        state.move_piece(0, &rook, Square::new(0, 0), Square::new(0, 3), MoveType::TakeMove).unwrap();
         
-        // 8랭크(y=7)에 폰 착수 시도 - 실패해야 함
-        let result = state.place_piece(0, PieceKind::Pawn, Square::new(0, 7));
-        assert!(result.is_err());
+        let dump = state.to_debug_dump();
+        assert!(dump.contains("active_piece: a4"));
+        assert!(dump.contains("global: mode=2"));
+        assert!(dump.lines().any(|l| l.starts_with("script Rook(w): ")));
+        
+        let restored = GameState::from_debug_dump(&dump).unwrap();
+        assert!(restored.same_position(&state));
+        assert_eq!((restored.turn, restored.action_taken), (state.turn, state.action_taken));
+        assert!(restored.pieces[&restored.board[&Square::new(0, 3)]].has_moved);
+        for square in state.board.keys() {
+            let moves = |s: &GameState| {
+                let mut v: Vec<(Square, MoveType)> = s.get_legal_moves_at(*square).iter().map(|m| (m.to, m.move_type)).collect();
+                v.sort_by_key(|(sq, _)| (sq.y, sq.x));
+                v
+            };
+            assert_eq!(moves(&restored), moves(&state));
+        }
+        // 활성 기물이 있으므로 다른 기물은 움직일 수 없음
+        let king = restored.board[&Square::new(4, 0)].clone();
+        assert!(restored.can_move_piece(0, &king, Square::new(4, 0), Square::new(4, 1), MoveType::TakeMove).is_err());
     }
     
     #[test]
-    fn test_king_legal_moves() {
-        let state = GameState::new(0);
+    fn test_debug_dump_restores_rules_and_custom_pieces() {
+        let mut state = GameState::with_size(10, 10, 0);
+        state.register_custom_piece("wazir".into(), "# 와지르\ntake-move(1, 0); take-move(-1, 0);\ntake-move(0, 1); take-move(0, -1);".into(), 2);
+        put_piece(&mut state, PieceKind::Custom("wazir".into()), 0, Square::new(9, 9));
+        put_piece(&mut state, PieceKind::Rook, 1, Square::new(4, 5));
+        state.royal_safety = true;
+        state.capture_rule = CaptureRule::Remove;
+        state.action_policy = TurnActionPolicy::MoveAndAction;
+        state.rules.move_stack_table = vec![(1, 4), (5, 2)];
+        state.rules.max_pocket_score = 20;
         
-        // 백 킹 (e1)의 이동 가능 칸 확인
-        let white_king_id = state.board.get(&Square::new(4, 0)).unwrap().clone();
-        let moves = state.get_legal_moves(&white_king_id);
+        let dump = state.to_debug_dump();
+        assert!(dump.contains(";10x10;"));
+        assert!(dump.contains("capture_rule: Remove"));
         
-        // e1에서 킹이 갈 수 있는 칸: d1, f1, d2, e2, f2 (5칸)
-        assert!(!moves.is_empty());
+        let restored = GameState::from_debug_dump(&dump).unwrap();
+        assert_eq!((restored.board_width, restored.board_height), (10, 10));
+        assert!(restored.royal_safety);
+        assert_eq!(restored.capture_rule, CaptureRule::Remove);
+        assert_eq!(restored.drop_in_check, state.drop_in_check);
+        assert_eq!(restored.action_policy, TurnActionPolicy::MoveAndAction);
+        assert_eq!(restored.rules.move_stack_table, vec![(1, 4), (5, 2)]);
+        assert_eq!(restored.rules.max_pocket_score, 20);
+        assert_eq!(restored.custom_pieces, state.custom_pieces);
+        for square in state.board.keys() {
+            let moves = |s: &GameState| {
+                let mut v: Vec<(Square, MoveType)> = s.get_legal_moves_at(*square).iter().map(|m| (m.to, m.move_type)).collect();
+                v.sort_by_key(|(sq, _)| (sq.y, sq.x));
+                v
+            };
+            assert_eq!(moves(&restored), moves(&state));
+        }
         
-        // d2로 이동 가능한지 확인
-        assert!(moves.iter().any(|m| m.to == Square::new(3, 1)));
-        // e2로 이동 가능한지 확인
-        assert!(moves.iter().any(|m| m.to == Square::new(4, 1)));
+        // 값 없이 키만 있는 줄은 허용하지만 구분자가 없는 줄은 오류
+        assert!(GameState::from_debug_dump(&dump.replace("global: ", "global:")).is_ok());
+        assert!(GameState::from_debug_dump(&format!("{}\ngarbage", dump)).is_err());
+        assert!(GameState::from_debug_dump(&dump.replace("capture_rule: Remove", "capture_rule: Steal")).is_err());
     }
     
     #[test]
-    fn test_rook_legal_moves() {
+    fn test_notation_roundtrip() {
+        let state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::King, 1, Square::new(4, 7)).royal()
+            .piece(PieceKind::Amazon, 0, Square::new(0, 0)).stun(3).move_stack(1)
+            .piece(PieceKind::Knightrider, 1, Square::new(7, 3))
+            .piece(PieceKind::Custom("wyvern".into()), 1, Square::new(2, 5)).stun(1)
+            .piece(PieceKind::Rook, 0, Square::new(1, 0)).royal()
+            .turn(1)
+            .pocket(0, vec![PieceKind::Queen, PieceKind::Cannon])
+            .build();
+        let mut state = state;
+        let rook = state.board[&Square::new(1, 0)].clone();
+        state.pieces.get_mut(&rook).unwrap().disguise = Some(PieceKind::Camel);
+        
+        let text = state.to_notation();
+        assert!(text.starts_with("4k*{0,3}3/8/2u'wyvern'{1,"));
+        assert!(text.contains("Am{3,1}R*~Cm{0,"));
+        assert!(text.ends_with(" b - - Q,Ca -"));
+        
+        let restored = GameState::from_notation(&text).unwrap();
+        assert!(restored.same_position(&state));
+        assert_eq!(restored.to_notation(), text);
+        assert_eq!(restored.get_pocket(0), state.get_pocket(0));
+        
+        assert!(GameState::from_notation("8/8/8/8/8/8/8/7 w - - - -").is_err());
+        assert!(GameState::from_notation("8/8/8/8/8/8/8/Zz{0,0}7 w - - - -").is_err());
+        assert!(GameState::from_notation("8/8/8/8/8/8/8/K7 w - - - -").is_err());
+        assert!(GameState::from_notation("8/8/8/8/8/8/8/K{0,0}7 w b1 - - -").is_err());
+        assert!(GameState::from_notation("8/8/8/8/8/8/8/K{0,0}7 w - - -").is_err());
+    }
+    
+    #[test]
+    fn test_notation_keeps_moved_and_en_passant() {
+        let mut state = GameState::builder()
+            .piece(PieceKind::King, 0, Square::new(4, 0)).royal()
+            .piece(PieceKind::Rook, 0, Square::new(7, 0))
+            .piece(PieceKind::King, 1, Square::new(4, 7)).royal()
+            .build();
+        let king = state.board[&Square::new(4, 0)].clone();
+        state.move_piece(0, &king, Square::new(4, 0), Square::new(4, 1), MoveType::TakeMove).unwrap();
+        state.end_turn().unwrap();
+        let text = state.to_notation();
+        assert!(text.ends_with(" b e2 - - -"));
+        let restored = GameState::from_notation(&text).unwrap();
+        assert!(restored.same_position(&state));
+        assert!(!restored.pieces[&restored.board[&Square::new(7, 0)]].has_moved);
+        assert_eq!(restored.zobrist_key(), state.zobrist_key());
+        
+        let (state, _, _) = double_stepped_pawns();
+        let text = state.to_notation();
+        assert!(text.ends_with(" b e4 e3 - -"));
+        let restored = GameState::from_notation(&text).unwrap();
+        assert_eq!(restored.en_passant_target, state.en_passant_target);
+        assert!(restored.same_position(&state));
+        assert_eq!(restored.zobrist_key(), state.zobrist_key());
+    }
+    
+    #[test]
+    fn test_undo_capture_restores_victim_and_stacks() {
         let mut state = GameState::new(0);
+        let rook = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        let knight = put_piece(&mut state, PieceKind::Knight, 1, Square::new(0, 5));
+        state.pieces.get_mut(&knight).unwrap().stun = 2;
+        let before = state.clone();
         
-        // 룩 배치 (d4)
-        let rook = state.create_piece(PieceKind::Rook, 0);
-        let rook_id = rook.id.clone();
-        state.pieces.insert(rook_id.clone(), rook);
-        if let Some(p) = state.pieces.get_mut(&rook_id) {
-            p.pos = Some(Square::new(3, 3)); // d4
-            p.move_stack = 3;
-            p.stun = 0;
-        }
-        state.board.insert(Square::new(3, 3), rook_id.clone());
+        state.move_piece(0, &rook, Square::new(0, 0), Square::new(0, 5), MoveType::TakeMove).unwrap();
+        assert!(!state.pieces.contains_key(&knight));
+        assert_eq!(state.pieces[&rook].stun, 2);
+        state.end_turn().unwrap();
+        assert_eq!(state.undo_depth(), 2);
         
-        // chessembly 직접 테스트
-        let script = "take-move(1, 0) repeat(1); take-move(-1, 0) repeat(1); take-move(0, 1) repeat(1); take-move(0, -1) repeat(1);";
+        // 턴 종료(스턴 감소, 스택 재충전)와 잡기를 차례로 되돌림
+        state.undo().unwrap();
+        assert_eq!(state.turn, 0);
+        state.undo().unwrap();
+        assert!(state.undo().is_err());
+        assert!(state.same_position(&before));
+        let restored = &state.pieces[&knight];
+        assert_eq!((restored.pos, restored.stun, restored.move_stack), (Some(Square::new(0, 5)), 2, before.pieces[&knight].move_stack));
+        assert_eq!((state.pieces[&rook].stun, state.pieces[&rook].move_stack), (0, before.pieces[&rook].move_stack));
+        assert_eq!(state.captured_value(0), 0);
+        assert!(state.events.is_empty());
         
-        let mut board = state.to_chessembly_board(&rook_id).unwrap();
-        let mut interpreter = Interpreter::new();
-        interpreter.set_debug(state.debug_mode);
-        interpreter.parse(script);
-        let activations = interpreter.execute(&mut board);
+        // 다시 실행하면 잡은 뒤의 국면과 이벤트로 돌아감
+        state.redo().unwrap();
+        assert!(!state.pieces.contains_key(&knight));
+        assert_eq!(state.events.len(), 2);
+        state.redo().unwrap();
+        assert_eq!(state.turn, 1);
+        assert!(state.redo().is_err());
         
-        println!("Script: {}", script);
-        println!("Piece at: ({}, {})", board.piece_x, board.piece_y);
-        println!("Activations count: {}", activations.len());
-        for a in &activations {
-            let target_x = board.piece_x + a.dx;
-            let target_y = board.piece_y + a.dy;
-            println!("  dx={}, dy={} -> ({}, {})", a.dx, a.dy, target_x, target_y);
+        // 새 행동을 하면 다시 실행 기록은 사라짐
+        state.undo().unwrap();
+        state.end_turn().unwrap();
+        assert_eq!(state.redo_depth(), 0);
+    }
+    
+    #[test]
+    fn test_perft_initial_kings() {
+        // e1 킹: 이동 5, 위장 16, 스턴 4(아군 1~3, 적 1), 턴 종료 1
+        let state = GameState::new(0);
+        assert_eq!(state.perft(0), 1);
+        assert_eq!(state.perft(1), 26);
+        // 이동 후: 연속 이동(d1/f1 5, d2/e2/f2 8) + 턴 종료, 위장/스턴 후: 턴 종료만,
+        // 턴 종료 후: 흑의 26
+        assert_eq!(state.perft(2), (6 + 6 + 9 + 9 + 9) + 16 + 4 + 26);
+    }
+    
+    #[test]
+    fn test_zobrist_transposition() {
+        // 같은 국면에 다른 경로로 도달 (킹 e1-d1-d2 / e1-e2-d2, 남은 이동 스택도 같음)
+        let mut a = GameState::new(0);
+        a.setup_initial_position();
+        let mut b = a.clone();
+        let king = a.board[&Square::new(4, 0)].clone();
+        for (state, via) in [(&mut a, Square::new(3, 0)), (&mut b, Square::new(4, 1))] {
+            state.move_piece(0, &king, Square::new(4, 0), via, MoveType::TakeMove).unwrap();
+            state.move_piece(0, &king, via, Square::new(3, 1), MoveType::TakeMove).unwrap();
+            state.end_turn().unwrap();
+            state.place_piece(1, PieceKind::Knight, Square::new(6, 7)).unwrap();
         }
         
-        // 오른쪽으로 이동 가능
-        assert!(activations.iter().any(|a| a.dx == 1 && a.dy == 0), "오른쪽 이동 필요");
-        // 왼쪽으로 이동 가능
-        assert!(activations.iter().any(|a| a.dx == -1 && a.dy == 0), "왼쪽 이동 필요");
-        // 위로 이동 가능
-        assert!(activations.iter().any(|a| a.dx == 0 && a.dy == 1), "위 이동 필요");
-        // 아래로 이동 가능
-        assert!(activations.iter().any(|a| a.dx == 0 && a.dy == -1), "아래 이동 필요");
+        assert!(a.same_position(&b));
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+        assert_eq!(a.zobrist_key(), a.zobrist_hash());
+        assert_eq!(b.zobrist_key(), b.zobrist_hash());
+        
+        // 스턴만 달라도 해시가 다름
+        let knight = a.pieces_of_kind(1, &PieceKind::Knight)[0].clone();
+        a.end_turn().unwrap();
+        let before = a.zobrist_key();
+        a.apply_stun(0, &knight, 1).unwrap();
+        assert_ne!(a.zobrist_key(), before);
+        assert_eq!(a.zobrist_key(), a.zobrist_hash());
+        a.undo().unwrap();
+        assert_eq!(a.zobrist_key(), before);
     }
     
     #[test]
-    fn test_knight_legal_moves() {
+    fn test_zobrist_incremental_matches_after_capture() {
         let mut state = GameState::new(0);
+        state.capture_rule = CaptureRule::ReturnToPocket;
+        let rook = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 0));
+        put_piece(&mut state, PieceKind::Knight, 1, Square::new(0, 5));
+        state.refresh_zobrist();
         
-        // 나이트 배치 (d4)
-        let knight = state.create_piece(PieceKind::Knight, 0);
-        let knight_id = knight.id.clone();
-        state.pieces.insert(knight_id.clone(), knight);
-        if let Some(p) = state.pieces.get_mut(&knight_id) {
-            p.pos = Some(Square::new(3, 3)); // d4
-            p.move_stack = 3;
-            p.stun = 0;
+        state.move_piece(0, &rook, Square::new(0, 0), Square::new(0, 5), MoveType::TakeMove).unwrap();
+        assert_eq!(state.zobrist_key(), state.zobrist_hash());
+        state.end_turn().unwrap();
+        assert_eq!(state.zobrist_key(), state.zobrist_hash());
+    }
+    
+    #[test]
+    fn test_threefold_repetition_king_shuffle() {
+        let mut state = GameState::new(0);
+        let shuffle = |state: &mut GameState, player: PlayerId, from: Square, to: Square| {
+            let king = state.board[&from].clone();
+            state.move_piece(player, &king, from, to, MoveType::TakeMove).unwrap();
+            state.end_turn().unwrap();
+        };
+        let (e1, e2, e7, e8) = (Square::new(4, 0), Square::new(4, 1), Square::new(4, 6), Square::new(4, 7));
+        assert_eq!(state.repetition_count(), 1);
+        
+        // 시작 국면의 킹 이동 스택(3)은 턴마다 채워지는 값과 달라 첫 바퀴부터 셈
+        for round in 1..=3 {
+            shuffle(&mut state, 0, e1, e2);
+            shuffle(&mut state, 1, e8, e7);
+            shuffle(&mut state, 0, e2, e1);
+            shuffle(&mut state, 1, e7, e8);
+            assert_eq!(state.repetition_count(), round);
         }
-        state.board.insert(Square::new(3, 3), knight_id.clone());
+        assert!(state.is_threefold_repetition());
         
-        let moves = state.get_legal_moves(&knight_id);
+        // 되돌리면 반복 횟수도 되돌아감
+        state.undo().unwrap();
+        assert!(!state.is_threefold_repetition());
+        state.redo().unwrap();
         
-        // 나이트 L자 이동: b3, b5, c2, c6, e2, e6, f3, f5 (8칸)
-        assert_eq!(moves.len(), 8);
+        // 스턴이 다르면 다른 국면
+        let black_king = state.board[&e8].clone();
+        state.apply_stun(0, &black_king, 1).unwrap();
+        state.end_turn().unwrap();
+        assert_eq!(state.repetition_count(), 1);
+    }
+    
+    #[test]
+    fn test_insufficient_material_lone_kings() {
+        let mut state = GameState::new(0);
+        assert!(state.is_insufficient_material());
+        assert_eq!(state.check_victory_full(), GameResult::Draw);
         
-        // b5 (1,4)로 이동 가능
-        assert!(moves.iter().any(|m| m.to == Square::new(1, 4)));
-        // f5 (5,4)로 이동 가능
-        assert!(moves.iter().any(|m| m.to == Square::new(5, 4)));
+        // 포켓에 폰이 남아 있으면 무승부가 아님
+        state.setup_pocket(1, vec![PieceSpec::new(PieceKind::Pawn)]).unwrap();
+        assert!(!state.is_insufficient_material());
+        assert_eq!(state.check_victory_full(), GameResult::Ongoing);
+        
+        // 보드 위 비로얄 기물도 마찬가지, 계승하면 로얄로 취급
+        let mut state = GameState::new(0);
+        let knight = put_piece(&mut state, PieceKind::Knight, 0, Square::new(1, 0));
+        assert!(!state.is_insufficient_material());
+        state.crown_piece(0, &knight).unwrap();
+        assert!(state.is_insufficient_material());
+    }
+    
+    #[test]
+    fn test_register_custom_wazir() {
+        let mut state = GameState::new(0);
+        let wazir = put_piece(&mut state, PieceKind::Custom("wazir".into()), 0, Square::new(3, 3));
+        // 등록 전에는 킹처럼
+        assert_eq!(state.get_legal_moves(&wazir).len(), 8);
+        
+        state.register_custom_piece("wazir".into(), "take-move(1, 0); take-move(-1, 0); take-move(0, 1); take-move(0, -1);".into(), 2);
+        let expected: HashSet<Square> = [(4, 3), (2, 3), (3, 4), (3, 2)].into_iter().map(|(x, y)| Square::new(x, y)).collect();
+        assert_eq!(targets(&state.get_legal_moves(&wazir)), expected);
+        
+        // 흑은 뒤집힌 스크립트로 전진
+        state.register_custom_piece("lance".into(), "move(0, 1);".into(), 3);
+        let lance = put_piece(&mut state, PieceKind::Custom("lance".into()), 1, Square::new(6, 5));
+        assert_eq!(targets(&state.get_legal_moves(&lance)), HashSet::from([Square::new(6, 4)]));
+    }
+    
+    #[test]
+    fn test_registered_custom_score() {
+        let mut state = GameState::new(0);
+        let wyvern = PieceKind::Custom("wyvern".into());
+        assert_eq!(state.score_of(&wyvern), 3);
+        
+        state.register_custom_piece("wyvern".into(), "take-move(0, 1);".into(), 8);
+        assert_eq!(state.score_of(&wyvern), 8);
+        state.setup_pocket(0, vec![PieceSpec::new(wyvern.clone())]).unwrap();
+        assert_eq!(state.material(0), PieceKind::King.score() + 8);
+        
+        // 착수 시 이동 스택과 스턴이 등록 점수를 따름
+        let id = state.place_piece(0, wyvern.clone(), Square::new(3, 2)).unwrap();
+        assert_eq!(state.pieces[&id].move_stack, state.initial_move_stack(8));
+        assert_eq!(state.pieces[&id].stun, 8);
+        
+        // 포켓 제한에도 반영
+        let over = vec![PieceSpec::new(wyvern); 5];
+        assert_eq!(state.setup_pocket(1, over), Err(GameError::PocketScoreExceeded { total: 40, limit: MAX_POCKET_SCORE }));
+    }
+    
+    #[test]
+    fn test_rook_slides_on_10x10_board() {
+        let mut state = GameState::with_size(10, 10, 0);
+        assert!(state.get_piece_at(Square::new(5, 0)).is_some_and(|k| k.kind == PieceKind::King));
+        assert!(state.get_piece_at(Square::new(5, 9)).is_some_and(|k| k.kind == PieceKind::King));
+        assert!(state.is_promotion_square(&PieceKind::Pawn, Square::new(0, 9), 0));
+        
+        let rook = put_piece(&mut state, PieceKind::Rook, 0, Square::new(0, 2));
+        let moves = targets(&state.get_legal_moves(&rook));
+        assert!(moves.contains(&Square::new(9, 2)));
+        assert!(moves.contains(&Square::new(0, 9)));
+        assert!(!moves.contains(&Square::new(10, 2)));
+        assert_eq!(moves.len(), 9 + 7 + 2);
     }
     
     #[test]
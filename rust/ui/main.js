@@ -41,14 +41,44 @@ async function main() {
 function render() {
     renderBoard();
     renderPockets();
+    renderPreview();
+    renderPromotion();
     updateTurnIndicator();
 }
 
+// 선택한 포켓 기물의 행마를 빈 보드(중앙 기준 ±7칸) 위에 표시
+function renderPreview() {
+    const preview = document.getElementById('previewBoard');
+    preview.innerHTML = '';
+
+    const offsets = selectedPocket ? game.piece_preview(selectedPocket.kind, selectedPocket.owner) : null;
+
+    for (let dy = 7; dy >= -7; dy--) {
+        for (let dx = -7; dx <= 7; dx++) {
+            const cell = document.createElement('div');
+            cell.className = `preview-cell ${(dx + dy) % 2 === 0 ? 'dark' : 'light'}`;
+            if (dx === 0 && dy === 0) {
+                cell.classList.add('origin');
+            } else if (offsets && offsets.some(o => o.dx === dx && o.dy === dy)) {
+                cell.textContent = '●';
+            }
+            preview.appendChild(cell);
+        }
+    }
+
+    if (selectedPocket && !offsets) {
+        preview.title = '보드 상태에 따라 달라지는 행마입니다';
+    } else {
+        preview.title = '';
+    }
+}
+
 function renderBoard() {
     const board = document.getElementById('board');
     board.innerHTML = '';
 
     const state = game.get_state();
+    const moveCounts = game.move_counts();
 
     // y=7이 위 (흑 진영), y=0이 아래 (백 진영)
     for (let y = 7; y >= 0; y--) {
@@ -64,6 +94,11 @@ function renderBoard() {
                 square.classList.add('selected');
             }
 
+            // 체크 중인 로얄 피스 강조
+            if (state.checked_royals.some(sq => sq.x === x && sq.y === y)) {
+                square.classList.add('checked-royal');
+            }
+
             // 이동 가능한 칸 표시
             const legalMove = legalMoves.find(m => m.to_x === x && m.to_y === y);
             if (legalMove) {
@@ -126,6 +161,15 @@ function renderBoard() {
                     moveEl.textContent = piece.move_stack;
                     square.appendChild(moveEl);
                 }
+
+                // 이동 가능 수 배지 (현재 플레이어 기물만)
+                const notation = String.fromCharCode(97 + x) + (y + 1);
+                if (notation in moveCounts) {
+                    const countEl = document.createElement('div');
+                    countEl.className = 'move-count-badge';
+                    countEl.textContent = moveCounts[notation];
+                    square.appendChild(countEl);
+                }
             }
 
             square.addEventListener('click', () => onSquareClick(x, y));
@@ -158,8 +202,15 @@ function onSquareClick(x, y) {
         if (success) {
             console.log(`Moved from (${selectedSquare.x}, ${selectedSquare.y}) to (${x}, ${y})`);
         }
-        selectedSquare = null;
-        legalMoves = [];
+        // 이동 스택이 남아 있으면 이동한 기물을 계속 선택
+        const continuation = success ? game.continuation_moves() : [];
+        if (continuation.length > 0) {
+            selectedSquare = { x: continuation[0].from_x, y: continuation[0].from_y };
+            legalMoves = continuation;
+        } else {
+            selectedSquare = null;
+            legalMoves = [];
+        }
         render();
         checkGameOver();
         return;
@@ -237,12 +288,39 @@ function updateTurnIndicator() {
 function checkGameOver() {
     if (game.is_game_over()) {
         const winner = game.winner();
-        alert(winner === 1 ? '백 승리!' : '흑 승리!');
+        alert(winner === 3 ? '무승부!' : winner === 1 ? '백 승리!' : '흑 승리!');
+    }
+}
+
+// 프로모션 대기 중이면 고를 수 있는 기물 버튼 표시 (고를 때까지 대기 상태 유지)
+function renderPromotion() {
+    const choices = game.promotion_choices();
+    const panel = document.getElementById('promotionPanel');
+    const container = document.getElementById('promotionChoices');
+    container.innerHTML = '';
+    panel.hidden = choices.length === 0;
+    const color = game.current_player() === 0 ? 'white' : 'black';
+    for (const kind of choices) {
+        const el = document.createElement('div');
+        el.className = 'pocket-piece';
+        const symbols = pieceSymbols[kind];
+        el.textContent = symbols ? symbols[color] : '?';
+        el.title = kind;
+        el.addEventListener('click', () => {
+            if (game.promote(kind)) {
+                render();
+            }
+        });
+        container.appendChild(el);
     }
 }
 
 function endTurn() {
-    game.end_turn();
+    if (!game.end_turn()) {
+        alert('프로모션할 기물을 먼저 선택해야 합니다');
+        render();
+        return;
+    }
     selectedSquare = null;
     legalMoves = [];
     selectedPocket = null;
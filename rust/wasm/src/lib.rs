@@ -1,11 +1,13 @@
 use wasm_bindgen::prelude::*;
+use std::collections::BTreeMap;
 use serde::{Serialize, Deserialize};
-use engine::{GameState, Square, PieceKind, Action, PlayerId, GameResult};
+use engine::{GameState, Square, PieceKind, Action, PlayerId, GameResult, LegalMove, MoveType, TraceStep};
 
 /// JS에서 사용할 게임 래퍼
 #[wasm_bindgen]
 pub struct Game {
     state: GameState,
+    last_trace: Vec<TraceStep>, // 마지막 get_legal_moves의 스크립트 실행 추적 (디버그 모드)
 }
 
 /// JS로 전달할 기물 정보
@@ -19,6 +21,7 @@ pub struct JsPiece {
     pub stun_stack: i32,
     pub move_stack: i32,
     pub is_royal: bool,
+    pub score: i32, // 보이는 점수 (위장한 로얄은 위장 기물 점수)
 }
 
 /// JS로 전달할 이동 정보
@@ -30,6 +33,41 @@ pub struct JsMove {
     pub to_y: i32,
     pub is_capture: bool,
     pub move_type: String, // "TakeMove", "Move", "Take", "Catch", "Shift", "Jump"
+    pub gives_check: bool,
+    pub catch_to: Option<JsSquare>, // Jump가 잡는 칸
+}
+
+/// JS로 전달할 칸 좌표
+#[derive(Serialize, Deserialize)]
+pub struct JsSquare {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// JS로 전달할 행마 미리보기 오프셋
+#[derive(Serialize, Deserialize)]
+pub struct JsOffset {
+    pub dx: i32,
+    pub dy: i32,
+    pub move_type: String,
+}
+
+/// JS로 전달할 기물 카탈로그 항목
+#[derive(Serialize, Deserialize)]
+pub struct JsPieceCatalogEntry {
+    pub name: String,
+    pub score: i32,
+    pub description: String,
+}
+
+/// JS로 전달할 스크립트 실행 추적 항목
+#[derive(Serialize, Deserialize)]
+pub struct JsTraceStep {
+    pub pc: usize,
+    pub token: String,
+    pub anchor_x: i32,
+    pub anchor_y: i32,
+    pub last_value: bool,
 }
 
 /// JS로 전달할 게임 상태
@@ -41,6 +79,20 @@ pub struct JsGameState {
     pub black_pocket: Vec<String>,
     pub is_game_over: bool,
     pub winner: Option<u8>,
+    pub white_piece_count: usize,
+    pub black_piece_count: usize,
+    pub white_captured_value: i32,
+    pub black_captured_value: i32,
+    pub active_move_budget: Option<i32>,
+    pub checked_royals: Vec<JsSquare>, // 체크 중인 로얄 피스 위치 (양쪽 모두)
+    pub pending_promotion: Option<JsSquare>, // 프로모션을 기다리는 기물 위치
+    pub turn_exhausted: bool, // 활성 기물이 더 움직일 수 없음 (턴 종료만 가능)
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[wasm_bindgen]
@@ -50,6 +102,7 @@ impl Game {
     pub fn new() -> Game {
         Game {
             state: GameState::new_default(),
+            last_trace: Vec::new(),
         }
     }
     
@@ -57,12 +110,38 @@ impl Game {
     #[wasm_bindgen]
     pub fn setup_initial(&mut self) {
         self.state.setup_initial_position();
+        self.reset_history();
     }
     
     /// 실험용 포켓으로 게임 시작
     #[wasm_bindgen]
     pub fn setup_experimental(&mut self) {
         self.state.setup_experimental_pocket();
+        self.reset_history();
+    }
+    
+    /// 한 수 되돌리기 (되돌릴 수가 없으면 false)
+    #[wasm_bindgen]
+    pub fn undo(&mut self) -> bool {
+        self.state.undo().is_ok()
+    }
+    
+    /// 되돌린 수 다시 적용 (다시 적용할 수가 없으면 false)
+    #[wasm_bindgen]
+    pub fn redo(&mut self) -> bool {
+        self.state.redo().is_ok()
+    }
+    
+    /// 현재 수 번호 (시작 상태는 0)
+    #[wasm_bindgen]
+    pub fn current_ply(&self) -> usize {
+        self.state.undo_depth()
+    }
+    
+    /// 기록된 전체 수 (되돌린 수 포함)
+    #[wasm_bindgen]
+    pub fn total_plies(&self) -> usize {
+        self.state.undo_depth() + self.state.redo_depth()
     }
     
     /// 디버그 모드 설정 (Chessembly 실행 추적)
@@ -71,6 +150,42 @@ impl Game {
         self.state.debug_mode = enabled;
     }
     
+    /// 활성 기물의 이동 스택이 떨어지면 턴을 자동으로 넘길지 설정
+    #[wasm_bindgen]
+    pub fn set_auto_end_turn(&mut self, enabled: bool) {
+        self.state.auto_end_turn = enabled;
+    }
+    
+    /// 포켓 점수 제한 설정 (드래프트 전에 지정, 이미 채운 포켓은 다시 검사하지 않음)
+    #[wasm_bindgen]
+    pub fn set_max_pocket_score(&mut self, limit: i32) {
+        self.state.rules.max_pocket_score = limit;
+    }
+    
+    /// 포켓 점수 제한
+    #[wasm_bindgen]
+    pub fn max_pocket_score(&self) -> i32 {
+        self.state.rules.max_pocket_score
+    }
+    
+    /// 전역 상태 값 설정 (`if-state`/`set-state` 스크립트용)
+    #[wasm_bindgen]
+    pub fn set_global(&mut self, key: &str, value: i32) {
+        self.state.set_global(key, value);
+    }
+    
+    /// 전역 상태 값 (없으면 0)
+    #[wasm_bindgen]
+    pub fn get_global(&self, key: &str) -> i32 {
+        self.state.get_global(key)
+    }
+    
+    /// 버그 리포트용 상태 덤프 (국면, 턴 진행 상태, 전역 상태, 보드 크기와 규칙 설정, 사용자 정의 기물, 행마 스크립트)
+    #[wasm_bindgen]
+    pub fn debug_dump(&self) -> String {
+        self.state.to_debug_dump()
+    }
+    
     /// 현재 게임 상태를 JSON으로 반환
     #[wasm_bindgen]
     pub fn get_state(&self) -> JsValue {
@@ -80,31 +195,92 @@ impl Game {
     
     /// 특정 칸의 기물이 갈 수 있는 칸 목록
     #[wasm_bindgen]
-    pub fn get_legal_moves(&self, x: i32, y: i32) -> JsValue {
-        let square = Square::new(x, y);
-        let moves = self.state.get_legal_moves_at(square);
-        
-        let js_moves: Vec<JsMove> = moves.iter().map(|m| {
-            let move_type_str = match m.move_type {
-                engine::MoveType::TakeMove => "TakeMove",
-                engine::MoveType::Move => "Move",
-                engine::MoveType::Take => "Take",
-                engine::MoveType::Catch => "Catch",
-                engine::MoveType::Shift => "Shift",
-                engine::MoveType::Jump => "Jump",
-            };
-            
-            JsMove {
-                from_x: m.from.x,
-                from_y: m.from.y,
-                to_x: m.to.x,
-                to_y: m.to.y,
-                is_capture: m.is_capture,
-                move_type: move_type_str.to_string(),
-            }
-        }).collect();
-        
-        serde_wasm_bindgen::to_value(&js_moves).unwrap()
+    pub fn get_legal_moves(&mut self, x: i32, y: i32) -> JsValue {
+        let moves = self.compute_legal_moves(Square::new(x, y));
+        serde_wasm_bindgen::to_value(&self.moves_to_js(&moves)).unwrap()
+    }
+    
+    /// 마지막 get_legal_moves 호출의 스크립트 실행 추적 (디버그 모드가 아니면 빈 배열)
+    #[wasm_bindgen]
+    pub fn get_last_trace(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.trace_to_js()).unwrap()
+    }
+    
+    /// 특정 칸의 기물이 갈 수 있는 도착 칸만 ([{x, y}], 호버 표시용)
+    #[wasm_bindgen]
+    pub fn legal_targets(&self, x: i32, y: i32) -> JsValue {
+        let squares: Vec<JsSquare> = self.state.legal_targets_at(Square::new(x, y)).into_iter()
+            .map(|sq| JsSquare { x: sq.x, y: sq.y })
+            .collect();
+        serde_wasm_bindgen::to_value(&squares).unwrap()
+    }
+    
+    /// 특정 칸의 기물이 갈 수 있는 칸을 이동 종류별로 묶은 객체 ({ "Move": [...], "Take": [...] })
+    #[wasm_bindgen]
+    pub fn legal_moves_grouped(&self, x: i32, y: i32) -> JsValue {
+        let grouped = self.grouped_moves_by_type(Square::new(x, y));
+        grouped.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).unwrap()
+    }
+    
+    /// 이번 턴에 이동 중인 기물의 연속 이동 가능 칸 목록 (없으면 빈 배열)
+    #[wasm_bindgen]
+    pub fn continuation_moves(&self) -> JsValue {
+        let moves = self.state.continuation_moves();
+        serde_wasm_bindgen::to_value(&self.moves_to_js(&moves)).unwrap()
+    }
+    
+    /// 칸의 기물이 위협하는 모든 칸 (빈 칸/아군 칸 포함)
+    #[wasm_bindgen]
+    pub fn attacks_of_square(&self, x: i32, y: i32) -> JsValue {
+        let squares: Vec<JsSquare> = match self.state.board.get(&Square::new(x, y)) {
+            Some(piece_id) => self.state.attacks_of(piece_id).into_iter()
+                .map(|sq| JsSquare { x: sq.x, y: sq.y })
+                .collect(),
+            None => Vec::new(),
+        };
+        serde_wasm_bindgen::to_value(&squares).unwrap()
+    }
+    
+    /// 플레이어의 로얄 피스 위치 목록
+    #[wasm_bindgen]
+    pub fn royal_squares(&self, player: u8) -> JsValue {
+        let squares: Vec<JsSquare> = self.state.royal_squares(player).into_iter()
+            .map(|sq| JsSquare { x: sq.x, y: sq.y })
+            .collect();
+        serde_wasm_bindgen::to_value(&squares).unwrap()
+    }
+    
+    /// 포켓 점수 제한 안에서 더 추가할 수 있는 기물 이름 목록
+    #[wasm_bindgen]
+    pub fn affordable_additions(&self, player: u8) -> Vec<String> {
+        self.state.affordable_additions(player).iter().map(|kind| self.kind_to_string(kind)).collect()
+    }
+    
+    /// 현재 플레이어 기물의 이동 가능 수 ("e4" 같은 칸 표기 -> 개수)
+    #[wasm_bindgen]
+    pub fn move_counts(&self) -> JsValue {
+        let counts = self.move_counts_by_square();
+        counts.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).unwrap()
+    }
+    
+    /// 모든 기물 종류의 이름/점수/설명 (드래프트 UI 팔레트용)
+    #[wasm_bindgen]
+    pub fn piece_catalog(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.catalog()).unwrap()
+    }
+    
+    /// 빈 보드 기준 행마 미리보기 (보드 상태에 의존하는 기물은 null)
+    #[wasm_bindgen]
+    pub fn piece_preview(&self, kind: &str, owner: u8) -> JsValue {
+        let piece_kind = self.parse_piece_kind(kind);
+        let offsets: Option<Vec<JsOffset>> = piece_kind.static_offsets(owner == 0).map(|offsets| {
+            offsets.into_iter().map(|(dx, dy, move_type)| JsOffset {
+                dx,
+                dy,
+                move_type: self.move_type_to_string(move_type),
+            }).collect()
+        });
+        serde_wasm_bindgen::to_value(&offsets).unwrap()
     }
     
     /// 기물 이동 실행
@@ -121,32 +297,49 @@ impl Game {
                     from, 
                     to,
                 };
-                self.state.apply_action(action);
-                return true;
+                return self.state.apply_action(action).is_ok();
             }
         }
         false
     }
     
-    /// 포켓에서 기물 배치 (간단화된 버전 - 실제 구현 필요)
+    /// 포켓에서 기물 배치
     #[wasm_bindgen]
     pub fn place_from_pocket(&mut self, kind: &str, x: i32, y: i32) -> bool {
+        let action = Action::Place { kind: self.parse_piece_kind(kind), target: Square::new(x, y) };
+        self.state.apply_action(action).is_ok()
+    }
+    
+    /// 포켓 기물을 해당 칸에 놓았을 때의 이동 미리보기 (상태 변경 없음)
+    #[wasm_bindgen]
+    pub fn preview_drop(&self, kind: &str, x: i32, y: i32) -> JsValue {
         let piece_kind = self.parse_piece_kind(kind);
-        let square = Square::new(x, y);
-
-        if self.state.can_place_from_pocket(&piece_kind, square) {
-            if self.state.place_piece(self.state.current_player(), piece_kind.clone(), square).is_ok() {
-                return true;
-            }
-        }
-
-        false
+        let moves = self.state.legal_moves_hypothetical(piece_kind, Square::new(x, y), self.state.current_player());
+        serde_wasm_bindgen::to_value(&self.moves_to_js(&moves)).unwrap()
+    }
+    
+    /// 턴 종료 (프로모션 대기 중이면 false)
+    #[wasm_bindgen]
+    pub fn end_turn(&mut self) -> bool {
+        self.state.end_turn().is_ok()
     }
     
-    /// 턴 종료
+    /// 프로모션 대기 중인 기물을 프로모션
     #[wasm_bindgen]
-    pub fn end_turn(&mut self) {
-        self.state.end_turn();
+    pub fn promote(&mut self, kind: &str) -> bool {
+        let Some(piece_id) = self.state.pending_promotion() else {
+            return false;
+        };
+        self.state.promote(&piece_id, self.parse_piece_kind(kind)).is_ok()
+    }
+    
+    /// 프로모션 대기 중인 기물이 고를 수 있는 기물 종류 (대기 중이 아니면 빈 목록)
+    #[wasm_bindgen]
+    pub fn promotion_choices(&self) -> Vec<String> {
+        self.state.pending_promotion()
+            .and_then(|id| self.state.pieces.get(&id))
+            .map(|p| p.kind.promotion_targets().iter().map(|k| self.kind_to_string(k)).collect())
+            .unwrap_or_default()
     }
     
     /// 현재 플레이어
@@ -158,21 +351,105 @@ impl Game {
     /// 게임 종료 여부
     #[wasm_bindgen]
     pub fn is_game_over(&self) -> bool {
-        !matches!(self.state.check_victory(), GameResult::Ongoing)
+        !matches!(self.state.game_status(), GameResult::Ongoing)
     }
     
-    /// 승자 (0=진행중, 1=백, 2=흑)
+    /// 승자 (0=진행중, 1=백, 2=흑, 3=무승부)
     #[wasm_bindgen]
     pub fn winner(&self) -> u8 {
-        match self.state.check_victory() {
+        match self.state.game_status() {
             GameResult::WhiteWins => 1,
             GameResult::BlackWins => 2,
+            GameResult::Draw => 3,
             GameResult::Ongoing => 0,
         }
     }
     
     // === Private helpers ===
     
+    fn reset_history(&mut self) {
+        self.state.clear_history();
+    }
+    
+    /// 칸의 기물 이동 목록 계산 (디버그 모드면 실행 추적을 저장)
+    fn compute_legal_moves(&mut self, square: Square) -> Vec<LegalMove> {
+        self.last_trace.clear();
+        let Some(piece_id) = self.state.board.get(&square).cloned() else {
+            return Vec::new();
+        };
+        if !self.state.debug_mode {
+            return self.state.get_legal_moves(&piece_id);
+        }
+        let (moves, trace) = self.state.get_legal_moves_traced(&piece_id);
+        self.last_trace = trace;
+        moves
+    }
+    
+    fn catalog(&self) -> Vec<JsPieceCatalogEntry> {
+        PieceKind::all().iter().map(|kind| JsPieceCatalogEntry {
+            name: self.kind_to_string(kind),
+            score: kind.score(),
+            description: kind.description().to_string(),
+        }).collect()
+    }
+    
+    fn trace_to_js(&self) -> Vec<JsTraceStep> {
+        self.last_trace.iter().map(|step| JsTraceStep {
+            pc: step.pc,
+            token: step.token.clone(),
+            anchor_x: step.anchor_x,
+            anchor_y: step.anchor_y,
+            last_value: step.last_value,
+        }).collect()
+    }
+    
+    fn move_counts_by_square(&self) -> BTreeMap<String, usize> {
+        self.state.move_counts(self.state.current_player()).into_iter()
+            .filter_map(|(id, count)| {
+                let pos = self.state.pieces.get(&id)?.pos?;
+                Some((pos.to_notation(), count))
+            })
+            .collect()
+    }
+    
+    fn grouped_moves_by_type(&self, square: Square) -> BTreeMap<String, Vec<JsSquare>> {
+        let Some(piece_id) = self.state.board.get(&square) else {
+            return BTreeMap::new();
+        };
+        self.state.legal_moves_grouped(piece_id).into_iter()
+            .map(|(move_type, squares)| {
+                let squares = squares.into_iter().map(|sq| JsSquare { x: sq.x, y: sq.y }).collect();
+                (self.move_type_to_string(move_type), squares)
+            })
+            .collect()
+    }
+    
+    fn moves_to_js(&self, moves: &[LegalMove]) -> Vec<JsMove> {
+        moves.iter().map(|m| {
+            JsMove {
+                from_x: m.from.x,
+                from_y: m.from.y,
+                to_x: m.to.x,
+                to_y: m.to.y,
+                is_capture: m.is_capture,
+                move_type: self.move_type_to_string(m.move_type),
+                gives_check: self.state.move_gives_check(m),
+                catch_to: m.catch_to.map(|sq| JsSquare { x: sq.x, y: sq.y }),
+            }
+        }).collect()
+    }
+    
+    fn move_type_to_string(&self, move_type: MoveType) -> String {
+        match move_type {
+            MoveType::TakeMove => "TakeMove",
+            MoveType::Move => "Move",
+            MoveType::Take => "Take",
+            MoveType::Catch => "Catch",
+            MoveType::Shift => "Shift",
+            MoveType::Jump => "Jump",
+        }.to_string()
+    }
+    
     fn build_js_state(&self) -> JsGameState {
         let pieces: Vec<JsPiece> = self.state.get_all_pieces().iter().map(|p| {
             JsPiece {
@@ -184,10 +461,11 @@ impl Game {
                 stun_stack: p.stun_stack,
                 move_stack: p.move_stack,
                 is_royal: p.is_royal,
+                score: p.score,
             }
         }).collect();
         
-        let victory = self.state.check_victory();
+        let victory = self.state.game_status();
         JsGameState {
             pieces,
             current_player: self.state.current_player(),
@@ -197,8 +475,21 @@ impl Game {
             winner: match victory {
                 GameResult::WhiteWins => Some(1),
                 GameResult::BlackWins => Some(2),
-                GameResult::Ongoing => None,
+                GameResult::Draw | GameResult::Ongoing => None,
             },
+            white_piece_count: self.state.piece_count(0),
+            black_piece_count: self.state.piece_count(1),
+            white_captured_value: self.state.captured_value(0),
+            black_captured_value: self.state.captured_value(1),
+            active_move_budget: self.state.active_move_budget(),
+            checked_royals: (0..2)
+                .flat_map(|player| self.state.checked_royals(player))
+                .map(|sq| JsSquare { x: sq.x, y: sq.y })
+                .collect(),
+            pending_promotion: self.state.pending_promotion()
+                .and_then(|id| self.state.pieces.get(&id)?.pos)
+                .map(|sq| JsSquare { x: sq.x, y: sq.y }),
+            turn_exhausted: self.state.is_turn_exhausted(),
         }
     }
     
@@ -268,3 +559,58 @@ extern "C" {
 pub fn main() {
     log("Chesstack WASM initialized!");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_piece_catalog_includes_fairy_pieces() {
+        let game = Game::new();
+        let catalog = game.catalog();
+        
+        let amazon = catalog.iter().find(|entry| entry.name == "amazon").unwrap();
+        assert_eq!(amazon.score, 13);
+        assert!(catalog.iter().any(|entry| entry.name == "experiment"));
+        assert_eq!(catalog.len(), PieceKind::all().len());
+    }
+    
+    #[test]
+    fn test_debug_trace_after_get_legal_moves() {
+        let mut game = Game::new();
+        let king = Square::new(4, 0);
+        
+        game.compute_legal_moves(king);
+        assert!(game.trace_to_js().is_empty());
+        
+        game.set_debug(true);
+        let moves = game.compute_legal_moves(king);
+        assert!(!moves.is_empty());
+        assert!(!game.trace_to_js().is_empty());
+        
+        // 새 계산마다 초기화
+        game.compute_legal_moves(Square::new(3, 3));
+        assert!(game.trace_to_js().is_empty());
+    }
+    
+    #[test]
+    fn test_ply_counters_after_undo() {
+        let mut game = Game::new();
+        assert!(game.move_piece(4, 0, 4, 1));
+        assert!(game.move_piece(4, 1, 4, 2));
+        assert!(game.undo());
+        assert_eq!(game.current_ply(), 1);
+        assert_eq!(game.total_plies(), 2);
+        assert!(game.state.get_piece_at(Square::new(4, 1)).is_some());
+        
+        assert!(game.redo());
+        assert!(!game.redo());
+        assert!(game.state.get_piece_at(Square::new(4, 2)).is_some());
+        
+        // 되돌린 뒤 새 수를 두면 이후 기록은 사라짐
+        game.undo();
+        game.undo();
+        assert!(game.end_turn());
+        assert_eq!((game.current_ply(), game.total_plies()), (1, 1));
+    }
+}